@@ -0,0 +1,17 @@
+#!/usr/bin/env bash
+# Measures the time it takes to compile the `dosio` crate
+#
+# usage: benches/build_time.sh [cargo build options]
+#
+# The `IO` enum is generated from the FEM in `FEM_REPO` if set, otherwise from the vendored manifest.
+set -e
+cd "$(dirname "$0")/.."
+cargo build --release "$@" 2>/dev/null
+for run in 1 2 3; do
+    cargo clean --release -p dosio 2>/dev/null
+    start=$(date +%s%N)
+    cargo build --release "$@" 2>/dev/null
+    stop=$(date +%s%N)
+    echo "run #${run}: $(( (stop - start) / 1000000 ))ms"
+done
+echo "libdosio.rlib: $(wc -c < target/release/libdosio.rlib) bytes"
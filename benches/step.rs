@@ -0,0 +1,53 @@
+//! Time spent by a simulation step moving data in and out of `Vec<IO>`
+//!
+//! Build time is measured with `benches/build_time.sh`
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use dosio::{io::IOKind, IOVec, IO};
+
+// One `IO` per variant
+fn outputs(n: usize) -> Vec<IO<Vec<f64>>> {
+    IOKind::ALL
+        .iter()
+        .map(|kind| kind.io(Some(vec![1f64; n])))
+        .collect()
+}
+
+fn step(c: &mut Criterion) {
+    let tags: Vec<IO<()>> = IOKind::ALL
+        .iter()
+        .rev()
+        .step_by(7)
+        .map(|kind| kind.io(None))
+        .collect();
+    c.bench_function("pop_these", |b| {
+        b.iter_batched(
+            || outputs(42),
+            |mut ios| black_box(ios.pop_these(tags.clone())),
+            criterion::BatchSize::SmallInput,
+        )
+    });
+    let ios = outputs(42);
+    c.bench_function("index", |b| {
+        b.iter(|| tags.iter().map(|tag| ios[tag].sum_sqred()).sum::<f64>())
+    });
+    c.bench_function("add_assign", |b| {
+        let mut lhs = outputs(42);
+        b.iter(|| {
+            lhs.iter_mut().zip(&ios).for_each(|(lhs, rhs)| {
+                *lhs += rhs;
+            })
+        })
+    });
+    c.bench_function("kind", |b| {
+        b.iter(|| {
+            ios.iter().for_each(|io| {
+                black_box(io.io_kind());
+            })
+        })
+    });
+    c.bench_function("clone", |b| b.iter(|| black_box(ios.clone())));
+}
+
+criterion_group!(benches, step);
+criterion_main!(benches);
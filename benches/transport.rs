@@ -0,0 +1,60 @@
+//! Round-trip latency of a remote component over TCP, Unix-domain sockets and shared memory
+//!
+//! The remote component is a gain on `N` channels, each iteration is one `in_step_out` call
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use dosio::{
+    blocks::Gain,
+    ios,
+    remote::{Client, Server},
+    Dos,
+};
+use std::{net::TcpListener, thread, time::Duration};
+
+const N: usize = 1000;
+const TIMEOUT: Option<Duration> = Some(Duration::from_secs(10));
+
+fn server() -> Server<Gain> {
+    Server::new(Gain::new(ios!(Pssn), ios!(SensorData), 2.))
+}
+
+fn round_trip(c: &mut Criterion, name: &str, mut client: Client) {
+    let u = vec![ios!(Pssn(vec![1f64; N]))];
+    c.bench_function(name, |b| {
+        b.iter(|| black_box(client.in_step_out(Some(u.clone())).unwrap()))
+    });
+}
+
+fn transport(c: &mut Criterion) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let tcp = thread::spawn(move || server().serve_tcp(&listener));
+    round_trip(c, "tcp", Client::connect_tcp(addr, TIMEOUT).unwrap());
+    tcp.join().unwrap().unwrap();
+
+    #[cfg(unix)]
+    {
+        let path = std::env::temp_dir().join(format!("dosio-bench-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let unix = thread::spawn(move || server().serve_unix(&listener));
+        round_trip(c, "unix", Client::connect_unix(&path, TIMEOUT).unwrap());
+        unix.join().unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    let path = std::env::temp_dir().join(format!("dosio-bench-{}.shm", std::process::id()));
+    let server_path = path.clone();
+    let shm = thread::spawn(move || {
+        server().serve_shm(
+            server_path,
+            vec![(ios!(Pssn), N)],
+            vec![(ios!(SensorData), N)],
+        )
+    });
+    round_trip(c, "shm", Client::connect_shm(&path, TIMEOUT).unwrap());
+    shm.join().unwrap().unwrap();
+}
+
+criterion_group!(benches, transport);
+criterion_main!(benches);
@@ -0,0 +1,37 @@
+use std::{env, path::Path};
+
+// Registers the FEM model as an input of the build, so the `IO` enum is
+// regenerated whenever `FEM_REPO` or the model files are changed
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=Cargo.toml");
+    println!("cargo:rerun-if-env-changed=FEM_REPO");
+    if let Ok(fem_repo) = env::var("FEM_REPO") {
+        let fem_repo = Path::new(&fem_repo);
+        let models: Vec<_> = [
+            "modal_state_space_model_2ndOrder.zip",
+            "modal_state_space_model_2ndOrder.rs.mat",
+        ]
+        .iter()
+        .map(|model| fem_repo.join(model))
+        .filter(|model| model.exists())
+        .collect();
+        if models.is_empty() {
+            println!("cargo:rerun-if-changed={}", fem_repo.display());
+        }
+        models
+            .iter()
+            .for_each(|model| println!("cargo:rerun-if-changed={}", model.display()));
+    }
+    #[cfg(feature = "fmi")]
+    fmi_logger();
+}
+
+// Compiles the variadic FMI 2.0 logger of the FMUs loaded by `fmi::Fmu`
+#[cfg(feature = "fmi")]
+fn fmi_logger() {
+    println!("cargo:rerun-if-changed=src/fmi/logger.c");
+    cc::Build::new()
+        .file("src/fmi/logger.c")
+        .compile("dosio_fmi_logger");
+}
@@ -0,0 +1,61 @@
+//! Writes the inputs/outputs manifest of a FEM model to the standard output
+//!
+//! The FEM repository is given either as the first argument or by the `FEM_REPO` environment variable.
+
+#[path = "../fem.rs"]
+#[allow(dead_code)]
+mod fem;
+#[cfg(feature = "hdf5")]
+#[path = "../hdf5_io.rs"]
+mod hdf5_io;
+#[cfg(feature = "prqt")]
+#[path = "../parquet_io.rs"]
+mod parquet_io;
+
+use fem::FemIo;
+use std::{
+    env,
+    io::{self, Write},
+    path::Path,
+    process,
+};
+
+// Writes the manifest
+fn write<W: Write>(mut writer: W, source: &Path, fem_io: &[FemIo]) -> io::Result<()> {
+    writeln!(writer, "# GMT FEM inputs/outputs manifest")?;
+    writeln!(writer, "# generated from {}", source.display())?;
+    writeln!(writer, "# <in|out> <group> <size>")?;
+    for io in fem_io {
+        writeln!(writer, "{} {} {}", io.kind, io.group, io.size)?;
+    }
+    // the aliases of the renamed variants are kept from the vendored manifest
+    let aliases = fem::aliases(fem::MANIFEST).unwrap_or_default();
+    if !aliases.is_empty() {
+        writeln!(writer, "# alias <old variant> <new variant>")?;
+    }
+    for (old, new) in aliases {
+        writeln!(writer, "alias {} {}", old, new)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let fem_repo = match env::args().nth(1).or_else(|| env::var("FEM_REPO").ok()) {
+        Some(val) => val,
+        None => {
+            eprintln!("usage: fem-manifest <FEM_REPO>");
+            process::exit(1);
+        }
+    };
+    let (source, fem_io) = match fem::from_repo(Path::new(&fem_repo)) {
+        Ok(val) => val,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    if let Err(e) = write(io::stdout().lock(), &source, &fem_io) {
+        eprintln!("Failed to write the manifest: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,215 @@
+//! `IOTags` and `Dos` derive macros
+//!
+//! The inputs and outputs are the fields annotated with `#[dos(input = <variant>)]` or `#[dos(output = <variant>)]`.
+//! The fields must be of type `Option<T>`, `T` being the same type for all the inputs and for all the outputs.
+//! The variants are resolved as the marker types of `dosio::io::jar`, so an unknown variant is a compile error at its name
+//! and the former names of renamed variants are accepted with a deprecation warning.
+
+use proc_macro::TokenStream;
+use proc_macro2::Ident;
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Fields, GenericArgument,
+    PathArguments, Type,
+};
+
+// Input or output field
+struct DosField {
+    field: Ident,
+    ty: Type,
+    variant: Ident,
+}
+
+// Inputs and outputs fields
+#[derive(Default)]
+struct DosFields {
+    inputs: Vec<DosField>,
+    outputs: Vec<DosField>,
+}
+
+// `IOKind` of the variant, resolved through the `jar` marker types so the aliases are deprecated where they are used
+fn kind(variant: &Ident) -> proc_macro2::TokenStream {
+    quote_spanned!(variant.span()=> <::dosio::io::jar::#variant as ::dosio::typed::UniqueIdentifier>::KIND)
+}
+
+// Returns `T` from `Option<T>`
+fn option_type(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => {
+            let segment = path.path.segments.last()?;
+            match &segment.arguments {
+                PathArguments::AngleBracketed(args) if segment.ident == "Option" => {
+                    match args.args.first()? {
+                        GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// Parses the `dos` attributes of the fields
+fn parse(input: &DeriveInput) -> Result<DosFields, Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.ident.span(),
+                    "dos derive macros require a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.ident.span(),
+                "dos derive macros require a struct with named fields",
+            ))
+        }
+    };
+    let mut dos_fields = DosFields::default();
+    for field in fields {
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("dos"))
+        {
+            attr.parse_nested_meta(|meta| {
+                let is_input = if meta.path.is_ident("input") {
+                    true
+                } else if meta.path.is_ident("output") {
+                    false
+                } else {
+                    return Err(meta.error("expected `input` or `output`"));
+                };
+                let variant: Ident = meta.value()?.parse()?;
+                let (fields, name) = if is_input {
+                    (&dos_fields.inputs, "input")
+                } else {
+                    (&dos_fields.outputs, "output")
+                };
+                if fields.iter().any(|field| field.variant == variant) {
+                    return Err(Error::new(
+                        variant.span(),
+                        format!("`{}` is already an {}", variant, name),
+                    ));
+                }
+                let ty = option_type(&field.ty)
+                    .ok_or_else(|| {
+                        Error::new(field.ty.span(), "dos fields must be of type `Option<T>`")
+                    })?
+                    .clone();
+                let dos_field = DosField {
+                    field: field.ident.clone().expect("named field"),
+                    ty,
+                    variant,
+                };
+                if is_input {
+                    dos_fields.inputs.push(dos_field);
+                } else {
+                    dos_fields.outputs.push(dos_field);
+                }
+                Ok(())
+            })?;
+        }
+    }
+    Ok(dos_fields)
+}
+
+pub fn derive_io_tags(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let fields = match parse(&input) {
+        Ok(fields) => fields,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let inputs = fields.inputs.iter().map(|field| kind(&field.variant));
+    let outputs = fields.outputs.iter().map(|field| kind(&field.variant));
+    quote!(
+        impl #impl_generics ::dosio::IOTags for #name #ty_generics #where_clause {
+            fn outputs_tags(&self) -> Vec<::dosio::IO<()>> {
+                vec![#(#outputs.io(None)),*]
+            }
+            fn inputs_tags(&self) -> Vec<::dosio::IO<()>> {
+                vec![#(#inputs.io(None)),*]
+            }
+        }
+    )
+    .into()
+}
+
+// Returns the type shared by all the `fields`, `()` if there is none
+fn same_type(fields: &[DosField], name: &str) -> Result<proc_macro2::TokenStream, Error> {
+    let mut types = fields.iter().map(|field| &field.ty);
+    let ty = match types.next() {
+        Some(ty) => ty.to_token_stream(),
+        None => return Ok(quote!(())),
+    };
+    match types.find(|other| other.to_token_stream().to_string() != ty.to_string()) {
+        Some(other) => Err(Error::new(
+            other.span(),
+            format!("all the {}s must have the same type", name),
+        )),
+        None => Ok(ty),
+    }
+}
+
+pub fn derive_dos(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let fields = match parse(&input) {
+        Ok(fields) => fields,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let (input_type, output_type) = match (
+        same_type(&fields.inputs, "input"),
+        same_type(&fields.outputs, "output"),
+    ) {
+        (Ok(input_type), Ok(output_type)) => (input_type, output_type),
+        (Err(e), _) | (_, Err(e)) => return e.to_compile_error().into(),
+    };
+    let (input_fields, input_variants): (Vec<_>, Vec<_>) = fields
+        .inputs
+        .iter()
+        .map(|field| (&field.field, kind(&field.variant)))
+        .unzip();
+    let (output_fields, output_variants): (Vec<_>, Vec<_>) = fields
+        .outputs
+        .iter()
+        .map(|field| (&field.field, kind(&field.variant)))
+        .unzip();
+    quote!(
+        impl #impl_generics ::dosio::Dos for #name #ty_generics #where_clause {
+            type Input = #input_type;
+            type Output = #output_type;
+            fn outputs(&mut self) -> Option<Vec<::dosio::IO<Self::Output>>> {
+                Some(vec![#(#output_variants.io(self.#output_fields.clone())),*])
+            }
+            #[allow(unused_mut, unused_variables)]
+            fn inputs(
+                &mut self,
+                data: Option<Vec<::dosio::IO<Self::Input>>>,
+            ) -> Result<&mut Self, ::dosio::DOSIOSError> {
+                let mut data = data.unwrap_or_default();
+                #(
+                    let kind = #input_variants;
+                    let idx = data
+                        .iter()
+                        .position(|io| io.io_kind() == kind)
+                        .ok_or_else(|| {
+                            ::dosio::DOSIOSError::Inputs(format!("{} input is missing", kind).into())
+                                .with_io(&kind.io::<()>(None))
+                        })?;
+                    self.#input_fields = data.swap_remove(idx).into_parts().1;
+                )*
+                Ok(self)
+            }
+        }
+    )
+    .into()
+}
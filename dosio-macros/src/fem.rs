@@ -0,0 +1,45 @@
+//! FEM inputs/outputs
+//!
+//! The inputs and outputs are read from the vendored manifest, parsed by the `manifest` module,
+//! or from the FEM model of a FEM repository.
+
+#[path = "manifest.rs"]
+mod manifest;
+
+// `fem.rs` is also included by the `fem-manifest` binary and the tests, which use part of it only
+#[allow(unused_imports)]
+pub use manifest::{aliases, parse, FemIo, Kind};
+use std::path::{Path, PathBuf};
+
+/// Vendored manifest of the GMT FEM inputs and outputs
+pub const MANIFEST: &str = include_str!("../fem-io.manifest");
+/// Path to the vendored manifest
+pub const MANIFEST_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fem-io.manifest");
+
+/// Reads the inputs and outputs of the FEM model in the `fem_repo` directory
+///
+/// Returns the path to the FEM model file and the model inputs and outputs
+#[cfg(any(feature = "hdf5", feature = "prqt"))]
+pub fn from_repo(fem_repo: &Path) -> Result<(PathBuf, Vec<FemIo>), String> {
+    #[cfg(feature = "hdf5")]
+    use crate::hdf5_io::{fem_io, FEM_MODEL};
+    #[cfg(feature = "prqt")]
+    use crate::parquet_io::{fem_io, FEM_MODEL};
+    let path = fem_repo.join(FEM_MODEL);
+    if !path.is_file() {
+        return Err(format!("Cannot find `{}` in `FEM_REPO`", FEM_MODEL));
+    }
+    fem_io(&path)
+        .map(|fem_io| (path, fem_io))
+        .map_err(|e| format!("Cannot read `{}` in `FEM_REPO`: {}", FEM_MODEL, e))
+}
+/// Reads the inputs and outputs of the FEM model in the `fem_repo` directory
+///
+/// Returns the path to the FEM model file and the model inputs and outputs
+#[cfg(not(any(feature = "hdf5", feature = "prqt")))]
+pub fn from_repo(_fem_repo: &Path) -> Result<(PathBuf, Vec<FemIo>), String> {
+    Err(
+        "Reading `FEM_REPO` requires either the `hdf5` or the `prqt` feature of `dosio-macros`"
+            .into(),
+    )
+}
@@ -1,5 +1,160 @@
-use proc_macro2::Ident;
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span};
 use quote::quote;
+use std::{
+    env,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+use syn::{parse::Parser, punctuated::Punctuated, Token};
+
+use crate::fem::{self, MANIFEST, MANIFEST_PATH};
+
+// `<old variant> => <new variant>` argument of `ad_hoc!`
+struct Alias {
+    old: Ident,
+    new: Ident,
+}
+impl syn::parse::Parse for Alias {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let old = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let new = input.parse()?;
+        Ok(Self { old, new })
+    }
+}
+
+pub fn ad_hoc_macro(_item: TokenStream) -> TokenStream {
+    let arguments = match Punctuated::<Alias, Token![,]>::parse_terminated.parse(_item) {
+        Ok(arguments) => arguments,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    if let Ok(fem_repo) = env::var("FEM_REPO") {
+        println!(
+            "Building `dosio::IO` enum to match inputs/outputs of FEM in {}",
+            fem_repo
+        );
+    } else {
+        println!(
+            "`FEM_REPO` environment variable is not set, using the vendored manifest instead."
+        );
+    }
+    let extra = arguments
+        .into_iter()
+        .map(|alias| (alias.old.to_string(), alias.new.to_string()));
+    let Variants {
+        source,
+        variants,
+        aliases,
+    } = match variants(extra) {
+        Ok(val) => val,
+        Err(msg) => return quote!(compile_error!(#msg);).into(),
+    };
+    let fingerprint = build_fingerprint(&source, variants.len());
+    let io = build_io(variants, aliases);
+    let shared = shared_items();
+
+    quote!(
+    #io
+    #fingerprint
+    #shared
+    )
+    .into()
+}
+
+/// `IO` variants and aliases of the renamed variants
+pub struct Variants {
+    /// FEM model file or manifest the variants are read from
+    pub source: PathBuf,
+    /// Sorted variants, with the size of their FEM group, if any
+    pub variants: Vec<(Ident, Option<usize>)>,
+    /// Sorted aliases, as `(old variant, new variant)` pairs
+    pub aliases: Vec<(Ident, Ident)>,
+}
+
+/// Returns the `IO` variants and the aliases of the renamed variants
+///
+/// The FEM inputs and outputs are read from the FEM model in `FEM_REPO` or, if `FEM_REPO` is not set, from the vendored manifest.
+/// The aliases are read from the vendored manifest and `extra` aliases are appended,
+/// a FEM group named after the old name of an alias is the new variant.
+/// An alias applies if its old name is not a variant and its new name is a variant:
+/// the aliases that do not apply to the FEM in `FEM_REPO` are skipped, they are errors in the vendored manifest.
+pub fn variants<I>(extra: I) -> Result<Variants, String>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let fem_repo = env::var("FEM_REPO").ok();
+    let (source, fem_io) = if let Some(fem_repo) = &fem_repo {
+        fem::from_repo(Path::new(fem_repo))?
+    } else {
+        (
+            PathBuf::from(MANIFEST_PATH),
+            fem::parse(MANIFEST).map_err(|e| format!("Invalid vendored manifest: {}", e))?,
+        )
+    };
+
+    let mut aliases =
+        fem::aliases(MANIFEST).map_err(|e| format!("Invalid vendored manifest: {}", e))?;
+    aliases.extend(extra);
+    aliases.sort();
+    if let Some(pair) = aliases.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        return Err(format!("the alias `{}` is defined twice", pair[0].0));
+    }
+
+    let mut variants: Vec<(String, Option<usize>)> = fem_io
+        .iter()
+        .map(|io| {
+            let variant = io.variant();
+            let variant = aliases
+                .iter()
+                .find(|(old, _)| *old == variant)
+                .map_or(variant, |(_, new)| new.clone());
+            (variant, Some(io.size))
+        })
+        .collect();
+    variants.extend(io_list().map(|&v| (v.to_string(), None)));
+    // the sort is stable so the FEM variants, with a size, are kept
+    variants.sort_by(|(a, _), (b, _)| a.cmp(b));
+    variants.dedup_by(|(a, _), (b, _)| a == b);
+
+    let is_variant = |name: &str| {
+        variants
+            .binary_search_by(|(variant, _)| variant.as_str().cmp(name))
+            .is_ok()
+    };
+    let mut applied = vec![];
+    for (old, new) in aliases {
+        let error = if is_variant(&old) {
+            format!("the alias `{}` is a `IO` variant", old)
+        } else if !is_variant(&new) {
+            format!(
+                "the alias `{}` renames `{}` which is not a `IO` variant",
+                old, new
+            )
+        } else {
+            applied.push((old, new));
+            continue;
+        };
+        if fem_repo.is_none() {
+            return Err(error);
+        }
+        println!("skipping {}", error);
+    }
+
+    let ident = |name: &str| Ident::new(name, Span::call_site());
+    Ok(Variants {
+        source,
+        variants: variants
+            .iter()
+            .map(|(variant, size)| (ident(variant), *size))
+            .collect(),
+        aliases: applied
+            .iter()
+            .map(|(old, new)| (ident(old), ident(new)))
+            .collect(),
+    })
+}
 
 pub fn io_list() -> impl Iterator<Item = &'static &'static str> {
     [
@@ -26,7 +181,7 @@ pub fn io_list() -> impl Iterator<Item = &'static &'static str> {
         //  - hardpoints load cells
         "M1HPLC",
         "OSSHardpointD",
-        "OSSHarpointDeltaF",
+        "OSSHardpointDeltaF",
         "M1HPCmd",
         // - hardpoints dynamics
         "HPFcmd",
@@ -88,227 +243,144 @@ pub fn io_list() -> impl Iterator<Item = &'static &'static str> {
     .iter()
 }
 
+// Items compiled in this crate and emitted by `ad_hoc!` in `dosio::io`,
+// so the fingerprint and the wire ids are computed by the same implementation on both sides
+macro_rules! shared {
+    ($($item:tt)*) => {
+        $($item)*
+        fn shared_items() -> proc_macro2::TokenStream {
+            quote!($($item)*)
+        }
+    };
+}
+shared! {
+    // FNV-1a 64 bits hash of the content of `reader`
+    pub(crate) fn fnv1a<R: std::io::Read>(mut reader: R) -> std::io::Result<u64> {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        let mut buffer = [0u8; 8192];
+        loop {
+            let n = reader.read(&mut buffer)?;
+            if n == 0 {
+                break Ok(hash);
+            }
+            buffer[..n].iter().for_each(|&byte| {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            });
+        }
+    }
+}
+
+// Build the fingerprint of the model the enum is derived from
+pub fn build_fingerprint(source: &Path, n_variant: usize) -> proc_macro2::TokenStream {
+    let hash = match File::open(source).and_then(|file| fnv1a(BufReader::new(file))) {
+        Ok(hash) => hash,
+        Err(e) => {
+            let msg = format!("Cannot hash {}: {}", source.display(), e);
+            return quote!(compile_error!(#msg););
+        }
+    };
+    let path = source.to_string_lossy().into_owned();
+    quote!(
+        /// Fingerprint of the model the [`IO`] enum has been generated from
+        pub const FINGERPRINT: Fingerprint = Fingerprint {
+            path: #path,
+            hash: #hash,
+            n_variant: #n_variant,
+        };
+    )
+}
+
 // Build the enum
-pub fn build_io(variant: Vec<Ident>) -> proc_macro2::TokenStream {
+//
+// Only the code that depends on the variants is generated here,
+// the rest of the `IO` API is written once over `IOKind` in `dosio::io`
+pub fn build_io(
+    variants: Vec<(Ident, Option<usize>)>,
+    aliases: Vec<(Ident, Ident)>,
+) -> proc_macro2::TokenStream {
+    let n_variant = variants.len();
+    let n_alias = aliases.len();
+    let old: Vec<_> = aliases.iter().map(|(old, _)| old).collect();
+    let new: Vec<_> = aliases.iter().map(|(_, new)| new).collect();
+    let note: Vec<_> = aliases
+        .iter()
+        .map(|(old, new)| format!("`{}` has been renamed `{}`", old, new))
+        .collect();
+    let size: Vec<_> = variants
+        .iter()
+        .map(|(_, size)| match size {
+            Some(size) => quote!(Some(#size)),
+            None => quote!(None),
+        })
+        .collect();
+    let variant: Vec<_> = variants.into_iter().map(|(variant, _)| variant).collect();
     quote!(
         /// Inputs/Outputs definition
-        #[derive(Debug,Clone,Serialize,Deserialize)]
         pub enum IO<T> {
             #(#variant{data: Option<T>}),*
         }
-        impl IO<usize> {
-            /// Assign `n` to `IO` `data`
-            pub fn assign(&mut self, n: usize) {
-                match self {
-                    #(IO::#variant{ data: values} => {*values=Some(n);}),*
-                }
-            }
+        /// Inputs/Outputs variants
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        #[repr(u16)]
+        pub enum IOKind {
+            #(#variant),*
         }
-        impl IO<Vec<f64>> {
-            /// Compute `io` sum squared
-            pub fn sum_sqred(&self) -> f64 {
-                match self {
-            #(IO::#variant{ data: None} => f64::NAN,)*
-                    #(IO::#variant{ data: Some(values)} => values.iter().map(|x: &f64| x * x).sum::<f64>()),*
-                }
-            }
-            /// Compute `io` mean sum squared
-            pub fn mean_sum_sqred(&self) -> f64 {
+        impl IOKind {
+            /// All the variants in alphabetical order
+            pub const ALL: [IOKind; #n_variant] = [#(IOKind::#variant),*];
+            /// All the variant names in alphabetical order
+            pub const NAMES: [&'static str; #n_variant] = [#(stringify!(#variant)),*];
+            /// The former names of the renamed variants in alphabetical order, with their new variant
+            pub const ALIASES: [(&'static str, IOKind); #n_alias] = [#((stringify!(#old), IOKind::#new)),*];
+            /// Creates a new `IO` of this kind with `data`
+            pub fn io<T>(self, data: Option<T>) -> IO<T> {
                 match self {
-            #(IO::#variant{ data: None} => f64::NAN,)*
-                    #(IO::#variant{ data: Some(values)} => (values.iter().map(|x: &f64| x * x).sum::<f64>()*(values.len() as f64).recip())),*
+                    #(IOKind::#variant => IO::#variant{ data }),*
                 }
             }
-            /// Compute the mean
-            pub fn mean(&self) -> f64 {
+        }
+        impl<T> IO<T> {
+            /// Returns the `IO` variant
+            pub fn io_kind(&self) -> IOKind {
                 match self {
-            #(IO::#variant{ data: None} => f64::NAN,)*
-                    #(IO::#variant{ data: Some(values)} => (values.iter().sum::<f64>()*(values.len() as f64).recip())),*
+                    #(IO::#variant{ .. } => IOKind::#variant),*
                 }
             }
-            /// Compute the variance
-            pub fn var(&self) -> f64 {
+            /// Splits `IO` into its variant and its data
+            pub fn into_parts(self) -> (IOKind, Option<T>) {
                 match self {
-            #(IO::#variant{ data: None} => f64::NAN,)*
-                    #(IO::#variant{ data: Some(values)} => {
-            let n_recip = (values.len() as f64).recip();
-            let mean = values.iter().sum::<f64>()*n_recip;
-            values.iter().map(|x| x -mean).map(|x| x*x).sum::<f64>()*n_recip
-            }),*
+                    #(IO::#variant{ data } => (IOKind::#variant, data)),*
                 }
             }
-            /// Compute the standard deviation
-            pub fn std(&self) -> f64 { self.var().sqrt() }
         }
-    impl<T> std::ops::Deref for IO<T> {
-        type Target = Option<T>;
-        fn deref(&self) -> &Self::Target {
-            match self {
-                #(IO::#variant{ data: values} => values),*
-            }
-        }
-    }
-    impl<T> std::ops::DerefMut for IO<T> {
-        fn deref_mut(&mut self) -> &mut Self::Target {
-            match self {
-                #(IO::#variant{ data: values} => values),*
-            }
-        }
-    }
-        impl<T,U> PartialEq<IO<T>> for IO<U> {
-            fn eq(&self, other: &IO<T>) -> bool {
-                match (self,other) {
-                    #((IO::#variant{..},IO::#variant{..}) => true,)*
-                    _ => false,
-                }
-            }
-        }
-        impl<T,U> From<&IO<U>> for IO<T> {
-            /// Converts a `IO<T>` into an `Option<T>`
-            fn from(io: &IO<U>) -> Self {
-                match io {
-                    #(IO::#variant{ ..} => IO::#variant{ data: Default::default()}),*
-                }
-            }
-        }
-        impl<T,U: Iterator<Item=T>> From<&mut IO<U>> for Option<IO<T>> {
-            /// Converts a `IO<T>` into an `Option<T>`
-            fn from(io: &mut IO<U>) -> Self {
-                match io {
-                    #(IO::#variant{ data: Some(data)} => data.next().map(|data| IO::#variant{ data: Some(data)}),)*
-                    #(IO::#variant{ data: None} => None,)*
-                }
-            }
-        }
-        impl<T> From<IO<T>> for Option<T> {
-            /// Converts a `IO<T>` into an `Option<T>`
-            fn from(io: IO<T>) -> Self {
-                match io {
+        impl<T> std::ops::Deref for IO<T> {
+            type Target = Option<T>;
+            fn deref(&self) -> &Self::Target {
+                match self {
                     #(IO::#variant{ data: values} => values),*
                 }
             }
         }
-        impl<'a, T> From<&'a IO<T>> for Option<&'a T> {
-            /// Converts a `&IO<T>` into an `Option<&T>`
-            fn from(io: &'a IO<T>) -> Self {
-                match io {
-                    #(IO::#variant{ data: values} => values.as_ref()),*
-                }
-            }
-        }
-        impl<T> From<(&IO<()>,Option<T>)> for IO<T> {
-            fn from((io,data): (&IO<()>,Option<T>)) -> Self {
-                match io {
-                    #(IO::#variant{ .. } => IO::#variant{ data: data}),*
-                }
-            }
-        }
-        impl<T: Debug> From<IO<T>> for Result<T,IOError<T>> {
-            /// Converts a `IO<T>` into an `Result<T,IOError<T>>`
-            fn from(io: IO<T>) -> Self {
-                match io {
-                    #(IO::#variant{ data: values} =>
-                      values.ok_or_else(||
-                                        //format!("{:?} data missing",IO::<T>::#variant{data: None}).into()
-                                        IOError::Missing(IO::<T>::#variant{data: None})
-                    )),*
-                }
-            }
-        }
-        impl<T: Clone> From<&IO<T>> for Option<T> {
-            /// Converts a `&IO<T>` into an `Option<T>`
-            fn from(io: &IO<T>) -> Self {
-                match io {
-                    #(IO::#variant{ data: values} => values.as_ref().cloned()),*
-                }
-            }
-        }
-        impl From<(&IO<usize>,Vec<f64>)> for IO<Vec<f64>> {
-            /// Converts a `(&IO<usize>,Vec<f64>)` into an `IO<Vec<f64>>`
-            fn from((io,v): (&IO<usize>,Vec<f64>)) -> Self {
-                match io {
-                    #(IO::#variant{ data: _} => IO::#variant{ data: Some(v)}),*
-                }
-            }
-        }
-        impl From<(&IO<()>,Vec<f64>)> for IO<Vec<f64>> {
-            /// Converts a `(&IO<()>,Vec<f64>)` into an `IO<Vec<f64>>`
-            fn from((io,v): (&IO<()>,Vec<f64>)) -> Self {
-                match io {
-                    #(IO::#variant{ data: _} => IO::#variant{ data: Some(v)}),*
-                }
-            }
-        }
-        impl std::ops::AddAssign<&IO<Vec<f64>>> for IO<Vec<f64>> {
-            fn add_assign(&mut self, other: &IO<Vec<f64>>) {
-                match self.clone() {
-                    #(IO::#variant{ data: Some(x)} => {
-                        Option::<Vec<f64>>::from(other).map(|y| {
-                            let z: Vec<_> = x.iter().zip(y).map(|(x,y)| x+y).collect();
-                            *self = IO::#variant{ data: Some(z)};
-                        });
-                    }),*
-                        _ => println!("Failed adding IO")
-                }
-            }
-        }
-        impl std::ops::SubAssign<&IO<Vec<f64>>> for IO<Vec<f64>> {
-            fn sub_assign(&mut self, other: &IO<Vec<f64>>) {
-                match self.clone() {
-                    #(IO::#variant{ data: Some(x)} => {
-                        Option::<Vec<f64>>::from(other).map(|y| {
-                            let z: Vec<_> = x.iter().zip(y).map(|(x,y)| x-y).collect();
-                            *self = IO::#variant{ data: Some(z)};
-                        });
-                    }),*
-                        _ => println!("Failed substracting IO")
-                }
-            }
-        }
-        impl std::ops::MulAssign<f64> for IO<Vec<f64>> {
-            fn mul_assign(&mut self, rhs: f64) {
-                match self.clone() {
-                    #(IO::#variant{ data: Some(x)} => {
-            let z: Vec<_> = x.iter().map(|x| x*rhs).collect();
-                        *self = IO::#variant{ data: Some(z)};
-                    }),*
-                        _ => println!("Failed substracting IO")
-                }
-            }
-        }
-    impl std::ops::Mul<f64> for &mut IO<Vec<f64>> {
-        type Output = ();
-        fn mul(self, rhs: f64) -> Self::Output {
-                match self {
-                    #(IO::#variant{ data: Some(values)} => {values.iter_mut().for_each(|v| {*v*=rhs;})}),*
-                        _ => println!("Failed scaling IO")
-                };
-        }
-    }
-    impl std::fmt::Display for IO<()> {
-        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        impl<T> std::ops::DerefMut for IO<T> {
+            fn deref_mut(&mut self) -> &mut Self::Target {
                 match self {
-                    #(IO::#variant{ ..} => write!(f,"{}",stringify!(#variant))),*
+                    #(IO::#variant{ data: values} => values),*
                 }
-        }
-    }
-    impl<T> IO<T> {
-        pub fn kind(&self) -> String
-        {
-            match self {
-                    #(IO::#variant{ ..} => stringify!(#variant).to_string()),*
             }
         }
-    }
         pub mod jar {
             //! A DOS Inputs/Outputs builder
-            use super::IO;
-            #(pub struct #variant {}
+            //!
+            //! Each variant has a marker type implementing [`UniqueIdentifier`](crate::typed::UniqueIdentifier)
+            use super::{IOKind, IO};
+            #(/// Marker type of the variant
+              pub struct #variant {}
               impl #variant {
                   /// Creates a new `IO` type variant with `data` set to `None`
-          #[deprecated(
-              note = "Please use the io function instead"
-          )]
+                  #[deprecated(
+                      note = "Please use the io function instead"
+                  )]
                   pub fn new<T>() -> IO<T> {
                       IO::#variant{ data: None}
                   }
@@ -317,9 +389,9 @@ pub fn build_io(variant: Vec<Ident>) -> proc_macro2::TokenStream {
                       IO::#variant{ data: None}
                   }
                   /// Creates a new `IO` type variant filled with `data`
-          #[deprecated(
-              note = "Please use the io_with function instead"
-          )]
+                  #[deprecated(
+                      note = "Please use the io_with function instead"
+                  )]
                   pub fn with<T>(data: T) -> IO<T> {
                       IO::#variant{ data: Some(data)}
                   }
@@ -328,6 +400,15 @@ pub fn build_io(variant: Vec<Ident>) -> proc_macro2::TokenStream {
                       IO::#variant{ data: Some(data)}
                   }
               }
+              impl crate::typed::UniqueIdentifier for #variant {
+                  type Data = Vec<f64>;
+                  const KIND: IOKind = IOKind::#variant;
+                  const SIZE: Option<usize> = #size;
+              }
+            )*
+            #(/// Former name of a renamed variant
+              #[deprecated(note = #note)]
+              pub type #old = #new;
             )*
         }
     )
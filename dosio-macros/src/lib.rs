@@ -1,24 +1,57 @@
 //! A macro to build the dos inputs and outputs enum variants
 //!
-//! For the FEM, the macro get the variant identifiers from the field names of the structures `fem_inputs` and `fem_outputs` in the file `modal_state_space_model_2ndOrder.rs.mat`.
-//! The location of the file is given by the environment variable `FEM_REPO`
+//! For the FEM, the macro get the variant identifiers from the field names of the structures `fem_inputs` and `fem_outputs` in the file `modal_state_space_model_2ndOrder.rs.mat`
+//! (`hdf5` feature) or from the groups of the tables in `modal_state_space_model_2ndOrder.zip` (`prqt` feature).
+//! The location of the file is given by the environment variable `FEM_REPO`.
+//!
+//! If `FEM_REPO` is not set, the variant identifiers are read from the vendored manifest `fem-io.manifest`.
+//! The manifest is regenerated from a FEM repository with:
+//! ```shell
+//! cargo run --features prqt --bin fem-manifest -- <FEM_REPO> > fem-io.manifest
+//! ```
+//! The `alias <old> <new>` lines of the manifest keep the former names of the variants renamed in the FEM,
+//! they are carried over when the manifest is regenerated.
+//! A FEM group named after the old name of an alias is the new variant,
+//! and the aliases that do not apply to the FEM in `FEM_REPO` are skipped.
 
 use proc_macro::TokenStream;
 
+mod derive;
+mod fem;
 mod io;
 
 #[cfg(feature = "hdf5")]
 mod hdf5_io;
-#[cfg(feature = "hdf5")]
-use hdf5_io::ad_hoc_macro;
 
 #[cfg(feature = "prqt")]
 mod parquet_io;
-#[cfg(feature = "prqt")]
-use parquet_io::ad_hoc_macro;
+
+use io::ad_hoc_macro;
 
 /// Ad-hoc `dosio` crate builder
+///
+/// The arguments are aliases of renamed variants, as `<old> => <new>`, added to the `alias` lines of the manifest.
+/// Each alias is a deprecated type of `jar` and is parsed into its new variant.
 #[proc_macro]
 pub fn ad_hoc(_item: TokenStream) -> TokenStream {
     ad_hoc_macro(_item)
 }
+
+/// Derives `IOTags` from the `#[dos(input = <variant>)]` and `#[dos(output = <variant>)]` field attributes
+///
+/// Unknown and duplicated variants are reported at compile time
+#[proc_macro_derive(IOTags, attributes(dos))]
+pub fn derive_io_tags(input: TokenStream) -> TokenStream {
+    derive::derive_io_tags(input)
+}
+
+/// Derives `Dos` from the `#[dos(input = <variant>)]` and `#[dos(output = <variant>)]` field attributes
+///
+/// The fields are of type `Option<T>`, with the same `T` for all the inputs and for all the outputs:
+/// `inputs` moves the data of each input variant into its field
+/// and `outputs` clones the field of each output variant into the `IO` vector.
+/// The lifecycle hooks of `Dos` are left to their default implementation.
+#[proc_macro_derive(Dos, attributes(dos))]
+pub fn derive_dos(input: TokenStream) -> TokenStream {
+    derive::derive_dos(input)
+}
@@ -0,0 +1,102 @@
+//! FEM inputs/outputs manifest
+//!
+//! The manifest lists the inputs and outputs of a FEM model, one per line, as
+//! `<in|out> <FEM group name> <size>`.
+//! Renamed variants are listed as `alias <old variant> <new variant>`.
+//! Lines starting with `#` are comments.
+//!
+//! The file is included with `#[path]` by `dosio`, so the FEM groups are named after the `IO` variants
+//! and the manifest is parsed the same way in both crates: it only depends on `std`.
+
+use std::fmt;
+
+/// FEM input or output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    In,
+    Out,
+}
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::In => write!(f, "in"),
+            Kind::Out => write!(f, "out"),
+        }
+    }
+}
+
+/// FEM inputs or outputs group
+#[derive(Debug, Clone, PartialEq)]
+pub struct FemIo {
+    /// Input or output
+    pub kind: Kind,
+    /// Group name in the FEM model
+    pub group: String,
+    /// Number of degrees of freedom in the group
+    pub size: usize,
+}
+impl FemIo {
+    /// Returns the name of the matching `IO` variant
+    pub fn variant(&self) -> String {
+        rename(&self.group)
+    }
+}
+
+/// Converts a FEM group name into a `IO` variant name
+pub fn rename(group: &str) -> String {
+    group
+        .split('_')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let (first, last) = s.split_at(1);
+            first.to_uppercase() + last
+        })
+        .collect::<String>()
+}
+
+// Returns the numbered lines of a manifest, without the comments and the blank lines
+fn lines(manifest: &str) -> impl Iterator<Item = (usize, &str)> {
+    manifest
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Parses the inputs and outputs of a manifest
+pub fn parse(manifest: &str) -> Result<Vec<FemIo>, String> {
+    lines(manifest)
+        .filter(|(_, line)| !line.starts_with("alias "))
+        .map(|(i, line)| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [kind, group, size] => Ok(FemIo {
+                    kind: match *kind {
+                        "in" => Kind::In,
+                        "out" => Kind::Out,
+                        _ => return Err(format!("line {}: expected `in` or `out`", i)),
+                    },
+                    group: group.to_string(),
+                    size: size
+                        .parse()
+                        .map_err(|e| format!("line {}: invalid size ({})", i, e))?,
+                }),
+                _ => Err(format!("line {}: expected `<in|out> <group> <size>`", i)),
+            }
+        })
+        .collect()
+}
+
+/// Parses the aliases of a manifest, as `(old variant, new variant)` pairs
+pub fn aliases(manifest: &str) -> Result<Vec<(String, String)>, String> {
+    lines(manifest)
+        .filter(|(_, line)| line.starts_with("alias "))
+        .map(|(i, line)| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [_, old, new] => Ok((old.to_string(), new.to_string())),
+                _ => Err(format!("line {}: expected `alias <old> <new>`", i)),
+            }
+        })
+        .collect()
+}
@@ -4,17 +4,16 @@ use parquet::{
     file::reader::SerializedFileReader,
     util::cursor::SliceableCursor,
 };
-use proc_macro::TokenStream;
-use proc_macro2::{Ident, Span};
-use quote::quote;
-use std::env;
 use std::{fs::File, io::Read, path::Path, sync::Arc};
 use zip::ZipArchive;
 
-use crate::io::{build_io, io_list};
+use crate::fem::{FemIo, Kind};
+
+/// FEM model file name
+pub const FEM_MODEL: &str = "modal_state_space_model_2ndOrder.zip";
 
 #[derive(thiserror::Error, Debug)]
-enum Error {
+pub enum Error {
     #[error("No suitable record in file")]
     NoRecord,
     #[error("No suitable data in file")]
@@ -29,65 +28,19 @@ enum Error {
     ReadZip(#[from] std::io::Error),
 }
 
-pub fn ad_hoc_macro(_item: TokenStream) -> TokenStream {
-    let mut variants: Vec<Ident> = if let Ok(fem_repo) = env::var("FEM_REPO") {
-        // Gets the FEM repository
-        println!(
-            "Building `dosio::IO` enum to match inputs/outputs of FEM in {}",
-            fem_repo
-        );
-        // Opens the mat file
-        let path = Path::new(&fem_repo);
-        let file = if let Ok(val) = File::open(path.join("modal_state_space_model_2ndOrder.zip")) {
-            val
-        } else {
-            return quote!(compile_error!("Cannot find `modal_state_space_model_2ndOrder.zip` in `FEM_REPO`");).into();
-        };
-        let mut zip_file = if let Ok(val) = zip::ZipArchive::new(file) {
-            val
-        } else {
-            return quote!(compile_error!("`modal_state_space_model_2ndOrder.zip` in `FEM_REPO` is not a valid zip archive");).into();
-        };
-
-        get_fem_io(&mut zip_file, "in")
-            .into_iter()
-            .chain(get_fem_io(&mut zip_file, "out").into_iter())
-            .flatten()
-            .collect()
-    } else {
-        println!("`FEM_REPO` environment variable is not set, using dummies instead.");
-        [
-            "Rodolphe",
-            "Rodrigo",
-            "Christoph",
-            "Henry",
-            "Conan",
-            "Romano",
-            "Dribusch",
-            "Fitzpatrick",
-        ]
-        .iter()
-        .map(|&v| Ident::new(v, Span::call_site()))
-        .collect()
-    };
-
-    variants.extend(io_list().map(|&v| Ident::new(v, Span::call_site())));
-
-    variants.sort();
-    variants.dedup();
-    let io = build_io(variants);
-
-    quote!(
-    #io
-    )
-    .into()
+/// Reads the inputs and outputs of the FEM model zip archive at `path`
+pub fn fem_io(path: &Path) -> Result<Vec<FemIo>, Error> {
+    let mut zip_file = zip::ZipArchive::new(File::open(path)?)?;
+    let mut fem_io = get_fem_io(&mut zip_file, Kind::In)?;
+    fem_io.extend(get_fem_io(&mut zip_file, Kind::Out)?);
+    Ok(fem_io)
 }
 
 // Read the fields
-fn get_fem_io(zip_file: &mut ZipArchive<File>, fem_io: &str) -> Result<Vec<Ident>, Error> {
+fn get_fem_io(zip_file: &mut ZipArchive<File>, kind: Kind) -> Result<Vec<FemIo>, Error> {
     let mut input_file = zip_file.by_name(&format!(
         "modal_state_space_model_2ndOrder_{}.parquet",
-        fem_io
+        kind
     ))?;
     let mut contents: Vec<u8> = Vec::new();
     input_file.read_to_end(&mut contents)?;
@@ -99,31 +52,30 @@ fn get_fem_io(zip_file: &mut ZipArchive<File>, fem_io: &str) -> Result<Vec<Ident
         .get_record_reader(2048)?
         .collect::<Result<Vec<RecordBatch>, arrow::error::ArrowError>>()
     {
-        let schema = input_records.get(0).unwrap().schema();
+        let schema = input_records.first().ok_or(Error::NoRecord)?.schema();
         let table = RecordBatch::concat(&schema, &input_records)?;
-        let (idx, _) = schema.column_with_name("group").unwrap();
+        let (idx, _) = schema.column_with_name("group").ok_or(Error::NoData)?;
         let data: Option<Vec<&str>> = table
             .column(idx)
             .as_any()
             .downcast_ref::<StringArray>()
-            .unwrap()
+            .ok_or(Error::NoData)?
             .iter()
             .collect();
-        if let Some(mut data) = data {
-            data.dedup();
-            Ok(data
-                .into_iter()
-                .map(|fem_io| {
-                    let fem_io_rsed = fem_io
-                        .split("_")
-                        .map(|s| {
-                            let (first, last) = s.split_at(1);
-                            first.to_uppercase() + last
-                        })
-                        .collect::<String>();
-                    Ident::new(&fem_io_rsed, Span::call_site())
-                })
-                .collect())
+        if let Some(data) = data {
+            // each row is a degree of freedom and rows are ordered by group
+            let mut fem_io: Vec<FemIo> = vec![];
+            for group in data {
+                match fem_io.last_mut() {
+                    Some(io) if io.group == group => io.size += 1,
+                    _ => fem_io.push(FemIo {
+                        kind,
+                        group: group.to_string(),
+                        size: 1,
+                    }),
+                }
+            }
+            Ok(fem_io)
         } else {
             Err(Error::NoData)
         }
@@ -0,0 +1,74 @@
+//! Reads the inputs and outputs of a synthetic FEM model with the macro backends
+
+#![cfg(any(feature = "hdf5", feature = "prqt"))]
+
+#[path = "../src/fem.rs"]
+#[allow(dead_code)]
+mod fem;
+#[cfg(feature = "hdf5")]
+#[path = "../src/hdf5_io.rs"]
+mod hdf5_io;
+#[cfg(feature = "prqt")]
+#[path = "../src/parquet_io.rs"]
+mod parquet_io;
+
+use dosio::fixture::Fixture;
+use std::{env, path::PathBuf};
+
+fn fixture() -> Fixture {
+    Fixture::new()
+        .input("OSS_ElDrive_Torque", 8)
+        .input("M1_actuators_segment_1", 335)
+        .output("OSS_ElEncoder_Angle", 4)
+        .output("OSS_M1_lcl", 42)
+        .modes(3)
+}
+
+fn fem_repo(backend: &str) -> PathBuf {
+    let fem_repo = env::temp_dir().join(format!(
+        "dosio-macros-fixture-{}-{}",
+        backend,
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&fem_repo).unwrap();
+    fem_repo
+}
+
+fn check(fem_repo: &PathBuf) {
+    let (_, fem_io) = fem::from_repo(fem_repo).unwrap();
+    std::fs::remove_dir_all(fem_repo).unwrap();
+    assert_eq!(
+        fem_io
+            .iter()
+            .map(|io| format!("{} {} {}", io.kind, io.variant(), io.size))
+            .collect::<Vec<_>>(),
+        vec![
+            "in OSSElDriveTorque 8",
+            "in M1ActuatorsSegment1 335",
+            "out OSSElEncoderAngle 4",
+            "out OSSM1Lcl 42"
+        ]
+    );
+}
+
+#[cfg(feature = "prqt")]
+#[test]
+fn parquet() {
+    let fem_repo = fem_repo("prqt");
+    fixture()
+        .model()
+        .to_zip(fem_repo.join(parquet_io::FEM_MODEL))
+        .unwrap();
+    check(&fem_repo);
+}
+
+#[cfg(feature = "hdf5")]
+#[test]
+fn hdf5() {
+    let fem_repo = fem_repo("hdf5");
+    fixture()
+        .model()
+        .to_mat(fem_repo.join(hdf5_io::FEM_MODEL))
+        .unwrap();
+    check(&fem_repo);
+}
@@ -0,0 +1,17 @@
+//! FMU of a gain
+//!
+//! The FMI functions of the model are exported from the `cdylib` of the example,
+//! the FMU is written with [`Model::package`]:
+//! ```shell
+//! cargo build --example fmu --features fmi
+//! ```
+
+use dosio::{blocks::Gain, fmi::export::Model, ios};
+
+pub fn model() -> Model<Gain> {
+    Model::new("gain", Gain::new(ios!(Pssn), ios!(SensorData), 2.))
+        .input_sizes(vec![(ios!(Pssn), 3)])
+        .output_sizes(vec![(ios!(SensorData), 3)])
+        .sample_time(1e-3)
+}
+dosio::export_fmu!(model());
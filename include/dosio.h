@@ -0,0 +1,77 @@
+/*
+ * dosio C API
+ *
+ * Rust `Dos` components exported to C are driven through an opaque
+ * `dosio_component` handle: the inputs are set one by one by variant name or
+ * wire id, `dosio_step` passes them to the component, steps it and retrieves
+ * its outputs, which are then read one by one.
+ *
+ * C components are imported in Rust as `Dos` components from a
+ * `dosio_c_component` callbacks table.
+ */
+#ifndef DOSIO_H
+#define DOSIO_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Rust component exported to C */
+typedef struct dosio_component dosio_component;
+
+/* Returns the wire id of the variant `name`, 0 if the variant does not exist */
+uint32_t dosio_id(const char *name);
+
+/* Sets the input `name` to the `len` values of `data`
+ * Returns 0 on success, -1 on error */
+int dosio_set_input(dosio_component *component, const char *name,
+                    const double *data, size_t len);
+/* Sets the input of wire id `id` to the `len` values of `data`
+ * Returns 0 on success, -1 on error */
+int dosio_set_input_id(dosio_component *component, uint32_t id,
+                       const double *data, size_t len);
+
+/* Passes the inputs to the component, steps it and retrieves its outputs
+ * Returns 0 on success, -1 on error */
+int dosio_step(dosio_component *component);
+
+/* Copies at most `len` values of the output `name` into `data`
+ * Returns the length of the output, -1 on error */
+int64_t dosio_get_output(dosio_component *component, const char *name,
+                         double *data, size_t len);
+/* Copies at most `len` values of the output of wire id `id` into `data`
+ * Returns the length of the output, -1 on error */
+int64_t dosio_get_output_id(dosio_component *component, uint32_t id,
+                            double *data, size_t len);
+
+/* Returns the message of the last error, NULL if the last call succeeded
+ * The message is valid until the next call with the same component */
+const char *dosio_last_error(const dosio_component *component);
+
+/* Frees the component */
+void dosio_free(dosio_component *component);
+
+/* C component imported in Rust
+ *
+ * The callbacks receive `handle` and both the wire id and the name of the
+ * variant. `set_input` and `step` return 0 on success, `get_output` copies at
+ * most `len` values into `data` and returns the length of the output or -1 on
+ * error. `free`, if not NULL, is called when the Rust component is dropped. */
+typedef struct {
+    void *handle;
+    int (*set_input)(void *handle, uint32_t id, const char *name,
+                     const double *data, size_t len);
+    int (*step)(void *handle);
+    int64_t (*get_output)(void *handle, uint32_t id, const char *name,
+                          double *data, size_t len);
+    void (*free)(void *handle);
+} dosio_c_component;
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* DOSIO_H */
@@ -0,0 +1,343 @@
+//! Inspects FEM models, the `IO` enum and logged runs
+//!
+//! ```shell
+//! dosio io
+//! dosio fem <FEM>
+//! dosio diff <FEM> <FEM>
+//! dosio log <LOG> [--csv <FILE>] [--json <FILE>]
+//! ```
+//!  - `io` prints the variants of the compiled `IO` enum with their wire ids and the aliases of the renamed variants,
+//!  - `fem` lists the inputs and outputs of a FEM with their sizes and `IO` variants,
+//!  - `diff` lists the inputs and outputs added, removed or resized from the first FEM to the second one,
+//!  - `log` summarizes a logged run and exports it to CSV or JSON (`-` for the standard output).
+//!
+//! A FEM is either a FEM repository, the directory given as `FEM_REPO`, or a model file,
+//! and `fem` and `diff` require the `fem-prqt` or `fem-hdf5` feature.
+//! A log is either a stream of the wire codec, one frame per step,
+//! or a regression run pickle file (`.pkl`, feature `regression`) with the inputs and outputs of each step.
+
+#[cfg(any(feature = "fem-prqt", feature = "fem-hdf5"))]
+use dosio::fem::{FemGroup, ModalModel};
+use dosio::{
+    io::{IOKind, FINGERPRINT},
+    wire::{self, Decoder},
+    IO,
+};
+use std::{
+    env,
+    fmt::Write as _,
+    fs::File,
+    io::{self, BufReader, Write},
+    path::Path,
+    process,
+};
+
+const USAGE: &str = "usage:
+  dosio io
+  dosio fem <FEM>
+  dosio diff <FEM> <FEM>
+  dosio log <LOG> [--csv <FILE>] [--json <FILE>]";
+
+fn exit<E: std::fmt::Display>(e: E) -> ! {
+    eprintln!("{}", e);
+    process::exit(1)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+    match args.as_slice() {
+        ["io"] => variants(),
+        #[cfg(any(feature = "fem-prqt", feature = "fem-hdf5"))]
+        ["fem", fem] => groups(&load(fem)),
+        #[cfg(any(feature = "fem-prqt", feature = "fem-hdf5"))]
+        ["diff", a, b] => diff(&load(a), &load(b)),
+        #[cfg(not(any(feature = "fem-prqt", feature = "fem-hdf5")))]
+        ["fem", _] | ["diff", _, _] => exit("FEMs require the `fem-prqt` or `fem-hdf5` feature"),
+        ["log", path, options @ ..] => {
+            let log = Log::read(path).unwrap_or_else(|e| exit(e));
+            if options.is_empty() {
+                log.summary();
+            }
+            for option in options.chunks(2) {
+                let result = match option {
+                    ["--csv", path] => output(path, |w| log.csv(w)),
+                    ["--json", path] => output(path, |w| log.json(w)),
+                    _ => exit(format!("invalid options: {}\n{}", options.join(" "), USAGE)),
+                };
+                result.unwrap_or_else(|e| exit(format!("Cannot write {}: {}", option[1], e)));
+            }
+        }
+        ["-h"] | ["--help"] => println!("{}", USAGE),
+        _ => exit(USAGE),
+    }
+}
+
+fn variants() {
+    println!("{}", FINGERPRINT);
+    for kind in IOKind::ALL {
+        println!("{:08x} {}", wire::id(kind), kind);
+    }
+    for (alias, kind) in IOKind::ALIASES {
+        println!("{} is a deprecated alias of {}", alias, kind);
+    }
+}
+
+#[cfg(any(feature = "fem-prqt", feature = "fem-hdf5"))]
+fn load(fem: &str) -> ModalModel {
+    let path = Path::new(fem);
+    if path.is_dir() {
+        ModalModel::from_repo(path)
+    } else {
+        ModalModel::load(path)
+    }
+    .unwrap_or_else(|e| exit(e))
+}
+
+#[cfg(any(feature = "fem-prqt", feature = "fem-hdf5"))]
+fn groups(fem: &ModalModel) {
+    for (name, groups) in [("inputs", &fem.inputs), ("outputs", &fem.outputs)] {
+        let size: usize = groups.iter().map(|group| group.size).sum();
+        println!(
+            "{} ({} groups, {} degrees of freedom):",
+            name,
+            groups.len(),
+            size
+        );
+        let width = groups
+            .iter()
+            .map(|group| group.group.len())
+            .max()
+            .unwrap_or(0);
+        for group in groups.iter() {
+            println!(
+                "  {:width$} {:>6} {}",
+                group.group,
+                group.size,
+                group
+                    .io_kind()
+                    .map_or_else(|| "-".to_string(), |kind| kind.to_string()),
+                width = width
+            );
+        }
+    }
+}
+
+#[cfg(any(feature = "fem-prqt", feature = "fem-hdf5"))]
+fn diff(a: &ModalModel, b: &ModalModel) {
+    let mut n_diff = 0;
+    for (name, a, b) in [
+        ("input", &a.inputs, &b.inputs),
+        ("output", &a.outputs, &b.outputs),
+    ] {
+        let find = |groups: &[FemGroup], group: &FemGroup| {
+            groups
+                .iter()
+                .find(|other| other.group == group.group)
+                .map(|other| other.size)
+        };
+        for group in a.iter() {
+            match find(b, group) {
+                None => println!("- {} {} ({})", name, group.group, group.size),
+                Some(size) if size != group.size => {
+                    println!("~ {} {} ({} -> {})", name, group.group, group.size, size)
+                }
+                Some(_) => continue,
+            }
+            n_diff += 1;
+        }
+        for group in b.iter().filter(|group| find(a, group).is_none()) {
+            println!("+ {} {} ({})", name, group.group, group.size);
+            n_diff += 1;
+        }
+    }
+    if n_diff == 0 {
+        println!("no differences");
+    }
+}
+
+// Writes to the file at `path` or to the standard output for `-`
+fn output<F>(path: &str, write: F) -> io::Result<()>
+where
+    F: FnOnce(&mut dyn Write) -> io::Result<()>,
+{
+    if path == "-" {
+        write(&mut io::stdout().lock())
+    } else {
+        let mut file = io::BufWriter::new(File::create(path)?);
+        write(&mut file)?;
+        file.flush()
+    }
+}
+
+// Values of a variant at each step of a log
+struct Series {
+    name: String,
+    steps: Vec<Option<Vec<f64>>>,
+}
+impl Series {
+    fn size(&self) -> usize {
+        self.steps
+            .iter()
+            .flatten()
+            .map(|x| x.len())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+// Logged run
+#[derive(Default)]
+struct Log {
+    n_step: usize,
+    series: Vec<Series>,
+}
+impl Log {
+    fn read(path: &str) -> Result<Self, String> {
+        let error = |e: &dyn std::fmt::Display| format!("Cannot read {}: {}", path, e);
+        let mut log = Log::default();
+        if Path::new(path).extension().and_then(|x| x.to_str()) == Some("pkl") {
+            #[cfg(feature = "regression")]
+            {
+                let run = dosio::regression::Run::load(path).map_err(|e| error(&e))?;
+                for step in run.steps {
+                    for (prefix, data) in [("input.", step.inputs), ("output.", step.outputs)] {
+                        log.push(prefix, data.unwrap_or_default());
+                    }
+                    log.n_step += 1;
+                }
+                return Ok(log);
+            }
+            #[cfg(not(feature = "regression"))]
+            return Err(error(&"regression runs require the `regression` feature"));
+        }
+        let file = BufReader::new(File::open(path).map_err(|e| error(&e))?);
+        let mut decoder = Decoder::unchecked(file).map_err(|e| error(&e))?;
+        if decoder.fingerprint() != FINGERPRINT.hash {
+            eprintln!(
+                "warning: {} was written with the IO enum of another FEM model ({:016x})",
+                path,
+                decoder.fingerprint()
+            );
+        }
+        while let Some(data) = decoder.decode().map_err(|e| error(&e))? {
+            log.push("", data);
+            log.n_step += 1;
+        }
+        Ok(log)
+    }
+    // Adds the data of the current step
+    fn push(&mut self, prefix: &str, data: Vec<IO<Vec<f64>>>) {
+        for io in data {
+            let name = format!("{}{}", prefix, io.io_kind());
+            let index = match self.series.iter().position(|series| series.name == name) {
+                Some(index) => index,
+                None => {
+                    self.series.push(Series {
+                        name,
+                        steps: vec![],
+                    });
+                    self.series.len() - 1
+                }
+            };
+            let steps = &mut self.series[index].steps;
+            steps.resize(self.n_step, None);
+            steps.push(io.into());
+        }
+    }
+    fn summary(&self) {
+        println!("{} steps, {} variants", self.n_step, self.series.len());
+        let width = self
+            .series
+            .iter()
+            .map(|series| series.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(7);
+        println!(
+            "{:width$} {:>6} {:>5} {:>12} {:>12} {:>12} {:>12}",
+            "variant",
+            "steps",
+            "size",
+            "min",
+            "max",
+            "mean",
+            "std",
+            width = width
+        );
+        for series in &self.series {
+            let values: Vec<f64> = series.steps.iter().flatten().flatten().cloned().collect();
+            let n = values.len() as f64;
+            let mean = values.iter().sum::<f64>() / n;
+            let std = (values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n).sqrt();
+            println!(
+                "{:width$} {:>6} {:>5} {:>12.5e} {:>12.5e} {:>12.5e} {:>12.5e}",
+                series.name,
+                series.steps.iter().flatten().count(),
+                series.size(),
+                values.iter().cloned().fold(f64::INFINITY, f64::min),
+                values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+                mean,
+                std,
+                width = width
+            );
+        }
+    }
+    // One row per step, one column per value of each variant
+    fn csv(&self, w: &mut dyn Write) -> io::Result<()> {
+        let sizes: Vec<usize> = self.series.iter().map(|series| series.size()).collect();
+        let mut header = String::from("step");
+        for (series, &size) in self.series.iter().zip(&sizes) {
+            for i in 1..=size {
+                let _ = write!(header, ",{}[{}]", series.name, i);
+            }
+        }
+        writeln!(w, "{}", header)?;
+        for step in 0..self.n_step {
+            let mut row = step.to_string();
+            for (series, &size) in self.series.iter().zip(&sizes) {
+                let values = series.steps.get(step).and_then(|x| x.as_ref());
+                for i in 0..size {
+                    row.push(',');
+                    if let Some(x) = values.and_then(|values| values.get(i)) {
+                        let _ = write!(row, "{}", x);
+                    }
+                }
+            }
+            writeln!(w, "{}", row)?;
+        }
+        Ok(())
+    }
+    // `{"steps": n, "variants": {"name": [[values], null, ...], ...}}`
+    fn json(&self, w: &mut dyn Write) -> io::Result<()> {
+        let number = |x: &f64| {
+            if x.is_finite() {
+                format!("{:?}", x)
+            } else {
+                "null".to_string()
+            }
+        };
+        write!(w, "{{\"steps\":{},\"variants\":{{", self.n_step)?;
+        for (i, series) in self.series.iter().enumerate() {
+            if i > 0 {
+                write!(w, ",")?;
+            }
+            write!(w, "\"{}\":[", series.name)?;
+            for step in 0..self.n_step {
+                if step > 0 {
+                    write!(w, ",")?;
+                }
+                match series.steps.get(step).and_then(|x| x.as_ref()) {
+                    Some(values) => write!(
+                        w,
+                        "[{}]",
+                        values.iter().map(number).collect::<Vec<_>>().join(",")
+                    )?,
+                    None => write!(w, "null")?,
+                }
+            }
+            write!(w, "]")?;
+        }
+        writeln!(w, "}}}}")
+    }
+}
@@ -0,0 +1,72 @@
+//! Writes a synthetic FEM model to a directory
+//!
+//! ```shell
+//! fem-fixture <DIR> [--manifest <FILE>] [--modes <N>] [--max-frequency <HZ>] [--damping <RATIO>] [--seed <SEED>]
+//! ```
+//! The inputs and outputs are read from the manifest, if any, otherwise the toy telescope of [`Fixture::default`] is used.
+//! The directory can then be used as `FEM_REPO`.
+
+use dosio::fixture::Fixture;
+use std::{env, fs, process};
+
+const USAGE: &str = "usage: fem-fixture <DIR> [--manifest <FILE>] [--modes <N>] [--max-frequency <HZ>] [--damping <RATIO>] [--seed <SEED>]";
+
+fn exit<E: std::fmt::Display>(e: E) -> ! {
+    eprintln!("{}", e);
+    process::exit(1)
+}
+
+// Parses the value of the option
+fn value<T: std::str::FromStr>(option: &str, value: Option<String>) -> T {
+    value
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| {
+            exit(format!(
+                "invalid or missing value for {}\n{}",
+                option, USAGE
+            ))
+        })
+}
+
+fn main() {
+    let mut fem_repo = None;
+    let mut manifest = None;
+    let mut options = vec![];
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--manifest" => manifest = Some(value::<String>(&arg, args.next())),
+            "--modes" | "--max-frequency" | "--damping" | "--seed" => {
+                let val = args.next();
+                options.push((arg, val))
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if fem_repo.is_none() && !arg.starts_with('-') => fem_repo = Some(arg),
+            _ => exit(format!("unexpected argument: {}\n{}", arg, USAGE)),
+        }
+    }
+    let fem_repo = fem_repo.unwrap_or_else(|| exit(USAGE));
+    let fixture = match manifest {
+        Some(path) => fs::read_to_string(&path)
+            .map_err(|e| format!("Cannot read {}: {}", path, e))
+            .and_then(|manifest| Fixture::from_manifest(&manifest).map_err(|e| e.to_string()))
+            .unwrap_or_else(|e| exit(e)),
+        None => Fixture::default(),
+    };
+    let fixture =
+        options
+            .into_iter()
+            .fold(fixture, |fixture, (option, val)| match option.as_str() {
+                "--modes" => fixture.modes(value(&option, val)),
+                "--max-frequency" => fixture.max_frequency(value(&option, val)),
+                "--damping" => fixture.damping(value(&option, val)),
+                _ => fixture.seed(value(&option, val)),
+            });
+    match fixture.write(&fem_repo) {
+        Ok(paths) => paths.iter().for_each(|path| println!("{}", path.display())),
+        Err(e) => exit(e),
+    }
+}
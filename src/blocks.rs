@@ -0,0 +1,688 @@
+//! Discrete-time signal blocks
+//!
+//! Standard blocks acting channel by channel on [`IO`]`<Vec<f64>>`:
+//! [`Gain`], [`Sum`], [`Integrator`], [`Delay`], [`Saturation`], [`RateLimiter`] and [`Deadband`].
+//!
+//! Each block reads its input from the [`IO`] variant(s) it is created with and writes its output to another [`IO`] variant.
+//! The parameters are either a single value for all the channels or one value per channel (see [`Parameter`]).
+//! The blocks configuration can be (de)serialized, their internal state is not.
+
+use crate::{io::IOKind, DOSIOSError, Dos, IOTags, IO};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Block parameter
+///
+/// Either the same value for all the channels or a value per channel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Parameter {
+    /// Same value for all the channels
+    Scalar(f64),
+    /// One value per channel
+    Channels(Vec<f64>),
+}
+impl Default for Parameter {
+    fn default() -> Self {
+        Parameter::Scalar(0f64)
+    }
+}
+impl From<f64> for Parameter {
+    fn from(value: f64) -> Self {
+        Parameter::Scalar(value)
+    }
+}
+impl From<Vec<f64>> for Parameter {
+    fn from(values: Vec<f64>) -> Self {
+        Parameter::Channels(values)
+    }
+}
+impl Parameter {
+    /// Returns the value for channel `i`
+    pub fn get(&self, i: usize) -> f64 {
+        match self {
+            Parameter::Scalar(value) => *value,
+            Parameter::Channels(values) => values[i],
+        }
+    }
+    /// Returns the values for `n` channels
+    pub fn values(&self, n: usize) -> Vec<f64> {
+        (0..n).map(|i| self.get(i)).collect()
+    }
+    // Checks that the parameter can be applied to `n` channels
+    fn check(&self, name: &str, n: usize) -> Result<(), DOSIOSError> {
+        match self {
+            Parameter::Channels(values) if values.len() != n => Err(DOSIOSError::Inputs(
+                format!(
+                    "{} has {} values but the input has {} channels",
+                    name,
+                    values.len(),
+                    n
+                )
+                .into(),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Removes the data of variant `kind` from `data`
+pub(crate) fn take(data: &mut Option<Vec<IO<Vec<f64>>>>, kind: IOKind) -> Option<Vec<f64>> {
+    let data = data.as_mut()?;
+    let idx = data.iter().position(|io| io.io_kind() == kind)?;
+    data.remove(idx).into_parts().1
+}
+pub(crate) fn take_or_err(
+    data: &mut Option<Vec<IO<Vec<f64>>>>,
+    kind: IOKind,
+) -> Result<Vec<f64>, DOSIOSError> {
+    take(data, kind).ok_or_else(|| {
+        DOSIOSError::Inputs(format!("{} input is missing", kind).into())
+            .with_io(&kind.io::<()>(None))
+    })
+}
+fn output(kind: IOKind, y: &Option<Vec<f64>>) -> Option<Vec<IO<Vec<f64>>>> {
+    y.clone().map(|y| vec![kind.io(Some(y))])
+}
+
+/// Gain: `y[k] = g u[k]`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Gain {
+    input: IOKind,
+    output: IOKind,
+    gain: Parameter,
+    #[serde(skip)]
+    u: Option<Vec<f64>>,
+    #[serde(skip)]
+    y: Option<Vec<f64>>,
+}
+impl Gain {
+    /// Creates a new gain block
+    pub fn new<P: Into<Parameter>>(input: IO<()>, output: IO<()>, gain: P) -> Self {
+        Self {
+            input: input.io_kind(),
+            output: output.io_kind(),
+            gain: gain.into(),
+            u: None,
+            y: None,
+        }
+    }
+}
+impl Iterator for Gain {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        let u = self.u.take()?;
+        self.y = Some(
+            u.iter()
+                .enumerate()
+                .map(|(i, u)| self.gain.get(i) * u)
+                .collect(),
+        );
+        Some(())
+    }
+}
+impl Dos for Gain {
+    type Input = Vec<f64>;
+    type Output = Vec<f64>;
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        output(self.output, &self.y)
+    }
+    fn inputs(&mut self, mut data: Option<Vec<IO<Vec<f64>>>>) -> Result<&mut Self, DOSIOSError> {
+        let u = take_or_err(&mut data, self.input)?;
+        self.gain.check("gain", u.len())?;
+        self.u = Some(u);
+        Ok(self)
+    }
+}
+impl IOTags for Gain {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        vec![self.output.io(None)]
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        vec![self.input.io(None)]
+    }
+}
+
+/// Summing junction: `y[k] = sum_j s_j u_j[k]`
+///
+/// The missing inputs are ignored but at least one input must be present
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sum {
+    inputs: Vec<(IOKind, f64)>,
+    output: IOKind,
+    #[serde(skip)]
+    u: Option<Vec<f64>>,
+    #[serde(skip)]
+    y: Option<Vec<f64>>,
+}
+impl Sum {
+    /// Creates a new summing junction, each input is given with its sign (or weight)
+    pub fn new(inputs: Vec<(IO<()>, f64)>, output: IO<()>) -> Self {
+        Self {
+            inputs: inputs
+                .into_iter()
+                .map(|(input, sign)| (input.io_kind(), sign))
+                .collect(),
+            output: output.io_kind(),
+            u: None,
+            y: None,
+        }
+    }
+}
+impl Iterator for Sum {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        self.y = Some(self.u.take()?);
+        Some(())
+    }
+}
+impl Dos for Sum {
+    type Input = Vec<f64>;
+    type Output = Vec<f64>;
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        output(self.output, &self.y)
+    }
+    fn inputs(&mut self, mut data: Option<Vec<IO<Vec<f64>>>>) -> Result<&mut Self, DOSIOSError> {
+        let mut sum: Option<Vec<f64>> = None;
+        for &(kind, sign) in &self.inputs {
+            if let Some(u) = take(&mut data, kind) {
+                match sum.as_mut() {
+                    None => sum = Some(u.into_iter().map(|u| sign * u).collect()),
+                    Some(sum) if sum.len() == u.len() => {
+                        sum.iter_mut().zip(u).for_each(|(s, u)| *s += sign * u)
+                    }
+                    Some(sum) => {
+                        return Err(DOSIOSError::Inputs(
+                            format!(
+                                "{} input has {} channels instead of {}",
+                                kind,
+                                u.len(),
+                                sum.len()
+                            )
+                            .into(),
+                        ))
+                    }
+                }
+            }
+        }
+        self.u = Some(sum.ok_or_else(|| DOSIOSError::Inputs("Sum inputs are missing".into()))?);
+        Ok(self)
+    }
+}
+impl IOTags for Sum {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        vec![self.output.io(None)]
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        self.inputs.iter().map(|(kind, _)| kind.io(None)).collect()
+    }
+}
+
+/// Integrator: `y[k] = y[k-1] + g u[k]` with `y[-1] = y0`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Integrator {
+    input: IOKind,
+    output: IOKind,
+    gain: Parameter,
+    #[serde(default)]
+    initial: Parameter,
+    #[serde(skip)]
+    u: Option<Vec<f64>>,
+    #[serde(skip)]
+    y: Option<Vec<f64>>,
+}
+impl Integrator {
+    /// Creates a new integrator, the gain usually includes the sampling period
+    pub fn new<P: Into<Parameter>>(input: IO<()>, output: IO<()>, gain: P) -> Self {
+        Self {
+            input: input.io_kind(),
+            output: output.io_kind(),
+            gain: gain.into(),
+            initial: Parameter::default(),
+            u: None,
+            y: None,
+        }
+    }
+    /// Sets the initial value `y0`
+    pub fn initial<P: Into<Parameter>>(self, initial: P) -> Self {
+        Self {
+            initial: initial.into(),
+            ..self
+        }
+    }
+}
+impl Iterator for Integrator {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        let u = self.u.take()?;
+        let (initial, gain) = (&self.initial, &self.gain);
+        let y = self.y.get_or_insert_with(|| initial.values(u.len()));
+        y.iter_mut()
+            .zip(u)
+            .enumerate()
+            .for_each(|(i, (y, u))| *y += gain.get(i) * u);
+        Some(())
+    }
+}
+impl Dos for Integrator {
+    type Input = Vec<f64>;
+    type Output = Vec<f64>;
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        output(self.output, &self.y)
+    }
+    fn inputs(&mut self, mut data: Option<Vec<IO<Vec<f64>>>>) -> Result<&mut Self, DOSIOSError> {
+        let u = take_or_err(&mut data, self.input)?;
+        self.gain.check("gain", u.len())?;
+        self.initial.check("initial value", u.len())?;
+        match &self.y {
+            Some(y) if y.len() != u.len() => {
+                return Err(DOSIOSError::Inputs(
+                    format!(
+                        "the state has {} channels but the input has {} channels",
+                        y.len(),
+                        u.len()
+                    )
+                    .into(),
+                ))
+            }
+            _ => self.u = Some(u),
+        }
+        Ok(self)
+    }
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.u = None;
+        self.y = None;
+        Ok(self)
+    }
+}
+impl IOTags for Integrator {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        vec![self.output.io(None)]
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        vec![self.input.io(None)]
+    }
+}
+
+/// Delay: `y[k] = u[k-n]` with `y[k] = y0` for `k<n`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delay {
+    input: IOKind,
+    output: IOKind,
+    n_step: usize,
+    #[serde(default)]
+    initial: Parameter,
+    #[serde(skip)]
+    buffer: Option<VecDeque<Vec<f64>>>,
+    #[serde(skip)]
+    y: Option<Vec<f64>>,
+}
+impl Delay {
+    /// Creates a new delay of `n_step` steps
+    pub fn new(input: IO<()>, output: IO<()>, n_step: usize) -> Self {
+        Self {
+            input: input.io_kind(),
+            output: output.io_kind(),
+            n_step,
+            initial: Parameter::default(),
+            buffer: None,
+            y: None,
+        }
+    }
+    /// Sets the output value `y0` before the first input comes out
+    pub fn initial<P: Into<Parameter>>(self, initial: P) -> Self {
+        Self {
+            initial: initial.into(),
+            ..self
+        }
+    }
+}
+impl Iterator for Delay {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        let buffer = self.buffer.as_mut()?;
+        if buffer.len() <= self.n_step {
+            return None;
+        }
+        self.y = buffer.pop_front();
+        Some(())
+    }
+}
+impl Dos for Delay {
+    type Input = Vec<f64>;
+    type Output = Vec<f64>;
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        output(self.output, &self.y)
+    }
+    fn inputs(&mut self, mut data: Option<Vec<IO<Vec<f64>>>>) -> Result<&mut Self, DOSIOSError> {
+        let u = take_or_err(&mut data, self.input)?;
+        self.initial.check("initial value", u.len())?;
+        let (initial, n_step) = (&self.initial, self.n_step);
+        self.buffer
+            .get_or_insert_with(|| (0..n_step).map(|_| initial.values(u.len())).collect())
+            .push_back(u);
+        Ok(self)
+    }
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.buffer = None;
+        self.y = None;
+        Ok(self)
+    }
+}
+impl IOTags for Delay {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        vec![self.output.io(None)]
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        vec![self.input.io(None)]
+    }
+}
+
+/// Saturation: `y[k] = min(max(u[k], lower), upper)`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Saturation {
+    input: IOKind,
+    output: IOKind,
+    lower: Parameter,
+    upper: Parameter,
+    #[serde(skip)]
+    u: Option<Vec<f64>>,
+    #[serde(skip)]
+    y: Option<Vec<f64>>,
+}
+impl Saturation {
+    /// Creates a new saturation between `lower` and `upper`
+    pub fn new<P: Into<Parameter>, Q: Into<Parameter>>(
+        input: IO<()>,
+        output: IO<()>,
+        lower: P,
+        upper: Q,
+    ) -> Self {
+        Self {
+            input: input.io_kind(),
+            output: output.io_kind(),
+            lower: lower.into(),
+            upper: upper.into(),
+            u: None,
+            y: None,
+        }
+    }
+}
+impl Iterator for Saturation {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        let u = self.u.take()?;
+        self.y = Some(
+            u.into_iter()
+                .enumerate()
+                .map(|(i, u)| u.max(self.lower.get(i)).min(self.upper.get(i)))
+                .collect(),
+        );
+        Some(())
+    }
+}
+impl Dos for Saturation {
+    type Input = Vec<f64>;
+    type Output = Vec<f64>;
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        output(self.output, &self.y)
+    }
+    fn inputs(&mut self, mut data: Option<Vec<IO<Vec<f64>>>>) -> Result<&mut Self, DOSIOSError> {
+        let u = take_or_err(&mut data, self.input)?;
+        self.lower.check("lower limit", u.len())?;
+        self.upper.check("upper limit", u.len())?;
+        self.u = Some(u);
+        Ok(self)
+    }
+}
+impl IOTags for Saturation {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        vec![self.output.io(None)]
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        vec![self.input.io(None)]
+    }
+}
+
+/// Rate limiter: `y[k] = y[k-1] + min(max(u[k] - y[k-1], -falling), rising)` with `y[0] = u[0]`
+///
+/// The rates are given per step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimiter {
+    input: IOKind,
+    output: IOKind,
+    rising: Parameter,
+    falling: Parameter,
+    #[serde(skip)]
+    u: Option<Vec<f64>>,
+    #[serde(skip)]
+    y: Option<Vec<f64>>,
+}
+impl RateLimiter {
+    /// Creates a new rate limiter with the given maximum rising and falling rates
+    pub fn new<P: Into<Parameter>, Q: Into<Parameter>>(
+        input: IO<()>,
+        output: IO<()>,
+        rising: P,
+        falling: Q,
+    ) -> Self {
+        Self {
+            input: input.io_kind(),
+            output: output.io_kind(),
+            rising: rising.into(),
+            falling: falling.into(),
+            u: None,
+            y: None,
+        }
+    }
+}
+impl Iterator for RateLimiter {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        let u = self.u.take()?;
+        let (rising, falling) = (&self.rising, &self.falling);
+        match self.y.as_mut() {
+            None => self.y = Some(u),
+            Some(y) => y
+                .iter_mut()
+                .zip(u)
+                .enumerate()
+                .for_each(|(i, (y, u))| *y += (u - *y).max(-falling.get(i)).min(rising.get(i))),
+        }
+        Some(())
+    }
+}
+impl Dos for RateLimiter {
+    type Input = Vec<f64>;
+    type Output = Vec<f64>;
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        output(self.output, &self.y)
+    }
+    fn inputs(&mut self, mut data: Option<Vec<IO<Vec<f64>>>>) -> Result<&mut Self, DOSIOSError> {
+        let u = take_or_err(&mut data, self.input)?;
+        self.rising.check("rising rate", u.len())?;
+        self.falling.check("falling rate", u.len())?;
+        self.u = Some(u);
+        Ok(self)
+    }
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.u = None;
+        self.y = None;
+        Ok(self)
+    }
+}
+impl IOTags for RateLimiter {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        vec![self.output.io(None)]
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        vec![self.input.io(None)]
+    }
+}
+
+/// Deadband: `y[k] = 0` if `lower <= u[k] <= upper`, `u[k] - upper` above and `u[k] - lower` below
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Deadband {
+    input: IOKind,
+    output: IOKind,
+    lower: Parameter,
+    upper: Parameter,
+    #[serde(skip)]
+    u: Option<Vec<f64>>,
+    #[serde(skip)]
+    y: Option<Vec<f64>>,
+}
+impl Deadband {
+    /// Creates a new deadband between `lower` and `upper`
+    pub fn new<P: Into<Parameter>, Q: Into<Parameter>>(
+        input: IO<()>,
+        output: IO<()>,
+        lower: P,
+        upper: Q,
+    ) -> Self {
+        Self {
+            input: input.io_kind(),
+            output: output.io_kind(),
+            lower: lower.into(),
+            upper: upper.into(),
+            u: None,
+            y: None,
+        }
+    }
+}
+impl Iterator for Deadband {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        let u = self.u.take()?;
+        self.y = Some(
+            u.into_iter()
+                .enumerate()
+                .map(|(i, u)| {
+                    let (lower, upper) = (self.lower.get(i), self.upper.get(i));
+                    if u > upper {
+                        u - upper
+                    } else if u < lower {
+                        u - lower
+                    } else {
+                        0f64
+                    }
+                })
+                .collect(),
+        );
+        Some(())
+    }
+}
+impl Dos for Deadband {
+    type Input = Vec<f64>;
+    type Output = Vec<f64>;
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        output(self.output, &self.y)
+    }
+    fn inputs(&mut self, mut data: Option<Vec<IO<Vec<f64>>>>) -> Result<&mut Self, DOSIOSError> {
+        let u = take_or_err(&mut data, self.input)?;
+        self.lower.check("lower limit", u.len())?;
+        self.upper.check("upper limit", u.len())?;
+        self.u = Some(u);
+        Ok(self)
+    }
+}
+impl IOTags for Deadband {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        vec![self.output.io(None)]
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        vec![self.input.io(None)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ios;
+
+    // Runs `block` with the successive inputs `u` on OSSM1Lcl and returns the SensorData outputs
+    fn run<D>(block: &mut D, u: Vec<Vec<f64>>) -> Vec<Vec<f64>>
+    where
+        D: Dos<Input = Vec<f64>, Output = Vec<f64>> + Iterator,
+    {
+        u.into_iter()
+            .map(|u| {
+                let mut y = block
+                    .in_step_out(Some(vec![ios!(OSSM1Lcl(u))]))
+                    .unwrap()
+                    .unwrap();
+                Option::<Vec<f64>>::from(y.pop().unwrap()).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn gain_sum() {
+        let mut gain = Gain::new(ios!(OSSM1Lcl), ios!(SensorData), vec![2., -1.]);
+        assert_eq!(run(&mut gain, vec![vec![1.5, 3.]]), vec![vec![3., -3.]]);
+        assert!(gain
+            .in_step_out(Some(vec![ios!(OSSM1Lcl(vec![1.]))]))
+            .is_err());
+        let mut sum = Sum::new(
+            vec![
+                (ios!(OSSM1Lcl), 1.),
+                (ios!(MCM2Lcl6D), -1.),
+                (ios!(Pssn), 1.),
+            ],
+            ios!(SensorData),
+        );
+        let y = sum
+            .in_step_out(Some(ios!(OSSM1Lcl(vec![1., 2.]), MCM2Lcl6D(vec![0.5, 4.]))))
+            .unwrap()
+            .unwrap();
+        assert_eq!(Option::<Vec<f64>>::from(&y[0]), Some(vec![0.5, -2.]));
+        assert_eq!(sum.inputs_tags().len(), 3);
+    }
+
+    #[test]
+    fn integrator_delay() {
+        // Step response: y[k] = y0 + (k+1) g u
+        let mut integrator = Integrator::new(ios!(OSSM1Lcl), ios!(SensorData), 0.5).initial(1.);
+        let y = run(&mut integrator, vec![vec![2.]; 4]);
+        assert_eq!(y, vec![vec![2.], vec![3.], vec![4.], vec![5.]]);
+        assert!(integrator
+            .inputs(Some(vec![ios!(OSSM1Lcl(vec![1., 2.]))]))
+            .is_err());
+        let mut delay = Delay::new(ios!(OSSM1Lcl), ios!(SensorData), 2).initial(vec![-1., -2.]);
+        let y = run(
+            &mut delay,
+            (0..4).map(|k| vec![k as f64, 10. * k as f64]).collect(),
+        );
+        assert_eq!(
+            y,
+            vec![vec![-1., -2.], vec![-1., -2.], vec![0., 0.], vec![1., 10.]]
+        );
+    }
+
+    #[test]
+    fn nonlinear() {
+        let ramp: Vec<Vec<f64>> = (0..6).map(|k| vec![k as f64 - 2.]).collect();
+        let mut saturation = Saturation::new(ios!(OSSM1Lcl), ios!(SensorData), -1., 1.5);
+        let y: Vec<f64> = run(&mut saturation, ramp.clone()).concat();
+        assert_eq!(y, vec![-1., -1., 0., 1., 1.5, 1.5]);
+        let mut deadband = Deadband::new(ios!(OSSM1Lcl), ios!(SensorData), -1., 1.);
+        let y: Vec<f64> = run(&mut deadband, ramp).concat();
+        assert_eq!(y, vec![-1., 0., 0., 0., 1., 2.]);
+        // Step response of the rate limiter: y[k] = min(k r, u)
+        let mut rate_limiter = RateLimiter::new(ios!(OSSM1Lcl), ios!(SensorData), 0.25, 1.);
+        let mut u = vec![vec![0.]];
+        u.extend(vec![vec![1.]; 5]);
+        u.push(vec![-1.]);
+        let y: Vec<f64> = run(&mut rate_limiter, u).concat();
+        assert_eq!(y, vec![0., 0.25, 0.5, 0.75, 1., 1., 0.]);
+    }
+
+    #[test]
+    fn config() {
+        let integrator = Integrator::new(ios!(OSSM1Lcl), ios!(SensorData), vec![1e-3, 2e-3]);
+        let config = serde_pickle::to_vec(&integrator, true).unwrap();
+        let mut integrator: Integrator = serde_pickle::from_slice(&config).unwrap();
+        let y = run(&mut integrator, vec![vec![1., 1.]; 2]);
+        assert_eq!(y[1], vec![2e-3, 4e-3]);
+    }
+}
@@ -0,0 +1,337 @@
+//! Component state checkpoint and restore
+//!
+//! A component implementing [`Checkpoint`] returns its state as a serializable value and can be restored from it.
+//! With the `checkpoint` feature, a [`Snapshot`] gathers the states of all the components of a simulation,
+//! together with the in-flight [`IO`](crate::IO) buffers and the [`Clock`](crate::Clock), into a single pickle file.
+//! A simulation restored from a [`Snapshot`] continues exactly as the original one would have.
+//! The snapshot of a [`Pipeline`](crate::Pipeline), its components and the outputs in flight between them,
+//! is taken in one call with [`Pipeline::snapshot`](crate::Pipeline::snapshot).
+
+use crate::DOSIOSError;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Component state checkpoint and restore interface
+pub trait Checkpoint {
+    /// State type
+    type State: Serialize + DeserializeOwned;
+    /// Returns the state of `Self`
+    fn checkpoint(&self) -> Result<Self::State, DOSIOSError>;
+    /// Sets `Self` to `state`
+    fn restore(&mut self, state: Self::State) -> Result<&mut Self, DOSIOSError>;
+}
+
+#[cfg(feature = "checkpoint")]
+pub use snapshot::{Snapshot, SnapshotError};
+
+#[cfg(feature = "checkpoint")]
+mod snapshot {
+    use super::Checkpoint;
+    use crate::{clock::Clock, DOSIOSError};
+    use serde::{de::DeserializeOwned, Serialize};
+    use serde_pickle::{HashableValue, Value};
+    use std::{
+        collections::BTreeMap,
+        fmt,
+        fs::File,
+        io::{BufReader, BufWriter},
+        path::Path,
+    };
+
+    /// Snapshot error type
+    #[derive(Debug)]
+    pub enum SnapshotError {
+        /// Cannot read or write the snapshot file
+        Io(std::io::Error),
+        /// Cannot (de)serialize a state
+        Pickle(serde_pickle::Error),
+        /// A component has failed to checkpoint or to restore its state
+        Dos(DOSIOSError),
+        /// The entry is not in the snapshot
+        Missing(String),
+        /// The snapshot file is not valid
+        Invalid,
+    }
+    impl fmt::Display for SnapshotError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Io(e) => write!(f, "Snapshot file error: {}", e),
+                Self::Pickle(e) => write!(f, "Snapshot (de)serialization error: {}", e),
+                Self::Dos(e) => write!(f, "Snapshot component error: {}", e),
+                Self::Missing(name) => write!(f, "{} is missing from the snapshot", name),
+                Self::Invalid => write!(f, "Invalid snapshot file"),
+            }
+        }
+    }
+    impl std::error::Error for SnapshotError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                Self::Io(e) => Some(e),
+                Self::Pickle(e) => Some(e),
+                Self::Dos(e) => Some(e),
+                _ => None,
+            }
+        }
+    }
+    impl From<std::io::Error> for SnapshotError {
+        fn from(e: std::io::Error) -> Self {
+            Self::Io(e)
+        }
+    }
+    impl From<serde_pickle::Error> for SnapshotError {
+        fn from(e: serde_pickle::Error) -> Self {
+            Self::Pickle(e)
+        }
+    }
+    impl From<DOSIOSError> for SnapshotError {
+        fn from(e: DOSIOSError) -> Self {
+            Self::Dos(e)
+        }
+    }
+
+    /// Simulation snapshot
+    ///
+    /// Component states and [`IO`](crate::IO) buffers are stored by name
+    #[derive(Debug, Clone, Default)]
+    pub struct Snapshot {
+        clock: Option<Clock>,
+        components: BTreeMap<String, Value>,
+        buffers: BTreeMap<String, Value>,
+    }
+    impl Snapshot {
+        /// Creates an empty snapshot
+        pub fn new() -> Self {
+            Default::default()
+        }
+        /// Adds the simulation clock
+        pub fn with_clock(&mut self, clock: &Clock) -> &mut Self {
+            self.clock = Some(*clock);
+            self
+        }
+        /// Adds the state of the component `name`
+        pub fn with_component<C: Checkpoint>(
+            &mut self,
+            name: &str,
+            component: &C,
+        ) -> Result<&mut Self, SnapshotError> {
+            let state = serde_pickle::to_value(&component.checkpoint()?)?;
+            self.components.insert(name.to_string(), state);
+            Ok(self)
+        }
+        /// Adds the buffer `name`, e.g. the outputs of a component that are yet to be passed to the next component
+        pub fn with_buffer<B: Serialize>(
+            &mut self,
+            name: &str,
+            buffer: &B,
+        ) -> Result<&mut Self, SnapshotError> {
+            self.buffers
+                .insert(name.to_string(), serde_pickle::to_value(buffer)?);
+            Ok(self)
+        }
+        /// Returns the simulation clock
+        pub fn clock(&self) -> Option<Clock> {
+            self.clock
+        }
+        /// Restores the component `name`
+        pub fn restore<'a, C: Checkpoint>(
+            &self,
+            name: &str,
+            component: &'a mut C,
+        ) -> Result<&'a mut C, SnapshotError> {
+            let state = self
+                .components
+                .get(name)
+                .ok_or_else(|| SnapshotError::Missing(name.to_string()))?;
+            Ok(component.restore(serde_pickle::from_value(state.clone())?)?)
+        }
+        /// Returns the buffer `name`
+        pub fn buffer<B: DeserializeOwned>(&self, name: &str) -> Result<B, SnapshotError> {
+            let buffer = self
+                .buffers
+                .get(name)
+                .ok_or_else(|| SnapshotError::Missing(name.to_string()))?;
+            Ok(serde_pickle::from_value(buffer.clone())?)
+        }
+        /// Saves the snapshot to a pickle file
+        pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SnapshotError> {
+            let dict = |map: &BTreeMap<String, Value>| {
+                Value::Dict(
+                    map.iter()
+                        .map(|(k, v)| (HashableValue::String(k.clone()), v.clone()))
+                        .collect(),
+                )
+            };
+            let mut snapshot = BTreeMap::new();
+            if let Some(clock) = &self.clock {
+                snapshot.insert(
+                    HashableValue::String("clock".into()),
+                    serde_pickle::to_value(clock)?,
+                );
+            }
+            snapshot.insert(
+                HashableValue::String("components".into()),
+                dict(&self.components),
+            );
+            snapshot.insert(HashableValue::String("buffers".into()), dict(&self.buffers));
+            let mut file = BufWriter::new(File::create(path)?);
+            serde_pickle::value_to_writer(&mut file, &Value::Dict(snapshot), true)?;
+            Ok(())
+        }
+        /// Loads a snapshot from a pickle file
+        pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SnapshotError> {
+            let file = BufReader::new(File::open(path)?);
+            let mut snapshot = match serde_pickle::value_from_reader(file)? {
+                Value::Dict(snapshot) => snapshot,
+                _ => return Err(SnapshotError::Invalid),
+            };
+            let mut map = |key: &str| -> Result<BTreeMap<String, Value>, SnapshotError> {
+                match snapshot.remove(&HashableValue::String(key.into())) {
+                    Some(Value::Dict(map)) => map
+                        .into_iter()
+                        .map(|(k, v)| match k {
+                            HashableValue::String(k) => Ok((k, v)),
+                            _ => Err(SnapshotError::Invalid),
+                        })
+                        .collect(),
+                    _ => Err(SnapshotError::Invalid),
+                }
+            };
+            let components = map("components")?;
+            let buffers = map("buffers")?;
+            let clock = snapshot
+                .remove(&HashableValue::String("clock".into()))
+                .map(serde_pickle::from_value)
+                .transpose()?;
+            Ok(Self {
+                clock,
+                components,
+                buffers,
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::{ios, Dos, IO};
+        use serde::Deserialize;
+
+        // Discrete integrator y[k+1] = y[k] + g u[k] with a mutable gain
+        #[derive(Serialize, Deserialize)]
+        struct Integrator {
+            gain: f64,
+            y: Vec<f64>,
+        }
+        impl Iterator for Integrator {
+            type Item = ();
+            fn next(&mut self) -> Option<()> {
+                self.gain *= 0.999;
+                Some(())
+            }
+        }
+        impl Dos for Integrator {
+            type Input = Vec<f64>;
+            type Output = Vec<f64>;
+            fn inputs(
+                &mut self,
+                data: Option<Vec<IO<Vec<f64>>>>,
+            ) -> Result<&mut Self, DOSIOSError> {
+                if let Some(u) = data
+                    .and_then(|mut data| data.pop())
+                    .and_then(Option::<Vec<f64>>::from)
+                {
+                    let g = self.gain;
+                    self.y.iter_mut().zip(u).for_each(|(y, u)| *y += g * u);
+                }
+                Ok(self)
+            }
+            fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+                Some(vec![ios!(SensorData(self.y.clone()))])
+            }
+        }
+        impl Checkpoint for Integrator {
+            type State = (f64, Vec<f64>);
+            fn checkpoint(&self) -> Result<Self::State, DOSIOSError> {
+                Ok((self.gain, self.y.clone()))
+            }
+            fn restore(&mut self, (gain, y): Self::State) -> Result<&mut Self, DOSIOSError> {
+                self.gain = gain;
+                self.y = y;
+                Ok(self)
+            }
+        }
+
+        #[test]
+        fn checkpoint_restore() {
+            let path =
+                std::env::temp_dir().join(format!("dosio-snapshot-{}.pkl", std::process::id()));
+            let mut a = Integrator {
+                gain: 0.1,
+                y: vec![0.; 3],
+            };
+            let mut b = Integrator {
+                gain: 0.3,
+                y: vec![0.; 3],
+            };
+            let mut clock = Clock::new(1e-3);
+            let mut a_out = Some(vec![ios!(OSSM1Lcl(vec![1., 2., 3.]))]);
+            let run = |a: &mut Integrator,
+                       b: &mut Integrator,
+                       a_out: &mut Option<Vec<IO<Vec<f64>>>>,
+                       clock: &mut Clock| {
+                let b_out = b.in_step_out(a_out.clone()).unwrap();
+                *a_out = a.in_step_out(b_out.clone()).unwrap();
+                clock.tick();
+                b_out
+            };
+            (0..100).for_each(|_| {
+                run(&mut a, &mut b, &mut a_out, &mut clock);
+            });
+            let mut snapshot = Snapshot::new();
+            snapshot
+                .with_clock(&clock)
+                .with_component("a", &a)
+                .unwrap()
+                .with_component("b", &b)
+                .unwrap()
+                .with_buffer("a_out", &a_out)
+                .unwrap();
+            snapshot.save(&path).unwrap();
+            let expected: Vec<_> = (0..100)
+                .filter_map(|_| run(&mut a, &mut b, &mut a_out, &mut clock))
+                .collect();
+
+            let snapshot = Snapshot::load(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            let mut a = Integrator {
+                gain: 0.,
+                y: vec![],
+            };
+            let mut b = Integrator {
+                gain: 0.,
+                y: vec![],
+            };
+            snapshot.restore("a", &mut a).unwrap();
+            snapshot.restore("b", &mut b).unwrap();
+            let mut a_out: Option<Vec<IO<Vec<f64>>>> = snapshot.buffer("a_out").unwrap();
+            let mut clock = snapshot.clock().unwrap();
+            assert_eq!(clock.step(), 100);
+            let restored: Vec<_> = (0..100)
+                .filter_map(|_| run(&mut a, &mut b, &mut a_out, &mut clock))
+                .collect();
+            assert_eq!(expected.len(), restored.len());
+            expected.iter().zip(&restored).for_each(|(e, r)| {
+                let e: Vec<u64> = Option::<Vec<f64>>::from(&e[0])
+                    .unwrap()
+                    .iter()
+                    .map(|x| x.to_bits())
+                    .collect();
+                let r: Vec<u64> = Option::<Vec<f64>>::from(&r[0])
+                    .unwrap()
+                    .iter()
+                    .map(|x| x.to_bits())
+                    .collect();
+                assert_eq!(e, r);
+            });
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! Simulation clock
+//!
+//! A [`Clock`] counts the simulation steps and converts them into simulation time given the sampling period.
+//! Components implementing [`TimedDos`] receive the clock with each call, so all the components of a simulation share the same time.
+//! Any [`Dos`] component that is also an [`Iterator`] implements [`TimedDos`] and ignores the clock.
+
+use crate::{DOSIOSError, Dos, IO};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Simulation clock
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Clock {
+    step: usize,
+    sampling_period: f64,
+    start: f64,
+}
+impl Clock {
+    /// Creates a new clock at step 0 and time 0 with the given sampling period in seconds
+    pub fn new(sampling_period: f64) -> Self {
+        Self {
+            step: 0,
+            sampling_period,
+            start: 0f64,
+        }
+    }
+    /// Creates a new clock from the sampling frequency in Hz
+    pub fn from_frequency(sampling_frequency: f64) -> Self {
+        Self::new(sampling_frequency.recip())
+    }
+    /// Sets the time in seconds at step 0
+    pub fn starting_at(self, start: f64) -> Self {
+        Self { start, ..self }
+    }
+    /// Returns the step index
+    pub fn step(&self) -> usize {
+        self.step
+    }
+    /// Returns the simulation time in seconds
+    pub fn time(&self) -> f64 {
+        self.start + self.step as f64 * self.sampling_period
+    }
+    /// Returns the sampling period in seconds
+    pub fn sampling_period(&self) -> f64 {
+        self.sampling_period
+    }
+    /// Returns the sampling frequency in Hz
+    pub fn sampling_frequency(&self) -> f64 {
+        self.sampling_period.recip()
+    }
+    /// Advances the clock by one step
+    pub fn tick(&mut self) -> &mut Self {
+        self.step += 1;
+        self
+    }
+    /// Sets the clock back to step 0
+    pub fn reset(&mut self) -> &mut Self {
+        self.step = 0;
+        self
+    }
+    /// Returns `data` stamped with the current step and time
+    pub fn stamp<T>(&self, data: T) -> Timestamped<T> {
+        Timestamped {
+            step: self.step,
+            time: self.time(),
+            data,
+        }
+    }
+}
+impl Iterator for Clock {
+    type Item = Clock;
+    /// Returns the current clock and advances it by one step
+    fn next(&mut self) -> Option<Self::Item> {
+        let clock = *self;
+        self.tick();
+        Some(clock)
+    }
+}
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "step #{} @ {:.6}s", self.step, self.time())
+    }
+}
+
+/// Data stamped with the step and time of the [`Clock`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Timestamped<T> {
+    /// Step index
+    pub step: usize,
+    /// Simulation time in seconds
+    pub time: f64,
+    /// Data
+    pub data: T,
+}
+impl<T> Timestamped<T> {
+    /// Returns the data, dropping the timestamp
+    pub fn into_inner(self) -> T {
+        self.data
+    }
+}
+
+/// Timestamped [`IO`] output vector
+pub type TimedOutputs<T> = Option<Timestamped<Vec<IO<T>>>>;
+
+/// Time-aware Dynamic Optics Simulation interface
+pub trait TimedDos {
+    /// `Self` inputs type
+    type Input;
+    /// `Self` outputs type
+    type Output;
+
+    /// Passes a [`IO`] input vector to `Self` at the time of `clock`
+    fn inputs_at(
+        &mut self,
+        clock: &Clock,
+        data: Option<Vec<IO<Self::Input>>>,
+    ) -> Result<&mut Self, DOSIOSError>;
+
+    /// Updates `Self` from the time of `clock` to the next step
+    fn step_at(&mut self, clock: &Clock) -> Result<&mut Self, DOSIOSError>;
+
+    /// Returns a [`IO`] output vector from `Self` at the time of `clock`
+    fn outputs_at(&mut self, clock: &Clock) -> Option<Vec<IO<Self::Output>>>;
+
+    /// Combines `inputs_at`, `step_at` and `outputs_at` then advances `clock` by one step
+    ///
+    /// The outputs are stamped with the clock before it is advanced
+    fn in_step_out_at(
+        &mut self,
+        clock: &mut Clock,
+        data: Option<Vec<IO<Self::Input>>>,
+    ) -> Result<TimedOutputs<Self::Output>, DOSIOSError>
+    where
+        Self: Sized,
+    {
+        let outputs = self
+            .inputs_at(clock, data)?
+            .step_at(clock)?
+            .outputs_at(clock)
+            .map(|outputs| clock.stamp(outputs));
+        clock.tick();
+        Ok(outputs)
+    }
+}
+impl<D: Dos + Iterator> TimedDos for D {
+    type Input = <D as Dos>::Input;
+    type Output = <D as Dos>::Output;
+
+    fn inputs_at(
+        &mut self,
+        _clock: &Clock,
+        data: Option<Vec<IO<Self::Input>>>,
+    ) -> Result<&mut Self, DOSIOSError> {
+        self.inputs(data)
+    }
+    fn step_at(&mut self, _clock: &Clock) -> Result<&mut Self, DOSIOSError> {
+        self.step()
+    }
+    fn outputs_at(&mut self, _clock: &Clock) -> Option<Vec<IO<Self::Output>>> {
+        self.outputs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ios;
+
+    // Sine wave generator
+    struct Sine {
+        frequency: f64,
+        y: f64,
+    }
+    impl TimedDos for Sine {
+        type Input = ();
+        type Output = Vec<f64>;
+        fn inputs_at(
+            &mut self,
+            _clock: &Clock,
+            _data: Option<Vec<IO<()>>>,
+        ) -> Result<&mut Self, DOSIOSError> {
+            Ok(self)
+        }
+        fn step_at(&mut self, clock: &Clock) -> Result<&mut Self, DOSIOSError> {
+            self.y = (2. * std::f64::consts::PI * self.frequency * clock.time()).sin();
+            Ok(self)
+        }
+        fn outputs_at(&mut self, _clock: &Clock) -> Option<Vec<IO<Vec<f64>>>> {
+            Some(vec![ios!(SensorData(vec![self.y]))])
+        }
+    }
+
+    // Counts the steps
+    struct Counter(usize);
+    impl Iterator for Counter {
+        type Item = usize;
+        fn next(&mut self) -> Option<usize> {
+            self.0 += 1;
+            Some(self.0)
+        }
+    }
+    impl Dos for Counter {
+        type Input = ();
+        type Output = usize;
+        fn inputs(&mut self, _data: Option<Vec<IO<()>>>) -> Result<&mut Self, DOSIOSError> {
+            Ok(self)
+        }
+        fn outputs(&mut self) -> Option<Vec<IO<usize>>> {
+            Some(vec![ios!(SensorData(self.0))])
+        }
+    }
+
+    #[test]
+    fn clock() {
+        let mut clock = Clock::from_frequency(1e3).starting_at(1.);
+        assert_eq!(clock.next().map(|c| c.step()), Some(0));
+        assert_eq!(clock.step(), 1);
+        assert!((clock.time() - 1.001).abs() < 1e-12);
+    }
+
+    #[test]
+    fn timed_dos() {
+        let mut clock = Clock::new(0.25);
+        let mut sine = Sine {
+            frequency: 1.,
+            y: 0.,
+        };
+        let y: Vec<_> = (0..4)
+            .map(|_| sine.in_step_out_at(&mut clock, None).unwrap().unwrap())
+            .collect();
+        assert_eq!(y[1].step, 1);
+        assert_eq!(y[1].time, 0.25);
+        assert!((Option::<Vec<f64>>::from(&y[1].data[0]).unwrap()[0] - 1.).abs() < 1e-12);
+        assert_eq!(clock.step(), 4);
+    }
+
+    #[test]
+    fn blanket_adapter() {
+        let mut clock = Clock::new(1e-3);
+        let mut counter = Counter(0);
+        let y = counter.in_step_out_at(&mut clock, None).unwrap().unwrap();
+        assert_eq!(y.step, 0);
+        assert_eq!(Option::<usize>::from(&y.data[0]), Some(1));
+        assert_eq!(clock.step(), 1);
+    }
+}
@@ -0,0 +1,555 @@
+//! DOS components composition
+//!
+//! The combinators build a new [`Dos`] component out of other components:
+//!  - [`Series`]: the outputs of the 1st component are the inputs of the 2nd component,
+//!  - [`Parallel`]: both components receive the same inputs and their outputs are merged,
+//!  - [`Feedback`]: the outputs of the controller are added to the inputs of the plant at the next step.
+//!
+//! The adapters of [`DosExt`] transform the inputs or the outputs of a component
+//! and [`FnDos`] turns a closure into a component.
+//! All of them are [`Dos`] components and [`Iterator`]s, so they can be nested or used anywhere a single component is.
+//!
+//! A composite component updates its members when it receives its inputs,
+//! i.e. the members `in_step_out` methods are called within the `inputs` method of the composite.
+
+use crate::{
+    io::IOKind,
+    pipeline::merge,
+    validate::{Mode, Validate},
+    DOSIOSError, Dos, IOTags, IO,
+};
+use std::marker::PhantomData;
+
+/// Two components in series
+///
+/// The outputs of the 1st component are the inputs of the 2nd one
+pub struct Series<A, B>(pub A, pub B);
+impl<A, B> Dos for Series<A, B>
+where
+    A: Dos + Iterator,
+    B: Dos<Input = A::Output> + Iterator,
+{
+    type Input = A::Input;
+    type Output = B::Output;
+    fn outputs(&mut self) -> Option<Vec<IO<Self::Output>>> {
+        self.1.outputs()
+    }
+    fn inputs(&mut self, data: Option<Vec<IO<Self::Input>>>) -> Result<&mut Self, DOSIOSError> {
+        let data = self.0.in_step_out(data)?;
+        self.1.inputs(data)?;
+        Ok(self)
+    }
+    fn init(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.0.init()?;
+        self.1.init()?;
+        Ok(self)
+    }
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.0.reset()?;
+        self.1.reset()?;
+        Ok(self)
+    }
+    fn finalize(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.0.finalize()?;
+        self.1.finalize()?;
+        Ok(self)
+    }
+}
+impl<A, B: Iterator> Iterator for Series<A, B> {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        self.1.next().map(|_| ())
+    }
+}
+impl<A: IOTags, B: IOTags> IOTags for Series<A, B> {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        self.1.outputs_tags()
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        self.0.inputs_tags()
+    }
+}
+
+/// Two components in parallel
+///
+/// Both components receive the same inputs and the outputs of the 2nd component are appended to the outputs of the 1st one
+pub struct Parallel<A, B>(pub A, pub B);
+impl<A, B> Dos for Parallel<A, B>
+where
+    A: Dos + Iterator,
+    A::Input: Clone,
+    B: Dos<Input = A::Input, Output = A::Output> + Iterator,
+{
+    type Input = A::Input;
+    type Output = A::Output;
+    fn outputs(&mut self) -> Option<Vec<IO<Self::Output>>> {
+        merge(vec![self.0.outputs(), self.1.outputs()].into_iter())
+    }
+    fn inputs(&mut self, data: Option<Vec<IO<Self::Input>>>) -> Result<&mut Self, DOSIOSError> {
+        self.0.inputs(data.clone())?;
+        self.1.inputs(data)?;
+        Ok(self)
+    }
+    fn init(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.0.init()?;
+        self.1.init()?;
+        Ok(self)
+    }
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.0.reset()?;
+        self.1.reset()?;
+        Ok(self)
+    }
+    fn finalize(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.0.finalize()?;
+        self.1.finalize()?;
+        Ok(self)
+    }
+}
+impl<A: Iterator, B: Iterator> Iterator for Parallel<A, B> {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        let (a, b) = (self.0.next(), self.1.next());
+        a.and(b).map(|_| ())
+    }
+}
+impl<A: IOTags, B: IOTags> IOTags for Parallel<A, B> {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        let mut tags = self.0.outputs_tags();
+        tags.extend(self.1.outputs_tags());
+        tags
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        let mut tags = self.0.inputs_tags();
+        tags.extend(self.1.inputs_tags());
+        tags
+    }
+}
+
+/// Plant and controller in a feedback loop
+///
+/// The controller receives the plant outputs and its outputs are appended to the plant inputs at the next step.
+/// The outputs of the loop are the plant outputs.
+pub struct Feedback<P: Dos, C: Dos> {
+    plant: P,
+    controller: C,
+    plant_outputs: Option<Vec<IO<P::Output>>>,
+    controller_outputs: Option<Vec<IO<C::Output>>>,
+}
+impl<P: Dos, C: Dos> Feedback<P, C> {
+    /// Creates a new feedback loop
+    pub fn new(plant: P, controller: C) -> Self {
+        Self {
+            plant,
+            controller,
+            plant_outputs: None,
+            controller_outputs: None,
+        }
+    }
+    /// Returns the plant
+    pub fn plant(&self) -> &P {
+        &self.plant
+    }
+    /// Returns the controller
+    pub fn controller(&self) -> &C {
+        &self.controller
+    }
+}
+impl<T, P, C> Dos for Feedback<P, C>
+where
+    T: Clone,
+    P: Dos<Input = T, Output = T> + Iterator,
+    C: Dos<Input = T, Output = T> + Iterator,
+{
+    type Input = T;
+    type Output = T;
+    fn outputs(&mut self) -> Option<Vec<IO<Self::Output>>> {
+        self.plant_outputs.clone()
+    }
+    fn inputs(&mut self, data: Option<Vec<IO<Self::Input>>>) -> Result<&mut Self, DOSIOSError> {
+        let feedback = self.controller_outputs.take();
+        let plant_outputs = self
+            .plant
+            .in_step_out(merge(vec![data, feedback].into_iter()))?;
+        self.controller_outputs = self.controller.in_step_out(plant_outputs.clone())?;
+        self.plant_outputs = plant_outputs;
+        Ok(self)
+    }
+    fn init(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.plant.init()?;
+        self.controller.init()?;
+        Ok(self)
+    }
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.plant.reset()?;
+        self.controller.reset()?;
+        self.plant_outputs = None;
+        self.controller_outputs = None;
+        Ok(self)
+    }
+    fn finalize(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.plant.finalize()?;
+        self.controller.finalize()?;
+        Ok(self)
+    }
+}
+impl<P: Dos, C: Dos> Iterator for Feedback<P, C> {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        Some(())
+    }
+}
+impl<P: Dos + IOTags, C: Dos + IOTags> IOTags for Feedback<P, C> {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        self.plant.outputs_tags()
+    }
+    /// Returns the plant inputs that are not controller outputs
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        let feedback = self.controller.outputs_tags();
+        self.plant
+            .inputs_tags()
+            .into_iter()
+            .filter(|tag| !feedback.contains(tag))
+            .collect()
+    }
+}
+
+/// Component with its inputs transformed by a closure
+pub struct MapInputs<D, F, I> {
+    dos: D,
+    f: F,
+    input: PhantomData<fn(I)>,
+}
+impl<D, F, I> Dos for MapInputs<D, F, I>
+where
+    D: Dos,
+    F: FnMut(Vec<IO<I>>) -> Vec<IO<D::Input>>,
+{
+    type Input = I;
+    type Output = D::Output;
+    fn outputs(&mut self) -> Option<Vec<IO<Self::Output>>> {
+        self.dos.outputs()
+    }
+    fn inputs(&mut self, data: Option<Vec<IO<Self::Input>>>) -> Result<&mut Self, DOSIOSError> {
+        self.dos.inputs(data.map(&mut self.f))?;
+        Ok(self)
+    }
+    fn init(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.dos.init()?;
+        Ok(self)
+    }
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.dos.reset()?;
+        Ok(self)
+    }
+    fn finalize(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.dos.finalize()?;
+        Ok(self)
+    }
+}
+impl<D: Iterator, F, I> Iterator for MapInputs<D, F, I> {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        self.dos.next().map(|_| ())
+    }
+}
+
+/// Component with its outputs transformed by a closure
+pub struct MapOutputs<D, F, O> {
+    dos: D,
+    f: F,
+    output: PhantomData<fn() -> O>,
+}
+impl<D, F, O> Dos for MapOutputs<D, F, O>
+where
+    D: Dos,
+    F: FnMut(Vec<IO<D::Output>>) -> Vec<IO<O>>,
+{
+    type Input = D::Input;
+    type Output = O;
+    fn outputs(&mut self) -> Option<Vec<IO<Self::Output>>> {
+        self.dos.outputs().map(&mut self.f)
+    }
+    fn inputs(&mut self, data: Option<Vec<IO<Self::Input>>>) -> Result<&mut Self, DOSIOSError> {
+        self.dos.inputs(data)?;
+        Ok(self)
+    }
+    fn init(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.dos.init()?;
+        Ok(self)
+    }
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.dos.reset()?;
+        Ok(self)
+    }
+    fn finalize(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.dos.finalize()?;
+        Ok(self)
+    }
+}
+impl<D: Iterator, F, O> Iterator for MapOutputs<D, F, O> {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        self.dos.next().map(|_| ())
+    }
+}
+
+/// Component with some of its outputs renamed
+pub struct Rename<D> {
+    dos: D,
+    renames: Vec<(IOKind, IOKind)>,
+}
+impl<D> Rename<D> {
+    fn renamed(&self, kind: IOKind) -> IOKind {
+        self.renames
+            .iter()
+            .find(|(from, _)| *from == kind)
+            .map_or(kind, |(_, to)| *to)
+    }
+}
+impl<D: Dos> Dos for Rename<D> {
+    type Input = D::Input;
+    type Output = D::Output;
+    fn outputs(&mut self) -> Option<Vec<IO<Self::Output>>> {
+        self.dos.outputs().map(|outputs| {
+            outputs
+                .into_iter()
+                .map(|io| {
+                    let (kind, data) = io.into_parts();
+                    self.renamed(kind).io(data)
+                })
+                .collect()
+        })
+    }
+    fn inputs(&mut self, data: Option<Vec<IO<Self::Input>>>) -> Result<&mut Self, DOSIOSError> {
+        self.dos.inputs(data)?;
+        Ok(self)
+    }
+    fn init(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.dos.init()?;
+        Ok(self)
+    }
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.dos.reset()?;
+        Ok(self)
+    }
+    fn finalize(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.dos.finalize()?;
+        Ok(self)
+    }
+}
+impl<D: Iterator> Iterator for Rename<D> {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        self.dos.next().map(|_| ())
+    }
+}
+impl<D: IOTags> IOTags for Rename<D> {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        self.dos
+            .outputs_tags()
+            .into_iter()
+            .map(|tag| self.renamed(tag.io_kind()).io(None))
+            .collect()
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        self.dos.inputs_tags()
+    }
+}
+
+/// Component made of a closure
+///
+/// The closure is called with the inputs (an empty [`Vec`] if there are none) and its result is returned once by `outputs`
+pub struct FnDos<F, I, O> {
+    f: F,
+    outputs: Option<Vec<IO<O>>>,
+    input: PhantomData<fn(I)>,
+}
+impl<F, I, O> FnDos<F, I, O>
+where
+    F: FnMut(Vec<IO<I>>) -> Vec<IO<O>>,
+{
+    /// Creates a new component from a closure
+    pub fn new(f: F) -> Self {
+        Self {
+            f,
+            outputs: None,
+            input: PhantomData,
+        }
+    }
+}
+impl<F, I, O> Dos for FnDos<F, I, O>
+where
+    F: FnMut(Vec<IO<I>>) -> Vec<IO<O>>,
+{
+    type Input = I;
+    type Output = O;
+    fn outputs(&mut self) -> Option<Vec<IO<Self::Output>>> {
+        self.outputs.take()
+    }
+    fn inputs(&mut self, data: Option<Vec<IO<Self::Input>>>) -> Result<&mut Self, DOSIOSError> {
+        self.outputs = Some((self.f)(data.unwrap_or_default()));
+        Ok(self)
+    }
+}
+impl<F, I, O> Iterator for FnDos<F, I, O> {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        Some(())
+    }
+}
+
+/// Combinators and adapters of [`Dos`] components
+pub trait DosExt: Dos + Sized {
+    /// Puts `Self` and `other` in series
+    fn series<B>(self, other: B) -> Series<Self, B> {
+        Series(self, other)
+    }
+    /// Puts `Self` and `other` in parallel
+    fn parallel<B>(self, other: B) -> Parallel<Self, B> {
+        Parallel(self, other)
+    }
+    /// Closes the loop between `Self`, the plant, and `controller`
+    fn feedback<C: Dos>(self, controller: C) -> Feedback<Self, C> {
+        Feedback::new(self, controller)
+    }
+    /// Transforms the inputs with `f` before passing them to `Self`
+    fn map_inputs<I, F>(self, f: F) -> MapInputs<Self, F, I>
+    where
+        F: FnMut(Vec<IO<I>>) -> Vec<IO<Self::Input>>,
+    {
+        MapInputs {
+            dos: self,
+            f,
+            input: PhantomData,
+        }
+    }
+    /// Transforms the outputs of `Self` with `f`
+    fn map_outputs<O, F>(self, f: F) -> MapOutputs<Self, F, O>
+    where
+        F: FnMut(Vec<IO<Self::Output>>) -> Vec<IO<O>>,
+    {
+        MapOutputs {
+            dos: self,
+            f,
+            output: PhantomData,
+        }
+    }
+    /// Renames the outputs of `Self`, each pair is given as `(from, to)`
+    fn rename(self, renames: Vec<(IO<()>, IO<()>)>) -> Rename<Self> {
+        Rename {
+            dos: self,
+            renames: renames
+                .into_iter()
+                .map(|(from, to)| (from.io_kind(), to.io_kind()))
+                .collect(),
+        }
+    }
+    /// Validates the inputs and outputs of `Self` against its [`IOTags`]
+    fn validate(self, mode: Mode) -> Validate<Self>
+    where
+        Self: IOTags,
+    {
+        Validate::new(self, mode)
+    }
+}
+impl<D: Dos> DosExt for D {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compare::IOVecCompare, ios, Pipeline};
+
+    type Data = Vec<IO<Vec<f64>>>;
+
+    // Multiplies all the inputs by `gain` and outputs them as `kind`
+    fn gain(gain: f64, kind: IOKind) -> impl Dos<Input = Vec<f64>, Output = Vec<f64>> + Iterator {
+        FnDos::new(move |data: Data| {
+            let u = data
+                .into_iter()
+                .filter_map(Option::<Vec<f64>>::from)
+                .fold(vec![0f64], |u, x| vec![u[0] + x[0]]);
+            vec![kind.io(Some(vec![gain * u[0]]))]
+        })
+    }
+    // y[k] = y[k-1] + u[k]
+    fn integrator(kind: IOKind) -> impl Dos<Input = Vec<f64>, Output = Vec<f64>> + Iterator + Send {
+        let mut y = 1f64;
+        FnDos::new(move |data: Data| {
+            y += data
+                .into_iter()
+                .filter_map(Option::<Vec<f64>>::from)
+                .map(|x| x[0])
+                .sum::<f64>();
+            vec![kind.io(Some(vec![y]))]
+        })
+    }
+    fn value(data: Option<Data>) -> f64 {
+        Option::<Vec<f64>>::from(&data.unwrap()[0]).unwrap()[0]
+    }
+
+    #[test]
+    fn series_parallel() {
+        let mut model = gain(2., IOKind::SensorData)
+            .series(gain(3., IOKind::Pssn).parallel(gain(-1., IOKind::M1RBMcmd)));
+        let y = model
+            .in_step_out(Some(vec![ios!(OSSM1Lcl(vec![1.5]))]))
+            .unwrap()
+            .unwrap();
+        assert!(y.eq_data(&[ios!(M1RBMcmd(vec![-3.])), ios!(Pssn(vec![9.]))]));
+    }
+
+    #[test]
+    fn feedback() {
+        let mut closed_loop = integrator(IOKind::OSSM1Lcl).feedback(gain(-0.5, IOKind::M1RBMcmd));
+        let y: Vec<f64> = (0..20)
+            .map(|_| value(closed_loop.in_step_out(None).unwrap()))
+            .collect();
+
+        let mut plant = integrator(IOKind::OSSM1Lcl);
+        let mut controller = gain(-0.5, IOKind::M1RBMcmd);
+        let mut pipeline = Pipeline::new();
+        let p = pipeline.add("plant", &mut plant);
+        let c = pipeline.add("controller", &mut controller);
+        pipeline
+            .connect(p, c, vec![])
+            .connect(c, p, vec![])
+            .output(p, vec![]);
+        let expected: Vec<f64> = pipeline
+            .run_sequential(20)
+            .unwrap()
+            .into_iter()
+            .map(value)
+            .collect();
+        assert_eq!(y, expected);
+        assert_eq!(y[0], 1.);
+        assert_eq!(y[1], 0.5);
+    }
+
+    #[test]
+    fn adapters() {
+        let mut model = gain(2., IOKind::SensorData)
+            .map_inputs(|data: Vec<IO<f64>>| {
+                data.into_iter()
+                    .map(|io| {
+                        let (kind, data) = io.into_parts();
+                        kind.io(data.map(|x| vec![x]))
+                    })
+                    .collect()
+            })
+            .rename(vec![(ios!(SensorData), ios!(Pssn))])
+            .map_outputs(|data| {
+                data.into_iter()
+                    .map(|io| {
+                        let (kind, data) = io.into_parts();
+                        kind.io(data.map(|x| x.len()))
+                    })
+                    .collect()
+            });
+        let y = model
+            .in_step_out(Some(vec![ios!(OSSM1Lcl(4f64))]))
+            .unwrap()
+            .unwrap();
+        assert!(y.eq_data(&[ios!(Pssn(1usize))]));
+    }
+}
@@ -0,0 +1,360 @@
+//! Data-aware comparison of inputs/outputs
+//!
+//! The [`PartialEq`] implementation of [`IO`] compares only the variants and ignores the data.
+//! This module provides the comparisons that account for the data:
+//! exact equality with [`IO::eq_data`] and tolerance-based equality with [`IO::approx_eq`],
+//! the differences being reported with [`IO::diff`].
+//! The [`IOVecCompare`] trait extends the same comparisons to [`Vec`] of [`IO`], irrespective of the order of the [`IO`]s.
+
+use crate::{io::IOKind, IO};
+use serde::{Deserialize, Serialize};
+use std::{fmt, ops::Deref};
+
+/// Maximum number of channels listed in a [`Difference`]
+const MAX_CHANNELS: usize = 5;
+
+/// Absolute and relative tolerances
+///
+/// Two values `a` and `b` are close if `|a-b| <= max(abs, rel * max(|a|,|b|))`
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Tolerance {
+    /// Absolute tolerance
+    pub abs: f64,
+    /// Relative tolerance
+    pub rel: f64,
+}
+impl Tolerance {
+    /// Creates a new tolerance
+    pub fn new(abs: f64, rel: f64) -> Self {
+        Self { abs, rel }
+    }
+    /// Zero tolerance
+    pub fn exact() -> Self {
+        Self::default()
+    }
+    /// Absolute tolerance only
+    pub fn abs(abs: f64) -> Self {
+        Self { abs, rel: 0f64 }
+    }
+    /// Relative tolerance only
+    pub fn rel(rel: f64) -> Self {
+        Self { abs: 0f64, rel }
+    }
+    /// Checks if `a` and `b` are within tolerance
+    ///
+    /// Two NaNs are considered equal
+    pub fn is_close(&self, a: f64, b: f64) -> bool {
+        if a.is_nan() || b.is_nan() {
+            return a.is_nan() && b.is_nan();
+        }
+        a == b || (a - b).abs() <= self.abs.max(self.rel * a.abs().max(b.abs()))
+    }
+}
+
+/// A channel that differs between two [`IO`]s
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Channel {
+    /// Channel index
+    pub index: usize,
+    /// Left hand side value
+    pub lhs: f64,
+    /// Right hand side value
+    pub rhs: f64,
+}
+
+/// Difference between two [`IO`]s
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Difference {
+    /// The variants are different
+    Kind { lhs: IOKind, rhs: IOKind },
+    /// Only one [`IO`] holds data
+    Data { kind: IOKind, lhs: bool, rhs: bool },
+    /// The data have different lengths
+    Length {
+        kind: IOKind,
+        lhs: usize,
+        rhs: usize,
+    },
+    /// Some channels are not within tolerance
+    Values {
+        kind: IOKind,
+        /// Number of channels not within tolerance
+        n_channel: usize,
+        /// The first channels not within tolerance
+        channels: Vec<Channel>,
+        /// Largest absolute difference
+        max_abs_error: f64,
+    },
+    /// The variant is missing from one of the [`Vec`]s
+    Missing { kind: IOKind, lhs: bool, rhs: bool },
+    /// Only one of the steps has [`IO`]s
+    Step { lhs: bool, rhs: bool },
+}
+impl Difference {
+    /// Returns the variant of the differing [`IO`], if any
+    pub fn kind(&self) -> Option<IOKind> {
+        match self {
+            Self::Kind { lhs, .. } => Some(*lhs),
+            Self::Data { kind, .. }
+            | Self::Length { kind, .. }
+            | Self::Values { kind, .. }
+            | Self::Missing { kind, .. } => Some(*kind),
+            Self::Step { .. } => None,
+        }
+    }
+}
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let some_or_none = |x: bool| if x { "Some" } else { "None" };
+        match self {
+            Self::Kind { lhs, rhs } => write!(f, "{} != {}", lhs, rhs),
+            Self::Data { kind, lhs, rhs } => write!(
+                f,
+                "{}: {} data != {} data",
+                kind,
+                some_or_none(*lhs),
+                some_or_none(*rhs)
+            ),
+            Self::Length { kind, lhs, rhs } => {
+                write!(f, "{}: {} channels != {} channels", kind, lhs, rhs)
+            }
+            Self::Values {
+                kind,
+                n_channel,
+                channels,
+                max_abs_error,
+            } => {
+                write!(
+                    f,
+                    "{}: {} channels differ (max. abs. error: {:e}):",
+                    kind, n_channel, max_abs_error
+                )?;
+                for channel in channels {
+                    write!(
+                        f,
+                        " #{} ({} != {})",
+                        channel.index, channel.lhs, channel.rhs
+                    )?;
+                }
+                if *n_channel > channels.len() {
+                    write!(f, " ...")?;
+                }
+                Ok(())
+            }
+            Self::Missing { kind, lhs, rhs } => write!(
+                f,
+                "{}: {} in left hand side, {} in right hand side",
+                kind,
+                if *lhs { "present" } else { "missing" },
+                if *rhs { "present" } else { "missing" }
+            ),
+            Self::Step { lhs, rhs } => write!(
+                f,
+                "{} outputs != {} outputs",
+                some_or_none(*lhs),
+                some_or_none(*rhs)
+            ),
+        }
+    }
+}
+
+impl<T: PartialEq> IO<T> {
+    /// Checks that both the variants and the data are equal
+    pub fn eq_data(&self, other: &IO<T>) -> bool {
+        self.io_kind() == other.io_kind() && self.deref() == other.deref()
+    }
+}
+impl<T: AsRef<[f64]>> IO<T> {
+    /// Checks that the variants are equal and that the data are within `tolerance`
+    pub fn approx_eq(&self, other: &IO<T>, tolerance: Tolerance) -> bool {
+        self.diff(other, tolerance).is_none()
+    }
+    /// Returns the [`Difference`] with `other` or `None` if the data are within `tolerance`
+    pub fn diff(&self, other: &IO<T>, tolerance: Tolerance) -> Option<Difference> {
+        let kind = self.io_kind();
+        if kind != other.io_kind() {
+            return Some(Difference::Kind {
+                lhs: kind,
+                rhs: other.io_kind(),
+            });
+        }
+        let (lhs, rhs) = match (self.deref(), other.deref()) {
+            (Some(lhs), Some(rhs)) => (lhs.as_ref(), rhs.as_ref()),
+            (None, None) => return None,
+            (lhs, rhs) => {
+                return Some(Difference::Data {
+                    kind,
+                    lhs: lhs.is_some(),
+                    rhs: rhs.is_some(),
+                })
+            }
+        };
+        if lhs.len() != rhs.len() {
+            return Some(Difference::Length {
+                kind,
+                lhs: lhs.len(),
+                rhs: rhs.len(),
+            });
+        }
+        let mut n_channel = 0;
+        let mut channels = vec![];
+        let mut max_abs_error = 0f64;
+        lhs.iter()
+            .zip(rhs)
+            .enumerate()
+            .filter(|(_, (&lhs, &rhs))| !tolerance.is_close(lhs, rhs))
+            .for_each(|(index, (&lhs, &rhs))| {
+                n_channel += 1;
+                max_abs_error = max_abs_error.max((lhs - rhs).abs());
+                if channels.len() < MAX_CHANNELS {
+                    channels.push(Channel { index, lhs, rhs });
+                }
+            });
+        if n_channel == 0 {
+            None
+        } else {
+            Some(Difference::Values {
+                kind,
+                n_channel,
+                channels,
+                max_abs_error,
+            })
+        }
+    }
+}
+
+/// Data-aware comparison of [`Vec`] of [`IO`]
+///
+/// The [`IO`]s are paired by variant irrespective of their order in the [`Vec`]s
+pub trait IOVecCompare<T> {
+    /// Checks that both [`Vec`]s have the same variants with the same data
+    fn eq_data(&self, other: &[IO<T>]) -> bool
+    where
+        T: PartialEq;
+    /// Checks that both [`Vec`]s have the same variants with data within `tolerance`
+    fn approx_eq(&self, other: &[IO<T>], tolerance: Tolerance) -> bool
+    where
+        T: AsRef<[f64]>,
+    {
+        self.diff(other, tolerance).is_empty()
+    }
+    /// Returns the [`Difference`]s between both [`Vec`]s
+    fn diff(&self, other: &[IO<T>], tolerance: Tolerance) -> Vec<Difference>
+    where
+        T: AsRef<[f64]>;
+}
+impl<T> IOVecCompare<T> for [IO<T>] {
+    fn eq_data(&self, other: &[IO<T>]) -> bool
+    where
+        T: PartialEq,
+    {
+        let (pairs, lhs_only, rhs_only) = pair(self, other);
+        lhs_only.is_empty()
+            && rhs_only.is_empty()
+            && pairs.into_iter().all(|(lhs, rhs)| lhs.eq_data(rhs))
+    }
+    fn diff(&self, other: &[IO<T>], tolerance: Tolerance) -> Vec<Difference>
+    where
+        T: AsRef<[f64]>,
+    {
+        let (pairs, lhs_only, rhs_only) = pair(self, other);
+        pairs
+            .into_iter()
+            .filter_map(|(lhs, rhs)| lhs.diff(rhs, tolerance))
+            .chain(lhs_only.into_iter().map(|io| Difference::Missing {
+                kind: io.io_kind(),
+                lhs: true,
+                rhs: false,
+            }))
+            .chain(rhs_only.into_iter().map(|io| Difference::Missing {
+                kind: io.io_kind(),
+                lhs: false,
+                rhs: true,
+            }))
+            .collect()
+    }
+}
+
+pub(crate) type Pairs<'a, T> = (Vec<(&'a IO<T>, &'a IO<T>)>, Vec<&'a IO<T>>, Vec<&'a IO<T>>);
+// Pairs the `IO`s of the same variant, the n-th occurence in `lhs` with the n-th occurence in `rhs`
+pub(crate) fn pair<'a, T>(lhs: &'a [IO<T>], rhs: &'a [IO<T>]) -> Pairs<'a, T> {
+    let mut rhs: Vec<Option<&IO<T>>> = rhs.iter().map(Some).collect();
+    let mut pairs = vec![];
+    let mut lhs_only = vec![];
+    for l in lhs {
+        match rhs
+            .iter_mut()
+            .find(|r| r.is_some_and(|r| r.io_kind() == l.io_kind()))
+            .and_then(|r| r.take())
+        {
+            Some(r) => pairs.push((l, r)),
+            None => lhs_only.push(l),
+        }
+    }
+    (pairs, lhs_only, rhs.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ios;
+
+    #[test]
+    fn eq_data() {
+        let a = ios!(OSSM1Lcl(vec![1f64, 2f64]));
+        assert!(a == ios!(OSSM1Lcl(vec![3f64, 4f64])));
+        assert!(!a.eq_data(&ios!(OSSM1Lcl(vec![3f64, 4f64]))));
+        assert!(a.eq_data(&ios!(OSSM1Lcl(vec![1f64, 2f64]))));
+    }
+
+    #[test]
+    fn approx_eq() {
+        let a = ios!(OSSM1Lcl(vec![1f64, 100f64]));
+        let b = ios!(OSSM1Lcl(vec![1.001f64, 100.5f64]));
+        assert!(!a.approx_eq(&b, Tolerance::exact()));
+        assert!(!a.approx_eq(&b, Tolerance::abs(1e-2)));
+        assert!(a.approx_eq(&b, Tolerance::new(1e-2, 1e-2)));
+        match a.diff(&b, Tolerance::abs(1e-2)) {
+            Some(Difference::Values {
+                n_channel,
+                channels,
+                ..
+            }) => {
+                assert_eq!(n_channel, 1);
+                assert_eq!(channels[0].index, 1);
+            }
+            diff => panic!("unexpected difference: {:?}", diff),
+        }
+    }
+
+    #[test]
+    fn vec_diff() {
+        let a = ios!(
+            OSSM1Lcl(vec![1f64]),
+            MCM2Lcl6D(vec![2f64]),
+            SensorData(vec![3f64])
+        );
+        let b = ios!(
+            MCM2Lcl6D(vec![2f64]),
+            OSSM1Lcl(vec![1f64]),
+            SensorData(vec![3f64])
+        );
+        assert!(a.eq_data(&b));
+        let c = ios!(
+            MCM2Lcl6D(vec![2f64]),
+            OSSM1Lcl(vec![1f64, 0f64]),
+            Pssn(vec![3f64])
+        );
+        let diff = a.diff(&c, Tolerance::exact());
+        assert_eq!(diff.len(), 3);
+        assert_eq!(diff[0].kind(), Some(IOKind::OSSM1Lcl));
+        assert_eq!(
+            diff[1],
+            Difference::Missing {
+                kind: IOKind::SensorData,
+                lhs: true,
+                rhs: false
+            }
+        );
+    }
+}
@@ -1,7 +1,15 @@
-use std::error::Error;
+use crate::io::{IOError, IO};
+use std::{error::Error, fmt};
 
-pub type BoxError = Box<dyn Error>;
+pub type BoxError = Box<dyn Error + Send + Sync>;
 /// DOS trait methods error
+///
+/// The error may carry a [`Context`]: the name of the component, the step and the [`IO`] variant the error occured with.
+/// The context is set with [`with_component`](DOSIOSError::with_component), [`with_step`](DOSIOSError::with_step)
+/// and [`with_io`](DOSIOSError::with_io) and does not change the variant of the error.
+///
+/// The errors of [`init`](crate::Dos::init), [`reset`](crate::Dos::reset), [`finalize`](crate::Dos::finalize)
+/// and [`Checkpoint`](crate::Checkpoint) are [`Step`](DOSIOSError::Step) errors, the component is given by the context.
 pub enum DOSIOSError {
     /// [`inputs`](crate::Dos::inputs) error type
     Inputs(BoxError),
@@ -10,8 +18,139 @@ pub enum DOSIOSError {
     /// [`step`](crate::Dos::step) error type
     Step(BoxError),
 }
-impl std::fmt::Display for DOSIOSError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+
+/// [`DOSIOSError`] context
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Context {
+    /// Component name
+    pub component: Option<String>,
+    /// Step index
+    pub step: Option<usize>,
+    /// [`IO`] variant
+    pub io: Option<IO<()>>,
+}
+impl Context {
+    /// Returns true if no context is set
+    pub fn is_empty(&self) -> bool {
+        self.component.is_none() && self.step.is_none() && self.io.is_none()
+    }
+    // Sets the fields that are not already set
+    fn merge(&mut self, other: Context) {
+        if self.component.is_none() {
+            self.component = other.component;
+        }
+        if self.step.is_none() {
+            self.step = other.step;
+        }
+        if self.io.is_none() {
+            self.io = other.io;
+        }
+    }
+}
+impl fmt::Display for Context {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut fields = vec![];
+        if let Some(component) = &self.component {
+            fields.push(format!("component `{}`", component));
+        }
+        if let Some(step) = self.step {
+            fields.push(format!("step #{}", step));
+        }
+        if let Some(io) = &self.io {
+            fields.push(format!("IO {}", io.io_kind()));
+        }
+        write!(f, "{}", fields.join(", "))
+    }
+}
+
+// Adds a context to an error, skipped in the chain of errors
+struct WithContext {
+    context: Context,
+    error: BoxError,
+}
+impl fmt::Display for WithContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.error.fmt(f)
+    }
+}
+impl fmt::Debug for WithContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.error, f)
+    }
+}
+impl Error for WithContext {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.error.source()
+    }
+}
+
+impl DOSIOSError {
+    // Returns the error wrapped into the variant
+    fn error(&self) -> &BoxError {
+        match self {
+            Self::Inputs(error) => error,
+            Self::Outputs(error) => error,
+            Self::Step(error) => error,
+        }
+    }
+    // Applies `f` to the error wrapped into the variant
+    fn map<F: FnOnce(BoxError) -> BoxError>(self, f: F) -> Self {
+        match self {
+            Self::Inputs(error) => Self::Inputs(f(error)),
+            Self::Outputs(error) => Self::Outputs(f(error)),
+            Self::Step(error) => Self::Step(f(error)),
+        }
+    }
+    /// Adds the context to the error
+    ///
+    /// The fields of the context that are already set are not overwritten
+    pub fn with_context(self, context: Context) -> Self {
+        self.map(|error| match error.downcast::<WithContext>() {
+            Ok(mut error) => {
+                error.context.merge(context);
+                error
+            }
+            Err(error) => Box::new(WithContext { context, error }),
+        })
+    }
+    /// Sets the name of the component the error occured with
+    pub fn with_component<S: Into<String>>(self, component: S) -> Self {
+        self.with_context(Context {
+            component: Some(component.into()),
+            ..Default::default()
+        })
+    }
+    /// Sets the step the error occured at
+    pub fn with_step(self, step: usize) -> Self {
+        self.with_context(Context {
+            step: Some(step),
+            ..Default::default()
+        })
+    }
+    /// Sets the [`IO`] variant the error occured with
+    pub fn with_io<T>(self, io: &IO<T>) -> Self {
+        self.with_context(Context {
+            io: Some(io.io_kind().io(None)),
+            ..Default::default()
+        })
+    }
+    /// Returns the error context, if any
+    pub fn context(&self) -> Option<&Context> {
+        self.error()
+            .downcast_ref::<WithContext>()
+            .map(|error| &error.context)
+    }
+    /// Returns the chain of errors, starting with `self`
+    pub fn chain(&self) -> Chain<'_> {
+        Chain {
+            next: Some(self as &(dyn Error + 'static)),
+        }
+    }
+    /// Returns a report of the error and of all its causes
+    pub fn report(&self) -> Report<'_> {
+        Report(self)
+    }
+    fn message(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Inputs(_) => write!(f, "An error occured with the Inputs method from DOS trait"),
             Self::Outputs(_) => {
@@ -19,23 +158,142 @@ impl std::fmt::Display for DOSIOSError {
             }
             Self::Step(_) => write!(f, "An error occured with the Step method from DOS trait"),
         }?;
+        match self.context() {
+            Some(context) if !context.is_empty() => write!(f, " ({})", context),
+            _ => Ok(()),
+        }
+    }
+}
+impl fmt::Display for DOSIOSError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.message(f)?;
         if let Some(error) = self.source() {
             write!(f, "\nCaused by: {}", error)?;
         }
         Ok(())
     }
 }
-impl std::fmt::Debug for DOSIOSError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        <DOSIOSError as std::fmt::Display>::fmt(self, f)
+impl fmt::Debug for DOSIOSError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        <DOSIOSError as fmt::Display>::fmt(self, f)
     }
 }
 impl Error for DOSIOSError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
-        match self {
-            Self::Inputs(error) => Some(error.as_ref()),
-            Self::Outputs(error) => Some(error.as_ref()),
-            Self::Step(error) => Some(error.as_ref()),
+        match self.error().downcast_ref::<WithContext>() {
+            Some(error) => Some(error.error.as_ref()),
+            None => Some(self.error().as_ref()),
+        }
+    }
+}
+
+/// Iterator over the chain of errors of a [`DOSIOSError`]
+pub struct Chain<'a> {
+    next: Option<&'a (dyn Error + 'static)>,
+}
+impl<'a> Iterator for Chain<'a> {
+    type Item = &'a (dyn Error + 'static);
+    fn next(&mut self) -> Option<Self::Item> {
+        let error = self.next?;
+        self.next = error.source();
+        Some(error)
+    }
+}
+
+/// Report of a [`DOSIOSError`] and of all its causes, one per line
+pub struct Report<'a>(&'a DOSIOSError);
+impl<'a> fmt::Display for Report<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.message(f)?;
+        let causes: Vec<_> = self.0.chain().skip(1).collect();
+        if !causes.is_empty() {
+            write!(f, "\nCaused by:")?;
+            for (i, error) in causes.into_iter().enumerate() {
+                write!(f, "\n  {}: {}", i, error)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Converts an [`IOError`] into an [`Inputs`](DOSIOSError::Inputs) error with the [`IO`] variant as context
+///
+/// [`IOError`]s are met when the inputs are read: [`outputs`](crate::Dos::outputs) returns an `Option` and cannot fail.
+/// An [`IOError`] of [`step`](crate::Dos::step) is converted explicitly, e.g. `DOSIOSError::Step(error.into())`.
+impl<T: fmt::Debug + Send + Sync + 'static> From<IOError<T>> for DOSIOSError {
+    fn from(error: IOError<T>) -> Self {
+        let io = error.io_kind().io::<()>(None);
+        Self::Inputs(Box::new(error)).with_io(&io)
+    }
+}
+/// Converts an I/O error into a [`Step`](DOSIOSError::Step) error
+///
+/// Components do their I/O in [`init`](crate::Dos::init), opening files or sockets,
+/// in [`step`](crate::Dos::step) and in [`finalize`](crate::Dos::finalize), flushing and closing them.
+/// The component is added with [`with_component`](DOSIOSError::with_component).
+impl From<std::io::Error> for DOSIOSError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Step(Box::new(error))
+    }
+}
+
+// Returns the message of a panic caught at the boundary of a thread or with C
+pub(crate) fn panic_message(payload: &(dyn std::any::Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(|s| s.as_str()))
+        .unwrap_or("unknown panic")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ios;
+
+    #[test]
+    fn context() {
+        fn is_send_sync<T: Send + Sync + 'static>(_: &T) {}
+        let error: DOSIOSError = IOError::Missing(ios!(Pssn(vec![1f64]))).into();
+        is_send_sync(&error);
+        let error = error.with_step(3).with_component("optics").with_step(5);
+        assert!(matches!(error, DOSIOSError::Inputs(_)));
+        assert_eq!(
+            error.context(),
+            Some(&Context {
+                component: Some("optics".into()),
+                step: Some(3),
+                io: Some(ios!(Pssn)),
+            })
+        );
+        assert_eq!(error.chain().count(), 2);
+        let report = error.report().to_string();
+        assert!(report.starts_with(
+            "An error occured with the Inputs method from DOS trait (component `optics`, step #3, IO Pssn)"
+        ));
+        assert!(report.ends_with("is missing"), "{}", report);
+
+        let error: DOSIOSError = std::io::Error::from(std::io::ErrorKind::NotFound).into();
+        let error = error.with_component("logger");
+        assert!(matches!(error, DOSIOSError::Step(_)));
+        assert_eq!(
+            error
+                .context()
+                .and_then(|context| context.component.as_deref()),
+            Some("logger")
+        );
+    }
+
+    #[test]
+    fn exhaustive() {
+        // the variants are matched without a wildcard arm
+        fn method(error: &DOSIOSError) -> &'static str {
+            match error {
+                DOSIOSError::Inputs(_) => "inputs",
+                DOSIOSError::Outputs(_) => "outputs",
+                DOSIOSError::Step(_) => "step",
+            }
         }
+        assert_eq!(method(&DOSIOSError::Outputs("failure".into())), "outputs");
     }
 }
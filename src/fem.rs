@@ -0,0 +1,841 @@
+//! Second-order modal FEM model
+//!
+//! A [`ModalModel`] holds the GMT FEM in modal form:
+//! the eigenfrequencies, the proportional damping ratios, the matrix transforming the inputs into modal forces
+//! and the matrix transforming the modal displacements into the outputs.
+//! The model is loaded from the same `modal_state_space_model_2ndOrder` file the `IO` variants are built from,
+//! either the zip archive of parquet tables (feature `fem-prqt`) or the `.rs.mat` file (feature `fem-hdf5`).
+//!
+//! [`ModalSystem`] is the [`Dos`] component integrating the model,
+//! each mode being discretized exactly (zero-order hold) or with the Tustin transform.
+//! The inputs and the outputs of the component are selected with [`IO`] variants,
+//! each variant being mapped onto the columns (inputs) or rows (outputs) of the FEM group of the same name.
+
+use crate::{
+    blocks::take_or_err,
+    io::IOKind,
+    lti::{self, ContinuousStateSpace, Discretization, LtiError, Rows},
+    DOSIOSError, Dos, IOTags, IO,
+};
+use nalgebra::{DMatrix, DVector};
+use serde::{Deserialize, Serialize};
+use std::{f64::consts::PI, fmt, ops::Range, path::Path};
+
+// FEM inputs/outputs manifest and naming of the `IO` variants, shared with `dosio-macros`
+#[path = "../dosio-macros/src/manifest.rs"]
+#[allow(dead_code)]
+pub(crate) mod manifest;
+
+/// FEM error type
+#[derive(Debug)]
+pub enum FemError {
+    /// Cannot read the FEM model file
+    Read(String),
+    /// Cannot write the FEM model file
+    Write(String),
+    /// The model matrices do not match the model inputs, outputs or modes
+    Dimension(String),
+    /// The variant is not an input or an output of the model
+    Missing(IOKind),
+    /// The modes cannot be discretized
+    Lti(LtiError),
+}
+impl fmt::Display for FemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read(msg) => write!(f, "Cannot read the FEM model: {}", msg),
+            Self::Write(msg) => write!(f, "Cannot write the FEM model: {}", msg),
+            Self::Dimension(msg) => write!(f, "FEM dimension mismatch: {}", msg),
+            Self::Missing(kind) => write!(f, "{} is not a FEM input or output", kind),
+            Self::Lti(e) => write!(f, "FEM discretization error: {}", e),
+        }
+    }
+}
+impl std::error::Error for FemError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Lti(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl From<LtiError> for FemError {
+    fn from(e: LtiError) -> Self {
+        Self::Lti(e)
+    }
+}
+
+/// FEM inputs or outputs group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FemGroup {
+    /// Group name in the FEM model
+    pub group: String,
+    /// Number of degrees of freedom in the group
+    pub size: usize,
+}
+impl FemGroup {
+    /// Creates a new group
+    pub fn new<S: Into<String>>(group: S, size: usize) -> Self {
+        Self {
+            group: group.into(),
+            size,
+        }
+    }
+    /// Returns the matching [`IO`] variant, if any
+    pub fn io_kind(&self) -> Option<IOKind> {
+        manifest::rename(&self.group).parse().ok()
+    }
+}
+
+/// Second-order modal model
+///
+/// The matrices are stored row-wise
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModalModel {
+    /// Eigenfrequencies in Hz
+    pub eigen_frequencies: Vec<f64>,
+    /// Proportional damping ratios
+    pub damping: Vec<f64>,
+    /// Inputs to modal forces matrix (`n_mode x n_input`)
+    pub inputs_to_modal_forces: Vec<f64>,
+    /// Modal displacements to outputs matrix (`n_output x n_mode`)
+    pub modal_disp_to_outputs: Vec<f64>,
+    /// Inputs groups, in the order of the matrix columns
+    pub inputs: Vec<FemGroup>,
+    /// Outputs groups, in the order of the matrix rows
+    pub outputs: Vec<FemGroup>,
+}
+impl ModalModel {
+    /// Returns the number of modes
+    pub fn n_mode(&self) -> usize {
+        self.eigen_frequencies.len()
+    }
+    /// Returns the number of inputs
+    pub fn n_input(&self) -> usize {
+        self.inputs.iter().map(|group| group.size).sum()
+    }
+    /// Returns the number of outputs
+    pub fn n_output(&self) -> usize {
+        self.outputs.iter().map(|group| group.size).sum()
+    }
+    /// Checks the consistency of the model dimensions
+    pub fn check(&self) -> Result<&Self, FemError> {
+        let (n_mode, n_input, n_output) = (self.n_mode(), self.n_input(), self.n_output());
+        if self.damping.len() != n_mode
+            || self.inputs_to_modal_forces.len() != n_mode * n_input
+            || self.modal_disp_to_outputs.len() != n_output * n_mode
+        {
+            return Err(FemError::Dimension(format!(
+                "{} modes, {} inputs and {} outputs but {} damping ratios, {} inputs to modal forces and {} modal displacements to outputs coefficients",
+                n_mode,
+                n_input,
+                n_output,
+                self.damping.len(),
+                self.inputs_to_modal_forces.len(),
+                self.modal_disp_to_outputs.len()
+            )));
+        }
+        Ok(self)
+    }
+    fn range(groups: &[FemGroup], kind: IOKind) -> Result<Range<usize>, FemError> {
+        let mut start = 0;
+        for group in groups {
+            if group.io_kind() == Some(kind) {
+                return Ok(start..start + group.size);
+            }
+            start += group.size;
+        }
+        Err(FemError::Missing(kind))
+    }
+    /// Returns the matrix columns of the input variant
+    pub fn inputs_range(&self, kind: IOKind) -> Result<Range<usize>, FemError> {
+        Self::range(&self.inputs, kind)
+    }
+    /// Returns the matrix rows of the output variant
+    pub fn outputs_range(&self, kind: IOKind) -> Result<Range<usize>, FemError> {
+        Self::range(&self.outputs, kind)
+    }
+    /// Removes the modes with an eigenfrequency larger than `max_frequency` in Hz
+    pub fn truncate(&mut self, max_frequency: f64) -> &mut Self {
+        let keep: Vec<usize> = (0..self.n_mode())
+            .filter(|&i| self.eigen_frequencies[i] <= max_frequency)
+            .collect();
+        *self = self.select(&keep);
+        self
+    }
+    // Returns the model reduced to the given modes
+    fn select(&self, modes: &[usize]) -> Self {
+        let (n_mode, n_input) = (self.n_mode(), self.n_input());
+        Self {
+            eigen_frequencies: modes.iter().map(|&i| self.eigen_frequencies[i]).collect(),
+            damping: modes.iter().map(|&i| self.damping[i]).collect(),
+            inputs_to_modal_forces: modes
+                .iter()
+                .flat_map(|&i| self.inputs_to_modal_forces[i * n_input..(i + 1) * n_input].to_vec())
+                .collect(),
+            modal_disp_to_outputs: self
+                .modal_disp_to_outputs
+                .chunks(n_mode)
+                .flat_map(|row| modes.iter().map(move |&i| row[i]))
+                .collect(),
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+        }
+    }
+    // Inputs to modal forces and modal displacements to outputs matrices for the given variants
+    fn matrices(
+        &self,
+        inputs: &[IOKind],
+        outputs: &[IOKind],
+    ) -> Result<(DMatrix<f64>, DMatrix<f64>), FemError> {
+        self.check()?;
+        let columns = inputs
+            .iter()
+            .map(|&kind| self.inputs_range(kind))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        let rows = outputs
+            .iter()
+            .map(|&kind| self.outputs_range(kind))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        let (n_mode, n_input) = (self.n_mode(), self.n_input());
+        Ok((
+            DMatrix::from_fn(n_mode, columns.len(), |i, j| {
+                self.inputs_to_modal_forces[i * n_input + columns[j]]
+            }),
+            DMatrix::from_fn(rows.len(), n_mode, |i, j| {
+                self.modal_disp_to_outputs[rows[i] * n_mode + j]
+            }),
+        ))
+    }
+    // Static gain between the given variants, the rigid body modes are discarded
+    fn static_gain_matrix(
+        &self,
+        inputs: &[IOKind],
+        outputs: &[IOKind],
+    ) -> Result<DMatrix<f64>, FemError> {
+        let (phi_in, phi_out) = self.matrices(inputs, outputs)?;
+        let mut gain = DMatrix::zeros(phi_out.nrows(), phi_in.ncols());
+        for (i, &frequency) in self.eigen_frequencies.iter().enumerate() {
+            if frequency > 0f64 {
+                let w2 = (2. * PI * frequency).powi(2);
+                gain += phi_out.column(i) * phi_in.row(i) / w2;
+            }
+        }
+        Ok(gain)
+    }
+    /// Returns the static gain between the given variants
+    ///
+    /// The rigid body modes (zero eigenfrequency) are discarded
+    pub fn static_gain(&self, inputs: &[IO<()>], outputs: &[IO<()>]) -> Result<Rows, FemError> {
+        let inputs: Vec<_> = inputs.iter().map(|io| io.io_kind()).collect();
+        let outputs: Vec<_> = outputs.iter().map(|io| io.io_kind()).collect();
+        let gain = self.static_gain_matrix(&inputs, &outputs)?;
+        Ok(gain
+            .row_iter()
+            .map(|row| row.iter().cloned().collect())
+            .collect())
+    }
+    /// Loads a model from either a zip archive (`.zip`), a mat file (`.mat`) or a pickle file (`.pkl`)
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, FemError> {
+        let path = path.as_ref();
+        match path.extension().and_then(|x| x.to_str()) {
+            #[cfg(feature = "fem-prqt")]
+            Some("zip") => Self::from_zip(path),
+            #[cfg(feature = "fem-hdf5")]
+            Some("mat") => Self::from_mat(path),
+            Some("pkl") => lti::load(path).map_err(|e| FemError::Read(e.to_string())),
+            _ => Err(FemError::Read(format!(
+                "unsupported file format: {:?}",
+                path
+            ))),
+        }
+    }
+    /// Loads the `modal_state_space_model_2ndOrder` model of a FEM repository, the directory given as `FEM_REPO`
+    pub fn from_repo<P: AsRef<Path>>(fem_repo: P) -> Result<Self, FemError> {
+        let fem_repo = fem_repo.as_ref();
+        let mut error = FemError::Read(format!(
+            "no modal_state_space_model_2ndOrder model in {:?}",
+            fem_repo
+        ));
+        for model in [
+            "modal_state_space_model_2ndOrder.zip",
+            "modal_state_space_model_2ndOrder.rs.mat",
+        ]
+        .iter()
+        .map(|model| fem_repo.join(model))
+        .filter(|model| model.exists())
+        {
+            // falls back on the other file if the format of the first one is not enabled
+            match Self::load(model) {
+                Ok(model) => return Ok(model),
+                Err(e) => error = e,
+            }
+        }
+        Err(error)
+    }
+}
+
+#[cfg(feature = "fem-prqt")]
+mod prqt {
+    use super::{FemError, FemGroup, ModalModel};
+    use arrow::{
+        array::{Array, Float64Array, ListArray, StringArray},
+        record_batch::RecordBatch,
+    };
+    use parquet::{
+        arrow::{ArrowReader, ParquetFileArrowReader},
+        file::reader::SerializedFileReader,
+        util::cursor::SliceableCursor,
+    };
+    use std::{fs::File, io::Read, path::Path, sync::Arc};
+    use zip::ZipArchive;
+
+    fn error<E: std::fmt::Display>(e: E) -> FemError {
+        FemError::Read(e.to_string())
+    }
+
+    // Reads the parquet table `name` of the archive
+    fn table(zip_file: &mut ZipArchive<File>, name: &str) -> Result<RecordBatch, FemError> {
+        let mut contents: Vec<u8> = Vec::new();
+        zip_file
+            .by_name(name)
+            .map_err(error)?
+            .read_to_end(&mut contents)
+            .map_err(error)?;
+        let mut arrow_reader = ParquetFileArrowReader::new(Arc::new(
+            SerializedFileReader::new(SliceableCursor::new(Arc::new(contents))).map_err(error)?,
+        ));
+        let records = arrow_reader
+            .get_record_reader(2048)
+            .map_err(error)?
+            .collect::<Result<Vec<RecordBatch>, arrow::error::ArrowError>>()
+            .map_err(error)?;
+        let schema = records
+            .first()
+            .ok_or_else(|| FemError::Read(format!("no record in {}", name)))?
+            .schema();
+        RecordBatch::concat(&schema, &records).map_err(error)
+    }
+    fn column<'a, T: 'static>(table: &'a RecordBatch, name: &str) -> Result<&'a T, FemError> {
+        let (idx, _) = table
+            .schema()
+            .column_with_name(name)
+            .ok_or_else(|| FemError::Read(format!("no column {}", name)))?;
+        table
+            .column(idx)
+            .as_any()
+            .downcast_ref::<T>()
+            .ok_or_else(|| FemError::Read(format!("invalid column {}", name)))
+    }
+    // Each row is a degree of freedom and the rows are ordered by group
+    fn groups(table: &RecordBatch) -> Result<Vec<FemGroup>, FemError> {
+        let mut groups: Vec<FemGroup> = vec![];
+        for group in column::<StringArray>(table, "group")?.iter() {
+            let group = group.ok_or_else(|| FemError::Read("missing group name".into()))?;
+            match groups.last_mut() {
+                Some(last) if last.group == group => last.size += 1,
+                _ => groups.push(FemGroup::new(group, 1)),
+            }
+        }
+        Ok(groups)
+    }
+    // Concatenates the lists of each row
+    fn rows(table: &RecordBatch, name: &str) -> Result<Vec<f64>, FemError> {
+        let list = column::<ListArray>(table, name)?;
+        let mut data = vec![];
+        for i in 0..list.len() {
+            let row = list.value(i);
+            data.extend_from_slice(
+                row.as_any()
+                    .downcast_ref::<Float64Array>()
+                    .ok_or_else(|| FemError::Read(format!("invalid column {}", name)))?
+                    .values(),
+            );
+        }
+        Ok(data)
+    }
+
+    impl ModalModel {
+        /// Loads the model from the `modal_state_space_model_2ndOrder.zip` archive
+        ///
+        /// The archive contains the tables `modal_state_space_model_2ndOrder_{in,out,mat}.parquet`:
+        ///  - the `in` and `out` tables have one row per degree of freedom with its `group` name,
+        ///  - the `mat` table has one row per mode with its `eigenfrequencies`, `proportionalDampingVec`,
+        ///    `inputs2ModalF` (row of the inputs to modal forces matrix) and `modalDisp2Outputs` (column of the modal displacements to outputs matrix).
+        pub fn from_zip<P: AsRef<Path>>(path: P) -> Result<Self, FemError> {
+            let mut zip_file = ZipArchive::new(File::open(path).map_err(error)?).map_err(error)?;
+            let inputs = groups(&table(
+                &mut zip_file,
+                "modal_state_space_model_2ndOrder_in.parquet",
+            )?)?;
+            let outputs = groups(&table(
+                &mut zip_file,
+                "modal_state_space_model_2ndOrder_out.parquet",
+            )?)?;
+            let mat = table(
+                &mut zip_file,
+                "modal_state_space_model_2ndOrder_mat.parquet",
+            )?;
+            let eigen_frequencies = column::<Float64Array>(&mat, "eigenfrequencies")?
+                .values()
+                .to_vec();
+            let damping = column::<Float64Array>(&mat, "proportionalDampingVec")?
+                .values()
+                .to_vec();
+            let inputs_to_modal_forces = rows(&mat, "inputs2ModalF")?;
+            // stored column-wise
+            let modal_disp_to_outputs_t = rows(&mat, "modalDisp2Outputs")?;
+            let n_mode = eigen_frequencies.len();
+            let n_output = modal_disp_to_outputs_t.len() / n_mode.max(1);
+            let modal_disp_to_outputs = (0..n_output)
+                .flat_map(|i| {
+                    let data = &modal_disp_to_outputs_t;
+                    (0..n_mode).map(move |j| data[j * n_output + i])
+                })
+                .collect();
+            let model = Self {
+                eigen_frequencies,
+                damping,
+                inputs_to_modal_forces,
+                modal_disp_to_outputs,
+                inputs,
+                outputs,
+            };
+            model.check()?;
+            Ok(model)
+        }
+    }
+}
+
+#[cfg(feature = "fem-hdf5")]
+mod mat {
+    use super::{FemError, FemGroup, ModalModel};
+    use std::path::Path;
+
+    fn error(e: hdf5::Error) -> FemError {
+        FemError::Read(e.to_string())
+    }
+    // Reads the groups in the `MATLAB_fields` attribute
+    fn groups(h5: &hdf5::File, name: &str) -> Result<Vec<FemGroup>, FemError> {
+        let fem_io = h5.group(name).map_err(error)?;
+        let data: Vec<hdf5::types::VarLenArray<hdf5::types::FixedAscii<1>>> = fem_io
+            .attr("MATLAB_fields")
+            .map_err(error)?
+            .read_raw()
+            .map_err(error)?;
+        data.into_iter()
+            .map(|v| -> Result<FemGroup, FemError> {
+                let group = v.iter().map(|x| x.as_str()).collect::<String>();
+                // one table row per degree of freedom
+                let size = fem_io
+                    .dataset(&group)
+                    .map_err(error)?
+                    .shape()
+                    .into_iter()
+                    .max()
+                    .filter(|&size| size > 0)
+                    .ok_or_else(|| FemError::Read(format!("The table of {} is empty", group)))?;
+                Ok(FemGroup { group, size })
+            })
+            .collect()
+    }
+
+    impl ModalModel {
+        /// Loads the model from the `modal_state_space_model_2ndOrder.rs.mat` file
+        ///
+        /// The matrices `inputs2ModalF` and `modalDisp2Outputs` are stored column-wise (MATLAB layout)
+        pub fn from_mat<P: AsRef<Path>>(path: P) -> Result<Self, FemError> {
+            let h5 = hdf5::File::open(path).map_err(error)?;
+            let read = |name: &str| -> Result<Vec<f64>, FemError> {
+                h5.dataset(name)
+                    .map_err(error)?
+                    .read_raw::<f64>()
+                    .map_err(error)
+            };
+            let inputs = groups(&h5, "fem_inputs")?;
+            let outputs = groups(&h5, "fem_outputs")?;
+            let eigen_frequencies = read("eigenfrequencies")?;
+            let damping = read("proportionalDampingVec")?;
+            let n_mode = eigen_frequencies.len();
+            let n_input: usize = inputs.iter().map(|group| group.size).sum();
+            let n_output: usize = outputs.iter().map(|group| group.size).sum();
+            let column_wise = |data: Vec<f64>, n_row: usize, n_col: usize| -> Vec<f64> {
+                (0..n_row)
+                    .flat_map(|i| (0..n_col).map(move |j| (i, j)))
+                    .map(|(i, j)| data[j * n_row + i])
+                    .collect()
+            };
+            let inputs_to_modal_forces = read("inputs2ModalF")?;
+            let modal_disp_to_outputs = read("modalDisp2Outputs")?;
+            if inputs_to_modal_forces.len() != n_mode * n_input
+                || modal_disp_to_outputs.len() != n_output * n_mode
+            {
+                return Err(FemError::Dimension(
+                    "the FEM matrices do not match the inputs, outputs and modes".into(),
+                ));
+            }
+            let model = Self {
+                inputs_to_modal_forces: column_wise(inputs_to_modal_forces, n_mode, n_input),
+                modal_disp_to_outputs: column_wise(modal_disp_to_outputs, n_output, n_mode),
+                eigen_frequencies,
+                damping,
+                inputs,
+                outputs,
+            };
+            model.check()?;
+            Ok(model)
+        }
+    }
+}
+
+/// [`ModalSystem`] configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModalConfig {
+    /// Sampling period in seconds
+    pub sampling_period: f64,
+    /// Input variants
+    pub inputs: Vec<IOKind>,
+    /// Output variants
+    pub outputs: Vec<IOKind>,
+    /// Modes discretization
+    pub discretization: Discretization,
+    /// Largest eigenfrequency in Hz of the modes that are kept
+    pub max_frequency: Option<f64>,
+    /// Adds the static gain of the truncated modes as a direct feedthrough
+    pub static_gain: bool,
+}
+impl ModalConfig {
+    /// Creates a new configuration with exact discretization and all the modes
+    pub fn new(sampling_period: f64, inputs: Vec<IO<()>>, outputs: Vec<IO<()>>) -> Self {
+        Self {
+            sampling_period,
+            inputs: inputs.into_iter().map(|io| io.io_kind()).collect(),
+            outputs: outputs.into_iter().map(|io| io.io_kind()).collect(),
+            discretization: Discretization::ZeroOrderHold,
+            max_frequency: None,
+            static_gain: false,
+        }
+    }
+    /// Sets the modes discretization: [`Discretization::ZeroOrderHold`] (exact) or [`Discretization::Tustin`]
+    pub fn discretization(self, discretization: Discretization) -> Self {
+        Self {
+            discretization,
+            ..self
+        }
+    }
+    /// Keeps only the modes with an eigenfrequency smaller than or equal to `max_frequency` in Hz
+    pub fn max_frequency(self, max_frequency: f64) -> Self {
+        Self {
+            max_frequency: Some(max_frequency),
+            ..self
+        }
+    }
+    /// Compensates the truncated modes with their static gain
+    pub fn static_gain(self) -> Self {
+        Self {
+            static_gain: true,
+            ..self
+        }
+    }
+}
+
+/// Discrete second-order modal system
+///
+/// For each mode, the state `x = [q, q']` is updated according to `x[k+1] = A x[k] + B f[k]`
+/// and the modal displacement is `z[k] = C x[k] + D f[k]`, `f` being the modal force
+pub struct ModalSystem {
+    config: ModalConfig,
+    // inputs to modal forces
+    phi_in: DMatrix<f64>,
+    // modal displacements to outputs
+    phi_out: DMatrix<f64>,
+    // per mode discrete model
+    a: Vec<[f64; 4]>,
+    b: Vec<[f64; 2]>,
+    c: Vec<[f64; 2]>,
+    d: Vec<f64>,
+    // static gain of the truncated modes
+    feedthrough: Option<DMatrix<f64>>,
+    output_sizes: Vec<usize>,
+    x: Vec<[f64; 2]>,
+    u: Option<DVector<f64>>,
+    y: Option<DVector<f64>>,
+}
+impl ModalSystem {
+    /// Creates a new modal system from the model
+    pub fn new(model: &ModalModel, config: ModalConfig) -> Result<Self, FemError> {
+        let truncated = match config.max_frequency {
+            Some(max_frequency) => model.clone().truncate(max_frequency).clone(),
+            None => model.clone(),
+        };
+        let (phi_in, phi_out) = truncated.matrices(&config.inputs, &config.outputs)?;
+        let feedthrough = if config.static_gain {
+            Some(
+                model.static_gain_matrix(&config.inputs, &config.outputs)?
+                    - truncated.static_gain_matrix(&config.inputs, &config.outputs)?,
+            )
+        } else {
+            None
+        };
+        let n_mode = truncated.n_mode();
+        let (mut a, mut b, mut c, mut d) = (
+            Vec::with_capacity(n_mode),
+            Vec::with_capacity(n_mode),
+            Vec::with_capacity(n_mode),
+            Vec::with_capacity(n_mode),
+        );
+        for (frequency, zeta) in truncated.eigen_frequencies.iter().zip(&truncated.damping) {
+            let w = 2. * PI * frequency;
+            let mode = ContinuousStateSpace {
+                a: vec![vec![0., 1.], vec![-w * w, -2. * zeta * w]],
+                b: vec![vec![0.], vec![1.]],
+                c: vec![vec![1., 0.]],
+                d: vec![vec![0.]],
+            }
+            .discretize(config.sampling_period, config.discretization)?;
+            a.push([mode.a[0][0], mode.a[0][1], mode.a[1][0], mode.a[1][1]]);
+            b.push([mode.b[0][0], mode.b[1][0]]);
+            c.push([mode.c[0][0], mode.c[0][1]]);
+            d.push(mode.d[0][0]);
+        }
+        let output_sizes = config
+            .outputs
+            .iter()
+            .map(|&kind| model.outputs_range(kind).map(|range| range.len()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            config,
+            phi_in,
+            phi_out,
+            a,
+            b,
+            c,
+            d,
+            feedthrough,
+            output_sizes,
+            x: vec![[0f64; 2]; n_mode],
+            u: None,
+            y: None,
+        })
+    }
+    /// Returns the number of modes
+    pub fn n_mode(&self) -> usize {
+        self.x.len()
+    }
+    /// Returns the configuration
+    pub fn config(&self) -> &ModalConfig {
+        &self.config
+    }
+}
+impl Iterator for ModalSystem {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        let u = self.u.take()?;
+        let f = &self.phi_in * &u;
+        let z = DVector::from_iterator(
+            self.x.len(),
+            self.x
+                .iter()
+                .zip(&self.c)
+                .zip(&self.d)
+                .zip(f.iter())
+                .map(|(((x, c), d), f)| c[0] * x[0] + c[1] * x[1] + d * f),
+        );
+        let mut y = &self.phi_out * z;
+        if let Some(feedthrough) = &self.feedthrough {
+            y += feedthrough * &u;
+        }
+        self.y = Some(y);
+        self.x
+            .iter_mut()
+            .zip(&self.a)
+            .zip(&self.b)
+            .zip(f.iter())
+            .for_each(|(((x, a), b), f)| {
+                *x = [
+                    a[0] * x[0] + a[1] * x[1] + b[0] * f,
+                    a[2] * x[0] + a[3] * x[1] + b[1] * f,
+                ]
+            });
+        Some(())
+    }
+}
+impl Dos for ModalSystem {
+    type Input = Vec<f64>;
+    type Output = Vec<f64>;
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        let y = self.y.as_ref()?;
+        let mut i = 0;
+        Some(
+            self.config
+                .outputs
+                .iter()
+                .zip(&self.output_sizes)
+                .map(|(kind, &n)| {
+                    let data = y.rows(i, n).iter().cloned().collect();
+                    i += n;
+                    kind.io(Some(data))
+                })
+                .collect(),
+        )
+    }
+    fn inputs(&mut self, mut data: Option<Vec<IO<Vec<f64>>>>) -> Result<&mut Self, DOSIOSError> {
+        let mut u = vec![];
+        for kind in &self.config.inputs {
+            u.extend(take_or_err(&mut data, *kind)?);
+        }
+        if u.len() != self.phi_in.ncols() {
+            return Err(DOSIOSError::Inputs(
+                format!(
+                    "the inputs have {} channels but the FEM has {} inputs",
+                    u.len(),
+                    self.phi_in.ncols()
+                )
+                .into(),
+            ));
+        }
+        self.u = Some(DVector::from_vec(u));
+        Ok(self)
+    }
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.x.iter_mut().for_each(|x| *x = [0f64; 2]);
+        self.u = None;
+        self.y = None;
+        Ok(self)
+    }
+}
+impl IOTags for ModalSystem {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        self.config
+            .outputs
+            .iter()
+            .map(|kind| kind.io(None))
+            .collect()
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        self.config
+            .inputs
+            .iter()
+            .map(|kind| kind.io(None))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ios;
+
+    fn model() -> ModalModel {
+        ModalModel {
+            eigen_frequencies: vec![1., 5., 40.],
+            damping: vec![0.5, 0.7, 0.6],
+            inputs_to_modal_forces: vec![
+                0.1, 0.2, 1.0, //
+                -0.3, 0.4, 0.5, //
+                0.7, -0.2, 0.3,
+            ],
+            modal_disp_to_outputs: vec![
+                1.0, 2.0, 0.5, //
+                0.5, -1.0, 1.5, //
+                -0.2, 0.3, 2.0,
+            ],
+            inputs: vec![
+                FemGroup::new("OSS_ElDrive_Torque", 2),
+                FemGroup::new("OSS_AzDrive_Torque", 1),
+            ],
+            outputs: vec![
+                FemGroup::new("OSS_ElEncoder_Angle", 1),
+                FemGroup::new("OSS_AzEncoder_Angle", 2),
+            ],
+        }
+    }
+    fn run(system: &mut ModalSystem, u: Vec<IO<Vec<f64>>>, n: usize) -> Vec<Vec<IO<Vec<f64>>>> {
+        (0..n)
+            .map(|_| system.in_step_out(Some(u.clone())).unwrap().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn exact_step_response() {
+        let model = model();
+        let ts = 1e-3;
+        let mut system = ModalSystem::new(
+            &model,
+            ModalConfig::new(
+                ts,
+                vec![ios!(OSSAzDriveTorque)],
+                vec![ios!(OSSElEncoderAngle)],
+            ),
+        )
+        .unwrap();
+        // step response of a damped oscillator to a unit force
+        let step = |w: f64, zeta: f64, t: f64| {
+            let wd = w * (1. - zeta * zeta).sqrt();
+            (1. - (-zeta * w * t).exp()
+                * ((wd * t).cos() + zeta / (1. - zeta * zeta).sqrt() * (wd * t).sin()))
+                / (w * w)
+        };
+        run(&mut system, vec![ios!(OSSAzDriveTorque(vec![1.]))], 500)
+            .into_iter()
+            .enumerate()
+            .for_each(|(k, y)| {
+                let t = k as f64 * ts;
+                let expected: f64 = (0..3)
+                    .map(|i| {
+                        let w = 2. * PI * model.eigen_frequencies[i];
+                        model.modal_disp_to_outputs[i]
+                            * model.inputs_to_modal_forces[i * 3 + 2]
+                            * step(w, model.damping[i], t)
+                    })
+                    .sum();
+                let y = Option::<Vec<f64>>::from(&y[0]).unwrap()[0];
+                assert!((y - expected).abs() < 1e-12, "{} != {}", y, expected);
+            });
+    }
+
+    #[test]
+    fn truncation_static_gain() {
+        let model = model();
+        let (inputs, outputs) = (
+            vec![ios!(OSSElDriveTorque), ios!(OSSAzDriveTorque)],
+            vec![ios!(OSSAzEncoderAngle), ios!(OSSElEncoderAngle)],
+        );
+        let config = ModalConfig::new(1e-2, inputs.clone(), outputs.clone())
+            .discretization(Discretization::Tustin { prewarp: None })
+            .max_frequency(10.)
+            .static_gain();
+        let mut system = ModalSystem::new(&model, config).unwrap();
+        assert_eq!(system.n_mode(), 2);
+        let u = vec![1., -2., 0.5];
+        let y = run(
+            &mut system,
+            vec![
+                ios!(OSSElDriveTorque(u[..2].to_vec())),
+                ios!(OSSAzDriveTorque(u[2..].to_vec())),
+            ],
+            1000,
+        )
+        .pop()
+        .unwrap();
+        let y: Vec<f64> = y
+            .into_iter()
+            .flat_map(|y| Option::<Vec<f64>>::from(y).unwrap())
+            .collect();
+        let gain = model.static_gain(&inputs, &outputs).unwrap();
+        gain.iter().zip(y).for_each(|(g, y)| {
+            let expected: f64 = g.iter().zip(&u).map(|(g, u)| g * u).sum();
+            assert!((y - expected).abs() < 1e-9, "{} != {}", y, expected);
+        });
+        assert!(matches!(
+            ModalSystem::new(&model, ModalConfig::new(1e-3, vec![ios!(Pssn)], vec![])),
+            Err(FemError::Missing(IOKind::Pssn))
+        ));
+    }
+}
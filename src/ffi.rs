@@ -0,0 +1,433 @@
+//! C ABI
+//!
+//! The C API is declared in `include/dosio.h`.
+//!
+//! A Rust [`Dos`] component is [`export`]ed to C as an opaque `dosio_component` handle:
+//! the C code sets the inputs one by one, by variant name or wire [`id`](crate::wire::id), with `dosio_set_input`,
+//! steps the component with `dosio_step` and reads the outputs with `dosio_get_output`.
+//!
+//! A C component, e.g. a controller generated from Simulink, is imported in Rust as a [`CComponent`]
+//! from a table of [`Callbacks`] to set its inputs, step it and get its outputs.
+//!
+//! A panic of a Rust component does not unwind into C: the call returns -1 and the panic message is the last error.
+
+use crate::{error::panic_message, io::IOKind, wire::id, DOSIOSError, Dos, IOTags, IO};
+use std::{
+    ffi::{CStr, CString},
+    os::raw::{c_char, c_int, c_void},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+// Object-safe interface of the exported components
+trait Component {
+    fn inputs(&mut self, data: Option<Vec<IO<Vec<f64>>>>) -> Result<(), DOSIOSError>;
+    fn step(&mut self) -> Result<(), DOSIOSError>;
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>>;
+}
+impl<D> Component for D
+where
+    D: Dos<Input = Vec<f64>, Output = Vec<f64>> + Iterator,
+{
+    fn inputs(&mut self, data: Option<Vec<IO<Vec<f64>>>>) -> Result<(), DOSIOSError> {
+        Dos::inputs(self, data).map(|_| ())
+    }
+    fn step(&mut self) -> Result<(), DOSIOSError> {
+        Dos::step(self).map(|_| ())
+    }
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        Dos::outputs(self)
+    }
+}
+
+/// Rust component exported to C
+///
+/// The `dosio_component` of the C API
+pub struct Exported {
+    dos: Box<dyn Component>,
+    inputs: Vec<IO<Vec<f64>>>,
+    outputs: Vec<IO<Vec<f64>>>,
+    error: Option<CString>,
+}
+impl Exported {
+    fn set_input(&mut self, kind: IOKind, data: &[f64]) {
+        let io = kind.io(Some(data.to_vec()));
+        match self.inputs.iter_mut().find(|input| input.io_kind() == kind) {
+            Some(input) => *input = io,
+            None => self.inputs.push(io),
+        }
+    }
+    fn step(&mut self) -> Result<(), DOSIOSError> {
+        // the outputs of the previous step are not read if this one fails
+        self.outputs.clear();
+        let inputs = std::mem::take(&mut self.inputs);
+        self.dos.inputs(if inputs.is_empty() {
+            None
+        } else {
+            Some(inputs)
+        })?;
+        self.dos.step()?;
+        self.outputs = self.dos.outputs().unwrap_or_default();
+        Ok(())
+    }
+    fn get_output(&self, kind: IOKind, data: &mut [f64]) -> Result<usize, String> {
+        let values = self
+            .outputs
+            .iter()
+            .find(|output| output.io_kind() == kind)
+            .and_then(|output| output.as_ref())
+            .ok_or_else(|| format!("{} output is missing", kind))?;
+        let n = values.len().min(data.len());
+        data[..n].copy_from_slice(&values[..n]);
+        Ok(values.len())
+    }
+    fn set_error<E: ToString>(&mut self, error: E) {
+        self.error = CString::new(error.to_string().replace('\0', " ")).ok();
+    }
+}
+
+// Clears the last error of the component and calls `f` with it,
+// returns `failed` if `component` is NULL or if `f` panics, the panic message being the last error
+unsafe fn call<T, F>(component: *mut Exported, failed: T, f: F) -> T
+where
+    F: FnOnce(&mut Exported) -> T,
+{
+    let component = match component.as_mut() {
+        Some(component) => component,
+        None => return failed,
+    };
+    component.error = None;
+    match panic::catch_unwind(AssertUnwindSafe(|| f(&mut *component))) {
+        Ok(value) => value,
+        Err(payload) => {
+            component.set_error(format!(
+                "the component panicked: {}",
+                panic_message(payload.as_ref())
+            ));
+            failed
+        }
+    }
+}
+
+/// Exports a [`Dos`] component to C
+///
+/// The returned handle is freed with `dosio_free`
+pub fn export<D>(dos: D) -> *mut Exported
+where
+    D: Dos<Input = Vec<f64>, Output = Vec<f64>> + Iterator + 'static,
+{
+    Box::into_raw(Box::new(Exported {
+        dos: Box::new(dos),
+        inputs: vec![],
+        outputs: vec![],
+        error: None,
+    }))
+}
+
+// Returns the variant of the name
+unsafe fn kind_from_name(name: *const c_char) -> Result<IOKind, String> {
+    if name.is_null() {
+        return Err("the variant name is NULL".into());
+    }
+    let name = CStr::from_ptr(name).to_string_lossy();
+    name.parse::<IOKind>()
+        .map_err(|_| format!("unknown IO variant {}", name))
+}
+// Returns the variant of the wire id
+fn kind_from_id(id: u32) -> Result<IOKind, String> {
+    IOKind::ALL
+        .iter()
+        .find(|&&kind| crate::wire::id(kind) == id)
+        .copied()
+        .ok_or_else(|| format!("unknown IO variant id {:08x}", id))
+}
+
+/// Returns the wire id of the variant `name`, 0 if the variant does not exist
+///
+/// # Safety
+/// `name` must be a NULL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn dosio_id(name: *const c_char) -> u32 {
+    panic::catch_unwind(|| kind_from_name(name).map_or(0, id)).unwrap_or(0)
+}
+
+// The variant is resolved by `kind` within the call
+unsafe fn set_input<K>(component: *mut Exported, kind: K, data: *const f64, len: usize) -> c_int
+where
+    K: FnOnce() -> Result<IOKind, String>,
+{
+    call(component, -1, |component| match kind() {
+        Ok(_) if data.is_null() && len > 0 => {
+            component.set_error("the input data is NULL");
+            -1
+        }
+        Ok(kind) => {
+            let data = if len == 0 {
+                &[]
+            } else {
+                slice::from_raw_parts(data, len)
+            };
+            component.set_input(kind, data);
+            0
+        }
+        Err(e) => {
+            component.set_error(e);
+            -1
+        }
+    })
+}
+/// Sets the input `name` to the `len` values of `data`
+///
+/// # Safety
+/// `component` must be a handle returned by [`export`], `name` a NULL-terminated string and `data` an array of `len` values
+#[no_mangle]
+pub unsafe extern "C" fn dosio_set_input(
+    component: *mut Exported,
+    name: *const c_char,
+    data: *const f64,
+    len: usize,
+) -> c_int {
+    set_input(component, || kind_from_name(name), data, len)
+}
+/// Sets the input of wire id `id` to the `len` values of `data`
+///
+/// # Safety
+/// `component` must be a handle returned by [`export`] and `data` an array of `len` values
+#[no_mangle]
+pub unsafe extern "C" fn dosio_set_input_id(
+    component: *mut Exported,
+    id: u32,
+    data: *const f64,
+    len: usize,
+) -> c_int {
+    set_input(component, || kind_from_id(id), data, len)
+}
+
+/// Passes the inputs to the component, steps it and retrieves its outputs
+///
+/// # Safety
+/// `component` must be a handle returned by [`export`]
+#[no_mangle]
+pub unsafe extern "C" fn dosio_step(component: *mut Exported) -> c_int {
+    call(component, -1, |component| match component.step() {
+        Ok(()) => 0,
+        Err(e) => {
+            component.set_error(e.report());
+            -1
+        }
+    })
+}
+
+// The variant is resolved by `kind` within the call
+unsafe fn get_output<K>(component: *mut Exported, kind: K, data: *mut f64, len: usize) -> i64
+where
+    K: FnOnce() -> Result<IOKind, String>,
+{
+    call(component, -1, |component| {
+        let data = if data.is_null() || len == 0 {
+            &mut []
+        } else {
+            slice::from_raw_parts_mut(data, len)
+        };
+        match kind().and_then(|kind| component.get_output(kind, data)) {
+            Ok(n) => n as i64,
+            Err(e) => {
+                component.set_error(e);
+                -1
+            }
+        }
+    })
+}
+/// Copies at most `len` values of the output `name` into `data`
+///
+/// # Safety
+/// `component` must be a handle returned by [`export`], `name` a NULL-terminated string and `data` an array of `len` values
+#[no_mangle]
+pub unsafe extern "C" fn dosio_get_output(
+    component: *mut Exported,
+    name: *const c_char,
+    data: *mut f64,
+    len: usize,
+) -> i64 {
+    get_output(component, || kind_from_name(name), data, len)
+}
+/// Copies at most `len` values of the output of wire id `id` into `data`
+///
+/// # Safety
+/// `component` must be a handle returned by [`export`] and `data` an array of `len` values
+#[no_mangle]
+pub unsafe extern "C" fn dosio_get_output_id(
+    component: *mut Exported,
+    id: u32,
+    data: *mut f64,
+    len: usize,
+) -> i64 {
+    get_output(component, || kind_from_id(id), data, len)
+}
+
+/// Returns the message of the last error, NULL if the last call succeeded
+///
+/// # Safety
+/// `component` must be a handle returned by [`export`]
+#[no_mangle]
+pub unsafe extern "C" fn dosio_last_error(component: *const Exported) -> *const c_char {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        component
+            .as_ref()
+            .and_then(|component| component.error.as_ref())
+            .map_or(ptr::null(), |error| error.as_ptr())
+    }))
+    .unwrap_or(ptr::null())
+}
+
+/// Frees the component
+///
+/// # Safety
+/// `component` must be a handle returned by [`export`] and not used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn dosio_free(component: *mut Exported) {
+    if !component.is_null() {
+        // a panic while dropping the component is not unwound into C
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(component))));
+    }
+}
+
+/// Callbacks of a C component
+///
+/// The `dosio_c_component` of the C API
+#[repr(C)]
+pub struct Callbacks {
+    pub handle: *mut c_void,
+    pub set_input:
+        unsafe extern "C" fn(*mut c_void, u32, *const c_char, *const f64, usize) -> c_int,
+    pub step: unsafe extern "C" fn(*mut c_void) -> c_int,
+    pub get_output: unsafe extern "C" fn(*mut c_void, u32, *const c_char, *mut f64, usize) -> i64,
+    pub free: Option<unsafe extern "C" fn(*mut c_void)>,
+}
+
+/// C component wrapped as a [`Dos`] component
+pub struct CComponent {
+    callbacks: Callbacks,
+    inputs: Vec<(IOKind, CString)>,
+    outputs: Vec<(IOKind, CString, usize)>,
+}
+impl CComponent {
+    /// Creates a new component from the C `callbacks`, with the given inputs and the outputs with their sizes
+    ///
+    /// # Safety
+    /// The callbacks must follow the C API and `callbacks.handle` must be valid until the component is dropped
+    pub unsafe fn new(
+        callbacks: Callbacks,
+        inputs: Vec<IO<()>>,
+        outputs: Vec<(IO<()>, usize)>,
+    ) -> Self {
+        let c_name = |kind: IOKind| CString::new(kind.name()).expect("variant name");
+        Self {
+            callbacks,
+            inputs: inputs
+                .into_iter()
+                .map(|io| (io.io_kind(), c_name(io.io_kind())))
+                .collect(),
+            outputs: outputs
+                .into_iter()
+                .map(|(io, n)| (io.io_kind(), c_name(io.io_kind()), n))
+                .collect(),
+        }
+    }
+}
+impl Dos for CComponent {
+    type Input = Vec<f64>;
+    type Output = Vec<f64>;
+    fn outputs(&mut self) -> Option<Vec<IO<Self::Output>>> {
+        let handle = self.callbacks.handle;
+        let get_output = self.callbacks.get_output;
+        self.outputs
+            .iter()
+            .map(|(kind, name, n)| {
+                let mut data = vec![0f64; *n];
+                let mut len =
+                    unsafe { get_output(handle, id(*kind), name.as_ptr(), data.as_mut_ptr(), *n) };
+                // the output is larger than expected
+                if len > *n as i64 {
+                    data.resize(len as usize, 0f64);
+                    len = unsafe {
+                        get_output(
+                            handle,
+                            id(*kind),
+                            name.as_ptr(),
+                            data.as_mut_ptr(),
+                            data.len(),
+                        )
+                    };
+                }
+                if len < 0 {
+                    None
+                } else {
+                    data.truncate(len as usize);
+                    Some(kind.io(Some(data)))
+                }
+            })
+            .collect()
+    }
+    fn inputs(&mut self, data: Option<Vec<IO<Self::Input>>>) -> Result<&mut Self, DOSIOSError> {
+        let data = data.unwrap_or_default();
+        for (kind, name) in &self.inputs {
+            let values = data
+                .iter()
+                .find(|io| io.io_kind() == *kind)
+                .and_then(|io| io.as_ref())
+                .ok_or_else(|| {
+                    DOSIOSError::Inputs(format!("{} input is missing", kind).into())
+                        .with_io(&kind.io::<()>(None))
+                })?;
+            let status = unsafe {
+                (self.callbacks.set_input)(
+                    self.callbacks.handle,
+                    id(*kind),
+                    name.as_ptr(),
+                    values.as_ptr(),
+                    values.len(),
+                )
+            };
+            if status != 0 {
+                return Err(DOSIOSError::Inputs(
+                    format!("the C component rejected {} input ({})", kind, status).into(),
+                )
+                .with_io(&kind.io::<()>(None)));
+            }
+        }
+        Ok(self)
+    }
+    fn step(&mut self) -> Result<&mut Self, DOSIOSError> {
+        match unsafe { (self.callbacks.step)(self.callbacks.handle) } {
+            0 => Ok(self),
+            status => Err(DOSIOSError::Step(
+                format!("the C component step failed ({})", status).into(),
+            )),
+        }
+    }
+}
+impl Iterator for CComponent {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        Dos::step(self).ok().map(|_| ())
+    }
+}
+impl IOTags for CComponent {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        self.outputs
+            .iter()
+            .map(|(kind, _, _)| kind.io(None))
+            .collect()
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        self.inputs.iter().map(|(kind, _)| kind.io(None)).collect()
+    }
+}
+impl Drop for CComponent {
+    fn drop(&mut self) {
+        if let Some(free) = self.callbacks.free {
+            unsafe { free(self.callbacks.handle) }
+        }
+    }
+}
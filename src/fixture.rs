@@ -0,0 +1,419 @@
+//! Synthetic FEM models
+//!
+//! [`Fixture`] builds a small [`ModalModel`] from a chosen set of inputs and outputs groups and modes,
+//! and writes it with the same layout as the GMT FEM model files:
+//! the `modal_state_space_model_2ndOrder.zip` archive of parquet tables (feature `fem-prqt`)
+//! and the `modal_state_space_model_2ndOrder.rs.mat` file (feature `fem-hdf5`).
+//!
+//! A directory with the fixture files can be given as `FEM_REPO` to build the [`IO`](crate::IO) variants of a toy telescope:
+//! ```shell
+//! cargo run --no-default-features --features fixture,fem-prqt --bin fem-fixture -- <DIR>
+//! FEM_REPO=<DIR> cargo build
+//! ```
+
+use crate::fem::{
+    manifest::{self, Kind},
+    FemError, FemGroup, ModalModel,
+};
+use std::path::{Path, PathBuf};
+
+/// FEM model zip archive file name
+pub const ZIP_MODEL: &str = "modal_state_space_model_2ndOrder.zip";
+/// FEM model mat file name
+pub const MAT_MODEL: &str = "modal_state_space_model_2ndOrder.rs.mat";
+
+/// Synthetic FEM model builder
+///
+/// The eigenfrequencies are logarithmically spaced between 1Hz and the largest eigenfrequency
+/// and the coefficients of the inputs to modal forces and modal displacements to outputs matrices
+/// are pseudo-random numbers uniformly distributed in \[-1,1\]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixture {
+    inputs: Vec<FemGroup>,
+    outputs: Vec<FemGroup>,
+    n_mode: usize,
+    max_frequency: f64,
+    damping: f64,
+    seed: u64,
+}
+impl Default for Fixture {
+    /// A toy telescope with the mount drives and encoders
+    fn default() -> Self {
+        Self::new()
+            .input("OSS_ElDrive_Torque", 8)
+            .input("OSS_AzDrive_Torque", 8)
+            .input("OSS_RotDrive_Torque", 4)
+            .output("OSS_AzEncoder_Angle", 6)
+            .output("OSS_ElEncoder_Angle", 4)
+            .output("OSS_RotEncoder_Angle", 4)
+    }
+}
+impl Fixture {
+    /// Creates a new fixture without inputs and outputs, with 10 modes up to 100Hz and 2% damping
+    pub fn new() -> Self {
+        Self {
+            inputs: vec![],
+            outputs: vec![],
+            n_mode: 10,
+            max_frequency: 100.,
+            damping: 0.02,
+            seed: 1,
+        }
+    }
+    /// Creates a new fixture from an inputs/outputs manifest
+    ///
+    /// The manifest lists the inputs and outputs, one per line, as `<in|out> <FEM group name> <size>`.
+    /// Lines starting with `#` are comments and the `alias` lines are skipped.
+    pub fn from_manifest(manifest: &str) -> Result<Self, FemError> {
+        let fem_io = manifest::parse(manifest).map_err(FemError::Read)?;
+        Ok(fem_io
+            .into_iter()
+            .fold(Self::new(), |fixture, io| match io.kind {
+                Kind::In => fixture.input(io.group, io.size),
+                Kind::Out => fixture.output(io.group, io.size),
+            }))
+    }
+    /// Adds an input group
+    pub fn input<S: Into<String>>(mut self, group: S, size: usize) -> Self {
+        self.inputs.push(FemGroup::new(group, size));
+        self
+    }
+    /// Adds an output group
+    pub fn output<S: Into<String>>(mut self, group: S, size: usize) -> Self {
+        self.outputs.push(FemGroup::new(group, size));
+        self
+    }
+    /// Sets the number of modes
+    pub fn modes(self, n_mode: usize) -> Self {
+        Self { n_mode, ..self }
+    }
+    /// Sets the largest eigenfrequency in Hz
+    pub fn max_frequency(self, max_frequency: f64) -> Self {
+        Self {
+            max_frequency,
+            ..self
+        }
+    }
+    /// Sets the damping ratio of all the modes
+    pub fn damping(self, damping: f64) -> Self {
+        Self { damping, ..self }
+    }
+    /// Sets the seed of the pseudo-random number generator
+    pub fn seed(self, seed: u64) -> Self {
+        Self { seed, ..self }
+    }
+    /// Returns the modal model
+    pub fn model(&self) -> ModalModel {
+        let n_mode = self.n_mode;
+        let eigen_frequencies = (0..n_mode)
+            .map(|i| {
+                if n_mode > 1 {
+                    self.max_frequency.powf(i as f64 / (n_mode - 1) as f64)
+                } else {
+                    1f64
+                }
+            })
+            .collect();
+        let n_input: usize = self.inputs.iter().map(|group| group.size).sum();
+        let n_output: usize = self.outputs.iter().map(|group| group.size).sum();
+        let mut rng = Lcg(self.seed);
+        ModalModel {
+            eigen_frequencies,
+            damping: vec![self.damping; n_mode],
+            inputs_to_modal_forces: (0..n_mode * n_input).map(|_| rng.uniform()).collect(),
+            modal_disp_to_outputs: (0..n_output * n_mode).map(|_| rng.uniform()).collect(),
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+        }
+    }
+    /// Writes the model files to the `fem_repo` directory
+    ///
+    /// The zip archive is written with the `fem-prqt` feature and the mat file with the `fem-hdf5` feature,
+    /// the paths to the files are returned
+    pub fn write<P: AsRef<Path>>(&self, fem_repo: P) -> Result<Vec<PathBuf>, FemError> {
+        let fem_repo = fem_repo.as_ref();
+        std::fs::create_dir_all(fem_repo).map_err(|e| FemError::Write(e.to_string()))?;
+        #[allow(unused_variables)]
+        let model = self.model();
+        #[allow(unused_mut)]
+        let mut paths = vec![];
+        #[cfg(feature = "fem-prqt")]
+        {
+            let path = fem_repo.join(ZIP_MODEL);
+            model.to_zip(&path)?;
+            paths.push(path);
+        }
+        #[cfg(feature = "fem-hdf5")]
+        {
+            let path = fem_repo.join(MAT_MODEL);
+            model.to_mat(&path)?;
+            paths.push(path);
+        }
+        if paths.is_empty() {
+            Err(FemError::Write(
+                "writing the FEM model requires either the `fem-prqt` or the `fem-hdf5` feature"
+                    .into(),
+            ))
+        } else {
+            Ok(paths)
+        }
+    }
+}
+
+// Linear congruential generator (Knuth MMIX)
+struct Lcg(u64);
+impl Lcg {
+    // uniform deviate in [-1,1]
+    fn uniform(&mut self) -> f64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        2. * (self.0 >> 11) as f64 / (1u64 << 53) as f64 - 1.
+    }
+}
+
+#[cfg(feature = "fem-prqt")]
+mod prqt {
+    use super::{FemError, FemGroup, ModalModel};
+    use arrow::{
+        array::{ArrayRef, Float64Array, ListArray, StringArray, UInt64Array},
+        datatypes::Float64Type,
+        datatypes::{Field, Schema},
+        record_batch::RecordBatch,
+    };
+    use parquet::{
+        arrow::ArrowWriter, file::properties::WriterProperties,
+        util::cursor::InMemoryWriteableCursor,
+    };
+    use std::{fs::File, io::Write, path::Path, sync::Arc};
+    use zip::{write::FileOptions, ZipWriter};
+
+    fn error<E: std::fmt::Display>(e: E) -> FemError {
+        FemError::Write(e.to_string())
+    }
+
+    // Writes the columns into a parquet table
+    fn table(columns: Vec<(&str, ArrayRef)>) -> Result<Vec<u8>, FemError> {
+        let schema = Arc::new(Schema::new(
+            columns
+                .iter()
+                .map(|(name, data)| Field::new(name, data.data_type().clone(), true))
+                .collect(),
+        ));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            columns.into_iter().map(|(_, data)| data).collect(),
+        )
+        .map_err(error)?;
+        let cursor = InMemoryWriteableCursor::default();
+        let mut writer = ArrowWriter::try_new(
+            cursor.clone(),
+            schema,
+            // the dictionary encoder of parquet 6 hashes unaligned data
+            Some(
+                WriterProperties::builder()
+                    .set_dictionary_enabled(false)
+                    .build(),
+            ),
+        )
+        .map_err(error)?;
+        writer.write(&batch).map_err(error)?;
+        writer.close().map_err(error)?;
+        Ok(cursor.data())
+    }
+    // One row per degree of freedom, ordered by group
+    fn groups(groups: &[FemGroup]) -> Result<Vec<u8>, FemError> {
+        let names: Vec<&str> = groups
+            .iter()
+            .flat_map(|group| vec![group.group.as_str(); group.size])
+            .collect();
+        table(vec![
+            (
+                "index",
+                Arc::new(UInt64Array::from_iter_values(1..=names.len() as u64)),
+            ),
+            ("group", Arc::new(StringArray::from(names))),
+        ])
+    }
+    fn rows<I: Iterator<Item = Vec<f64>>>(rows: I) -> ArrayRef {
+        Arc::new(ListArray::from_iter_primitive::<Float64Type, _, _>(
+            rows.map(|row| Some(row.into_iter().map(Some).collect::<Vec<_>>())),
+        ))
+    }
+
+    impl ModalModel {
+        /// Writes the model to a zip archive with the layout read by [`ModalModel::from_zip`]
+        pub fn to_zip<P: AsRef<Path>>(&self, path: P) -> Result<(), FemError> {
+            self.check()?;
+            let (n_mode, n_input) = (self.n_mode(), self.n_input());
+            let mat = table(vec![
+                (
+                    "eigenfrequencies",
+                    Arc::new(Float64Array::from(self.eigen_frequencies.clone())),
+                ),
+                (
+                    "proportionalDampingVec",
+                    Arc::new(Float64Array::from(self.damping.clone())),
+                ),
+                (
+                    "inputs2ModalF",
+                    rows(
+                        self.inputs_to_modal_forces
+                            .chunks(n_input.max(1))
+                            .map(|row| row.to_vec())
+                            .take(n_mode),
+                    ),
+                ),
+                (
+                    "modalDisp2Outputs",
+                    rows((0..n_mode).map(|j| {
+                        self.modal_disp_to_outputs
+                            .iter()
+                            .skip(j)
+                            .step_by(n_mode)
+                            .cloned()
+                            .collect()
+                    })),
+                ),
+            ])?;
+            let mut zip_file = ZipWriter::new(File::create(path).map_err(error)?);
+            for (name, contents) in [
+                ("in", groups(&self.inputs)?),
+                ("out", groups(&self.outputs)?),
+                ("mat", mat),
+            ] {
+                zip_file
+                    .start_file(
+                        format!("modal_state_space_model_2ndOrder_{}.parquet", name),
+                        FileOptions::default(),
+                    )
+                    .map_err(error)?;
+                zip_file.write_all(&contents).map_err(error)?;
+            }
+            zip_file.finish().map_err(error)?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "fem-hdf5")]
+mod mat {
+    use super::{FemError, FemGroup, ModalModel};
+    use hdf5::types::{FixedAscii, VarLenArray};
+    use std::path::Path;
+
+    fn error<E: std::fmt::Display>(e: E) -> FemError {
+        FemError::Write(e.to_string())
+    }
+
+    // Writes the group names in the `MATLAB_fields` attribute and one table per group
+    fn groups(h5: &hdf5::File, name: &str, groups: &[FemGroup]) -> Result<(), FemError> {
+        let fem_io = h5.create_group(name).map_err(error)?;
+        let fields = groups
+            .iter()
+            .map(|group| {
+                group
+                    .group
+                    .bytes()
+                    .map(|c| FixedAscii::<1>::from_ascii(&[c]))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|chars| VarLenArray::from_slice(&chars))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(error)?;
+        fem_io
+            .new_attr::<VarLenArray<FixedAscii<1>>>()
+            .shape(fields.len())
+            .create("MATLAB_fields")
+            .map_err(error)?
+            .write_raw(&fields)
+            .map_err(error)?;
+        // one table row per degree of freedom
+        let mut index = 0usize;
+        for group in groups {
+            let dofs: Vec<f64> = (index..index + group.size)
+                .map(|i| (i + 1) as f64)
+                .collect();
+            index += group.size;
+            fem_io
+                .new_dataset::<f64>()
+                .shape((group.size, 1))
+                .create(group.group.as_str())
+                .map_err(error)?
+                .write_raw(&dofs)
+                .map_err(error)?;
+        }
+        Ok(())
+    }
+
+    impl ModalModel {
+        /// Writes the model to a mat file with the layout read by [`ModalModel::from_mat`]
+        ///
+        /// The matrices are stored column-wise (MATLAB layout)
+        pub fn to_mat<P: AsRef<Path>>(&self, path: P) -> Result<(), FemError> {
+            self.check()?;
+            let h5 = hdf5::File::create(path).map_err(error)?;
+            groups(&h5, "fem_inputs", &self.inputs)?;
+            groups(&h5, "fem_outputs", &self.outputs)?;
+            let (n_mode, n_input, n_output) = (self.n_mode(), self.n_input(), self.n_output());
+            let column_wise = |data: &[f64], n_row: usize, n_col: usize| -> Vec<f64> {
+                (0..n_col)
+                    .flat_map(|j| (0..n_row).map(move |i| (i, j)))
+                    .map(|(i, j)| data[i * n_col + j])
+                    .collect()
+            };
+            for (name, data, (n_row, n_col)) in [
+                (
+                    "eigenfrequencies",
+                    self.eigen_frequencies.clone(),
+                    (n_mode, 1),
+                ),
+                ("proportionalDampingVec", self.damping.clone(), (n_mode, 1)),
+                (
+                    "inputs2ModalF",
+                    column_wise(&self.inputs_to_modal_forces, n_mode, n_input),
+                    (n_mode, n_input),
+                ),
+                (
+                    "modalDisp2Outputs",
+                    column_wise(&self.modal_disp_to_outputs, n_output, n_mode),
+                    (n_output, n_mode),
+                ),
+            ] {
+                h5.new_dataset::<f64>()
+                    .shape((n_col, n_row))
+                    .create(name)
+                    .map_err(error)?
+                    .write_raw(&data)
+                    .map_err(error)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest() {
+        let fixture = Fixture::from_manifest(
+            "# toy telescope\nin OSS_ElDrive_Torque 2\n\nout OSS_ElEncoder_Angle 3\n",
+        )
+        .unwrap()
+        .modes(4);
+        let model = fixture.model();
+        assert_eq!(
+            (model.n_mode(), model.n_input(), model.n_output()),
+            (4, 2, 3)
+        );
+        assert!(model.check().is_ok());
+        assert_eq!(model.eigen_frequencies[0], 1.);
+        assert!((model.eigen_frequencies[3] - 100.).abs() < 1e-12);
+        assert_eq!(model, fixture.model());
+        assert!(model.inputs_to_modal_forces.iter().all(|x| x.abs() <= 1.));
+        assert!(Fixture::from_manifest("io OSS_ElDrive_Torque 2").is_err());
+    }
+}
@@ -0,0 +1,1025 @@
+//! FMI co-simulation
+//!
+//! [`Fmu`] loads a co-simulation FMU, FMI 2.0 or FMI 3.0, and [`Fmu::instantiate`] returns a [`FmuComponent`],
+//! a [`Dos`] component with the real-valued FMU variables mapped to [`IO`] variants by a [`Mapping`].
+//! Each [`step`](Dos::step) of the component advances the FMU by one communication step.
+//!
+//! The mapping of the FMU variables to the [`IO`] variants is written in a text file, one [`IO`] per line:
+//! ```text
+//! # mount model
+//! input OSSElDriveTorque = el_torque[1], el_torque[2]
+//! output OSSElEncoderAngle = el_angle
+//! ```
+//!
+//! The messages logged by the FMU are passed to the callback given to [`Fmu::logger`].
+//!
+//! The [`export`] module packages [`Dos`] components as FMUs.
+
+pub mod export;
+
+use crate::{io::IOKind, DOSIOSError, Dos, IOTags, IO};
+use libloading::Library;
+use std::{
+    env::consts,
+    error::Error,
+    ffi::{CStr, CString},
+    fmt, fs,
+    os::raw::{c_char, c_int, c_void},
+    panic,
+    path::{Path, PathBuf},
+    ptr,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// FMI error
+#[derive(Debug)]
+pub enum FmiError {
+    /// Read or write error
+    Io(std::io::Error),
+    /// The FMU archive cannot be read or written
+    Zip(String),
+    /// Invalid model description
+    Xml(String),
+    /// The FMU library or one of its functions cannot be loaded
+    Library(String),
+    /// Invalid mapping of the FMU variables
+    Mapping(String),
+    /// The FMU cannot be instantiated
+    Instantiate(String),
+    /// An FMU function has failed
+    Status { function: &'static str, status: i32 },
+}
+impl fmt::Display for FmiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "FMU I/O error: {}", e),
+            Self::Zip(e) => write!(f, "FMU archive error: {}", e),
+            Self::Xml(e) => write!(f, "FMU model description error: {}", e),
+            Self::Library(e) => write!(f, "FMU library error: {}", e),
+            Self::Mapping(e) => write!(f, "FMU mapping error: {}", e),
+            Self::Instantiate(model) => write!(f, "Failed to instantiate FMU {}", model),
+            Self::Status { function, status } => {
+                write!(f, "FMU function {} failed with status {}", function, status)
+            }
+        }
+    }
+}
+impl Error for FmiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl From<std::io::Error> for FmiError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+impl From<zip::result::ZipError> for FmiError {
+    fn from(e: zip::result::ZipError) -> Self {
+        Self::Zip(e.to_string())
+    }
+}
+
+/// FMI version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    /// FMI 2.0
+    V2,
+    /// FMI 3.0
+    V3,
+}
+impl Version {
+    /// Returns the directory of the FMU binaries for the current platform
+    pub fn platform(self) -> String {
+        match self {
+            Self::V2 => {
+                let os = match consts::OS {
+                    "macos" => "darwin",
+                    "windows" => "win",
+                    os => os,
+                };
+                format!("{}{}", os, 8 * std::mem::size_of::<usize>())
+            }
+            Self::V3 => {
+                let os = match consts::OS {
+                    "macos" => "darwin",
+                    os => os,
+                };
+                format!("{}-{}", consts::ARCH, os)
+            }
+        }
+    }
+}
+
+/// Causality of an FMU variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Causality {
+    Input,
+    Output,
+    Parameter,
+    Local,
+}
+impl Causality {
+    fn parse(causality: Option<&str>) -> Self {
+        match causality {
+            Some("input") => Self::Input,
+            Some("output") => Self::Output,
+            Some("parameter") => Self::Parameter,
+            _ => Self::Local,
+        }
+    }
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Input => "input",
+            Self::Output => "output",
+            Self::Parameter => "parameter",
+            Self::Local => "local",
+        }
+    }
+}
+
+/// Real-valued FMU variable
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variable {
+    pub name: String,
+    pub value_reference: u32,
+    pub causality: Causality,
+    pub start: Option<f64>,
+}
+
+/// FMU model description
+///
+/// Only the real-valued variables, `Real` in FMI 2.0 and `Float64` in FMI 3.0, are kept
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelDescription {
+    pub version: Version,
+    pub model_name: String,
+    pub model_identifier: String,
+    /// GUID in FMI 2.0, instantiation token in FMI 3.0
+    pub token: String,
+    /// Default communication step size
+    pub step_size: Option<f64>,
+    /// `canHandleVariableCommunicationStepSize`, if not the FMU is stepped with [`step_size`](ModelDescription::step_size)
+    pub variable_step_size: bool,
+    pub variables: Vec<Variable>,
+}
+impl ModelDescription {
+    /// Returns the variable `name`
+    pub fn variable(&self, name: &str) -> Option<&Variable> {
+        self.variables.iter().find(|variable| variable.name == name)
+    }
+    /// Writes the model description XML
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let (version, token) = match self.version {
+            Version::V2 => ("2.0", "guid"),
+            Version::V3 => ("3.0", "instantiationToken"),
+        };
+        xml.push_str(&format!(
+            "<fmiModelDescription fmiVersion=\"{}\" modelName=\"{}\" {}=\"{}\">\n",
+            version,
+            escape(&self.model_name),
+            token,
+            escape(&self.token)
+        ));
+        let fixed_step_size = match (self.version, self.step_size) {
+            (Version::V3, Some(step_size)) if !self.variable_step_size => {
+                format!(" fixedInternalStepSize=\"{:e}\"", step_size)
+            }
+            _ => String::new(),
+        };
+        xml.push_str(&format!(
+            "  <CoSimulation modelIdentifier=\"{}\" canHandleVariableCommunicationStepSize=\"{}\"{}/>\n",
+            escape(&self.model_identifier),
+            self.variable_step_size,
+            fixed_step_size
+        ));
+        if let Some(step_size) = self.step_size {
+            xml.push_str(&format!(
+                "  <DefaultExperiment stepSize=\"{:e}\"/>\n",
+                step_size
+            ));
+        }
+        xml.push_str("  <ModelVariables>\n");
+        for variable in &self.variables {
+            let variability = match variable.causality {
+                Causality::Parameter => "tunable",
+                _ => "continuous",
+            };
+            let attributes = format!(
+                "name=\"{}\" valueReference=\"{}\" causality=\"{}\" variability=\"{}\"",
+                escape(&variable.name),
+                variable.value_reference,
+                variable.causality.as_str(),
+                variability
+            );
+            let start = variable
+                .start
+                .map(|start| format!(" start=\"{:e}\"", start))
+                .unwrap_or_default();
+            match self.version {
+                Version::V2 => xml.push_str(&format!(
+                    "    <ScalarVariable {}>\n      <Real{}/>\n    </ScalarVariable>\n",
+                    attributes, start
+                )),
+                Version::V3 => xml.push_str(&format!("    <Float64 {}{}/>\n", attributes, start)),
+            }
+        }
+        xml.push_str("  </ModelVariables>\n  <ModelStructure>\n");
+        let outputs = self
+            .variables
+            .iter()
+            .enumerate()
+            .filter(|(_, variable)| variable.causality == Causality::Output);
+        match self.version {
+            Version::V2 => {
+                xml.push_str("    <Outputs>\n");
+                for (i, _) in outputs {
+                    xml.push_str(&format!("      <Unknown index=\"{}\"/>\n", i + 1));
+                }
+                xml.push_str("    </Outputs>\n");
+            }
+            Version::V3 => {
+                for (_, variable) in outputs {
+                    xml.push_str(&format!(
+                        "    <Output valueReference=\"{}\"/>\n",
+                        variable.value_reference
+                    ));
+                }
+            }
+        }
+        xml.push_str("  </ModelStructure>\n</fmiModelDescription>\n");
+        xml
+    }
+}
+impl FromStr for ModelDescription {
+    type Err = FmiError;
+    /// Parses the model description XML
+    fn from_str(xml: &str) -> Result<Self, Self::Err> {
+        let doc = roxmltree::Document::parse(xml).map_err(|e| FmiError::Xml(e.to_string()))?;
+        let root = doc.root_element();
+        let version = match root.attribute("fmiVersion") {
+            Some(version) if version.starts_with("2.") => Version::V2,
+            Some(version) if version.starts_with("3.") => Version::V3,
+            version => {
+                return Err(FmiError::Xml(format!(
+                    "unsupported FMI version {}",
+                    version.unwrap_or("(none)")
+                )))
+            }
+        };
+        let attribute = |name: &str| -> Result<String, FmiError> {
+            root.attribute(name)
+                .map(String::from)
+                .ok_or_else(|| FmiError::Xml(format!("{} is missing", name)))
+        };
+        let element = |name: &str| root.children().find(|node| node.has_tag_name(name));
+        let co_simulation = element("CoSimulation")
+            .ok_or_else(|| FmiError::Xml("not a co-simulation FMU".into()))?;
+        let model_identifier = co_simulation
+            .attribute("modelIdentifier")
+            .ok_or_else(|| FmiError::Xml("modelIdentifier is missing".into()))?
+            .to_string();
+        let variable_step_size =
+            co_simulation.attribute("canHandleVariableCommunicationStepSize") == Some("true");
+        let step_size = element("DefaultExperiment")
+            .and_then(|node| node.attribute("stepSize"))
+            .and_then(|step_size| step_size.parse().ok());
+        let mut variables = vec![];
+        for node in element("ModelVariables")
+            .into_iter()
+            .flat_map(|node| node.children())
+            .filter(|node| node.is_element())
+        {
+            let real = match version {
+                Version::V2 => node
+                    .children()
+                    .find(|child| child.has_tag_name("Real"))
+                    .filter(|_| node.has_tag_name("ScalarVariable")),
+                Version::V3 => Some(node).filter(|node| node.has_tag_name("Float64")),
+            };
+            let real = match real {
+                Some(real) => real,
+                None => continue,
+            };
+            let name = node
+                .attribute("name")
+                .ok_or_else(|| FmiError::Xml("a variable name is missing".into()))?;
+            let value_reference = node
+                .attribute("valueReference")
+                .and_then(|vr| vr.parse().ok())
+                .ok_or_else(|| {
+                    FmiError::Xml(format!("the value reference of {} is invalid", name))
+                })?;
+            variables.push(Variable {
+                name: name.to_string(),
+                value_reference,
+                causality: Causality::parse(node.attribute("causality")),
+                start: real.attribute("start").and_then(|start| start.parse().ok()),
+            });
+        }
+        Ok(Self {
+            version,
+            model_name: attribute("modelName")?,
+            model_identifier,
+            token: attribute(match version {
+                Version::V2 => "guid",
+                Version::V3 => "instantiationToken",
+            })?,
+            step_size,
+            variable_step_size,
+            variables,
+        })
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Mapping of the FMU variables to [`IO`] variants
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mapping {
+    pub inputs: Vec<(IOKind, Vec<String>)>,
+    pub outputs: Vec<(IOKind, Vec<String>)>,
+}
+impl Mapping {
+    /// Creates an empty mapping
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Maps the input FMU `variables` to `io`
+    pub fn input(mut self, io: IO<()>, variables: &[&str]) -> Self {
+        self.inputs.push((
+            io.io_kind(),
+            variables.iter().map(|name| name.to_string()).collect(),
+        ));
+        self
+    }
+    /// Maps the output FMU `variables` to `io`
+    pub fn output(mut self, io: IO<()>, variables: &[&str]) -> Self {
+        self.outputs.push((
+            io.io_kind(),
+            variables.iter().map(|name| name.to_string()).collect(),
+        ));
+        self
+    }
+    /// Reads the mapping file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, FmiError> {
+        fs::read_to_string(path)?.parse()
+    }
+}
+impl FromStr for Mapping {
+    type Err = FmiError;
+    fn from_str(mapping: &str) -> Result<Self, Self::Err> {
+        let mut this = Self::new();
+        for (i, line) in mapping.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = || FmiError::Mapping(format!("invalid line {}: {}", i + 1, line));
+            let (io, variables) = line.split_once('=').ok_or_else(error)?;
+            let mut io = io.split_whitespace();
+            let (causality, kind) = match (io.next(), io.next(), io.next()) {
+                (Some(causality), Some(kind), None) => (causality, kind),
+                _ => return Err(error()),
+            };
+            let kind: IOKind = kind
+                .parse()
+                .map_err(|_| FmiError::Mapping(format!("unknown IO variant {}", kind)))?;
+            let variables: Vec<String> = variables
+                .split(',')
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .collect();
+            match causality {
+                "input" => this.inputs.push((kind, variables)),
+                "output" => this.outputs.push((kind, variables)),
+                _ => return Err(error()),
+            }
+        }
+        Ok(this)
+    }
+}
+impl fmt::Display for Mapping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (causality, ios) in [("input", &self.inputs), ("output", &self.outputs)] {
+            for (kind, variables) in ios.iter() {
+                writeln!(f, "{} {} = {}", causality, kind, variables.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// C types of the FMI standards
+pub(crate) mod sys {
+    use std::os::raw::{c_char, c_int, c_void};
+
+    pub type Fmi2Logger =
+        unsafe extern "C" fn(*mut c_void, *const c_char, c_int, *const c_char, *const c_char, ...);
+    #[repr(C)]
+    pub struct Fmi2Callbacks {
+        pub logger: Option<Fmi2Logger>,
+        pub allocate_memory: Option<unsafe extern "C" fn(usize, usize) -> *mut c_void>,
+        pub free_memory: Option<unsafe extern "C" fn(*mut c_void)>,
+        pub step_finished: Option<unsafe extern "C" fn(*mut c_void, c_int)>,
+        pub component_environment: *mut c_void,
+    }
+    pub type Fmi3LogMessage =
+        unsafe extern "C" fn(*mut c_void, c_int, *const c_char, *const c_char);
+
+    pub const FMI2_COSIMULATION: c_int = 1;
+    pub const OK: c_int = 0;
+    pub const WARNING: c_int = 1;
+    pub const ERROR: c_int = 3;
+}
+use sys::{Fmi2Callbacks, Fmi2Logger};
+
+extern "C" {
+    // FMI 2.0 logger in `logger.c`, `env` is a `LogEnv`
+    fn dosio_fmi2_logger(
+        env: *mut c_void,
+        instance: *const c_char,
+        status: c_int,
+        category: *const c_char,
+        format: *const c_char,
+        ...
+    );
+}
+
+/// Callback of the messages logged by an FMU, called with the status and the message
+pub type Logger = Arc<dyn Fn(i32, &str) + Send + Sync>;
+
+// Environment of an FMU instance, `dosio_log_env` in `logger.c`
+#[repr(C)]
+struct LogEnv {
+    log: sys::Fmi3LogMessage,
+    logger: Option<Logger>,
+}
+// Passes a message of the FMU to the logger of the environment, it is the FMI 3.0 logger
+unsafe extern "C" fn log(
+    env: *mut c_void,
+    status: c_int,
+    _category: *const c_char,
+    message: *const c_char,
+) {
+    if message.is_null() {
+        return;
+    }
+    let logger = match (env as *const LogEnv).as_ref() {
+        Some(LogEnv {
+            logger: Some(logger),
+            ..
+        }) => logger,
+        _ => return,
+    };
+    let message = CStr::from_ptr(message).to_string_lossy();
+    // a panic of the logger does not unwind into the FMU
+    let _ = panic::catch_unwind(panic::AssertUnwindSafe(|| logger(status, &message)));
+}
+
+// FMI 2.0 co-simulation functions
+#[derive(Clone, Copy)]
+struct Fmi2 {
+    instantiate: unsafe extern "C" fn(
+        *const c_char,
+        c_int,
+        *const c_char,
+        *const c_char,
+        *const Fmi2Callbacks,
+        c_int,
+        c_int,
+    ) -> *mut c_void,
+    setup_experiment: unsafe extern "C" fn(*mut c_void, c_int, f64, f64, c_int, f64) -> c_int,
+    enter_initialization_mode: unsafe extern "C" fn(*mut c_void) -> c_int,
+    exit_initialization_mode: unsafe extern "C" fn(*mut c_void) -> c_int,
+    set_real: unsafe extern "C" fn(*mut c_void, *const u32, usize, *const f64) -> c_int,
+    get_real: unsafe extern "C" fn(*mut c_void, *const u32, usize, *mut f64) -> c_int,
+    do_step: unsafe extern "C" fn(*mut c_void, f64, f64, c_int) -> c_int,
+    terminate: unsafe extern "C" fn(*mut c_void) -> c_int,
+    reset: unsafe extern "C" fn(*mut c_void) -> c_int,
+    free_instance: unsafe extern "C" fn(*mut c_void),
+}
+// FMI 3.0 co-simulation functions
+#[derive(Clone, Copy)]
+#[allow(clippy::type_complexity)]
+struct Fmi3 {
+    instantiate_co_simulation: unsafe extern "C" fn(
+        *const c_char,
+        *const c_char,
+        *const c_char,
+        bool,
+        bool,
+        bool,
+        bool,
+        *const u32,
+        usize,
+        *mut c_void,
+        Option<sys::Fmi3LogMessage>,
+        *const c_void,
+    ) -> *mut c_void,
+    enter_initialization_mode:
+        unsafe extern "C" fn(*mut c_void, bool, f64, f64, bool, f64) -> c_int,
+    exit_initialization_mode: unsafe extern "C" fn(*mut c_void) -> c_int,
+    set_float64: unsafe extern "C" fn(*mut c_void, *const u32, usize, *const f64, usize) -> c_int,
+    get_float64: unsafe extern "C" fn(*mut c_void, *const u32, usize, *mut f64, usize) -> c_int,
+    do_step: unsafe extern "C" fn(
+        *mut c_void,
+        f64,
+        f64,
+        bool,
+        *mut bool,
+        *mut bool,
+        *mut bool,
+        *mut f64,
+    ) -> c_int,
+    terminate: unsafe extern "C" fn(*mut c_void) -> c_int,
+    reset: unsafe extern "C" fn(*mut c_void) -> c_int,
+    free_instance: unsafe extern "C" fn(*mut c_void),
+}
+#[derive(Clone, Copy)]
+enum Api {
+    V2(Fmi2),
+    V3(Fmi3),
+}
+
+unsafe fn symbol<T: Copy>(library: &Library, name: &str) -> Result<T, FmiError> {
+    library
+        .get::<T>(format!("{}\0", name).as_bytes())
+        .map(|symbol| *symbol)
+        .map_err(|e| FmiError::Library(format!("{}: {}", name, e)))
+}
+fn check(function: &'static str, status: c_int) -> Result<(), FmiError> {
+    if status == sys::OK || status == sys::WARNING {
+        Ok(())
+    } else {
+        Err(FmiError::Status { function, status })
+    }
+}
+fn c_string(value: &str) -> Result<CString, FmiError> {
+    CString::new(value).map_err(|_| FmiError::Mapping(format!("{:?} contains a NUL byte", value)))
+}
+
+// FMU library and the directory the FMU is extracted to
+struct Unpacked {
+    library: Library,
+    dir: Option<PathBuf>,
+}
+impl Drop for Unpacked {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.dir {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// Co-simulation FMU
+pub struct Fmu {
+    description: ModelDescription,
+    unpacked: Arc<Unpacked>,
+    resources: PathBuf,
+    logger: Option<Logger>,
+}
+impl Fmu {
+    /// Loads the FMU archive at `path`
+    ///
+    /// The archive is extracted to a temporary directory, removed when the FMU and all its instances are dropped
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, FmiError> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "dosio-fmu-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut archive = zip::ZipArchive::new(fs::File::open(path)?)?;
+        archive.extract(&dir)?;
+        let mut fmu = Self::from_dir(&dir);
+        if let Ok(fmu) = &mut fmu {
+            if let Some(unpacked) = Arc::get_mut(&mut fmu.unpacked) {
+                unpacked.dir = Some(dir);
+            }
+        } else {
+            let _ = fs::remove_dir_all(&dir);
+        }
+        fmu
+    }
+    /// Loads an extracted FMU
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self, FmiError> {
+        let dir = dir.as_ref();
+        let description: ModelDescription =
+            fs::read_to_string(dir.join("modelDescription.xml"))?.parse()?;
+        let binary = dir
+            .join("binaries")
+            .join(description.version.platform())
+            .join(&description.model_identifier)
+            .with_extension(consts::DLL_EXTENSION);
+        // Safety: loading the library runs its initialization routines, the FMU is trusted
+        let library = unsafe { Library::new(&binary) }
+            .map_err(|e| FmiError::Library(format!("{}: {}", binary.display(), e)))?;
+        let mut fmu = Self::from_library(description, library);
+        fmu.resources = dir.join("resources");
+        Ok(fmu)
+    }
+    /// Creates an FMU from its model description and its loaded library
+    pub fn from_library(description: ModelDescription, library: Library) -> Self {
+        Self {
+            description,
+            unpacked: Arc::new(Unpacked { library, dir: None }),
+            resources: PathBuf::new(),
+            logger: None,
+        }
+    }
+    /// Sets the callback of the messages logged by the instances of the FMU, the messages are discarded otherwise
+    pub fn logger<F>(mut self, logger: F) -> Self
+    where
+        F: Fn(i32, &str) + Send + Sync + 'static,
+    {
+        self.logger = Some(Arc::new(logger));
+        self
+    }
+    /// Returns the model description
+    pub fn description(&self) -> &ModelDescription {
+        &self.description
+    }
+    // Returns the value references of the mapped variables
+    fn value_references(
+        &self,
+        ios: &[(IOKind, Vec<String>)],
+        causality: Causality,
+    ) -> Result<Vec<(IOKind, Vec<u32>)>, FmiError> {
+        ios.iter()
+            .map(|(kind, names)| {
+                let vrs = names
+                    .iter()
+                    .map(|name| match self.description.variable(name) {
+                        Some(variable) if variable.causality == causality => {
+                            Ok(variable.value_reference)
+                        }
+                        Some(_) => Err(FmiError::Mapping(format!(
+                            "{} is not an {} variable",
+                            name,
+                            causality.as_str()
+                        ))),
+                        None => Err(FmiError::Mapping(format!(
+                            "{} is not a real variable of {}",
+                            name, self.description.model_name
+                        ))),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((*kind, vrs))
+            })
+            .collect()
+    }
+    unsafe fn api(&self) -> Result<Api, FmiError> {
+        let library = &self.unpacked.library;
+        Ok(match self.description.version {
+            Version::V2 => Api::V2(Fmi2 {
+                instantiate: symbol(library, "fmi2Instantiate")?,
+                setup_experiment: symbol(library, "fmi2SetupExperiment")?,
+                enter_initialization_mode: symbol(library, "fmi2EnterInitializationMode")?,
+                exit_initialization_mode: symbol(library, "fmi2ExitInitializationMode")?,
+                set_real: symbol(library, "fmi2SetReal")?,
+                get_real: symbol(library, "fmi2GetReal")?,
+                do_step: symbol(library, "fmi2DoStep")?,
+                terminate: symbol(library, "fmi2Terminate")?,
+                reset: symbol(library, "fmi2Reset")?,
+                free_instance: symbol(library, "fmi2FreeInstance")?,
+            }),
+            Version::V3 => Api::V3(Fmi3 {
+                instantiate_co_simulation: symbol(library, "fmi3InstantiateCoSimulation")?,
+                enter_initialization_mode: symbol(library, "fmi3EnterInitializationMode")?,
+                exit_initialization_mode: symbol(library, "fmi3ExitInitializationMode")?,
+                set_float64: symbol(library, "fmi3SetFloat64")?,
+                get_float64: symbol(library, "fmi3GetFloat64")?,
+                do_step: symbol(library, "fmi3DoStep")?,
+                terminate: symbol(library, "fmi3Terminate")?,
+                reset: symbol(library, "fmi3Reset")?,
+                free_instance: symbol(library, "fmi3FreeInstance")?,
+            }),
+        })
+    }
+    /// Instantiates the FMU as a [`Dos`] component named `name`, with the communication step size `step_size`
+    pub fn instantiate(
+        &self,
+        name: &str,
+        mapping: &Mapping,
+        step_size: f64,
+    ) -> Result<FmuComponent, FmiError> {
+        let inputs = self.value_references(&mapping.inputs, Causality::Input)?;
+        let outputs = self.value_references(&mapping.outputs, Causality::Output)?;
+        let api = unsafe { self.api()? };
+        let instance_name = c_string(name)?;
+        let token = c_string(&self.description.token)?;
+        let mut callbacks = None;
+        let log_env = Box::new(LogEnv {
+            log,
+            logger: self.logger.clone(),
+        });
+        let env = &*log_env as *const LogEnv as *mut c_void;
+        let instance = match api {
+            Api::V2(fmi) => {
+                let resources = c_string(&format!("file://{}", self.resources.display()))?;
+                let fmi2_callbacks = Box::new(Fmi2Callbacks {
+                    logger: Some(dosio_fmi2_logger as Fmi2Logger),
+                    allocate_memory: Some(libc::calloc),
+                    free_memory: Some(libc::free),
+                    step_finished: None,
+                    component_environment: env,
+                });
+                let instance = unsafe {
+                    (fmi.instantiate)(
+                        instance_name.as_ptr(),
+                        sys::FMI2_COSIMULATION,
+                        token.as_ptr(),
+                        resources.as_ptr(),
+                        &*fmi2_callbacks,
+                        0,
+                        0,
+                    )
+                };
+                callbacks = Some(fmi2_callbacks);
+                instance
+            }
+            Api::V3(fmi) => {
+                let mut resources = self.resources.clone().into_os_string();
+                resources.push(std::path::MAIN_SEPARATOR.to_string());
+                let resources = c_string(&resources.to_string_lossy())?;
+                unsafe {
+                    (fmi.instantiate_co_simulation)(
+                        instance_name.as_ptr(),
+                        token.as_ptr(),
+                        resources.as_ptr(),
+                        false,
+                        false,
+                        false,
+                        false,
+                        ptr::null(),
+                        0,
+                        env,
+                        Some(log),
+                        ptr::null(),
+                    )
+                }
+            }
+        };
+        if instance.is_null() {
+            return Err(FmiError::Instantiate(
+                self.description.model_identifier.clone(),
+            ));
+        }
+        let mut component = FmuComponent {
+            api,
+            instance,
+            _callbacks: callbacks,
+            _log_env: log_env,
+            _instance_name: instance_name,
+            _unpacked: self.unpacked.clone(),
+            inputs,
+            outputs,
+            time: 0f64,
+            step_size,
+        };
+        component.initialize()?;
+        Ok(component)
+    }
+}
+
+/// FMU instance wrapped as a [`Dos`] component
+pub struct FmuComponent {
+    api: Api,
+    instance: *mut c_void,
+    // the FMI 2.0 callbacks, the environment and the instance name must outlive the instance
+    _callbacks: Option<Box<Fmi2Callbacks>>,
+    _log_env: Box<LogEnv>,
+    _instance_name: CString,
+    _unpacked: Arc<Unpacked>,
+    inputs: Vec<(IOKind, Vec<u32>)>,
+    outputs: Vec<(IOKind, Vec<u32>)>,
+    time: f64,
+    step_size: f64,
+}
+// Safety: an FMU instance is only called from one thread at a time, through `&mut self`
+unsafe impl Send for FmuComponent {}
+impl FmuComponent {
+    /// Returns the current communication point
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+    fn initialize(&mut self) -> Result<(), FmiError> {
+        let instance = self.instance;
+        unsafe {
+            match self.api {
+                Api::V2(fmi) => {
+                    check(
+                        "fmi2SetupExperiment",
+                        (fmi.setup_experiment)(instance, 0, 0., self.time, 0, 0.),
+                    )?;
+                    check(
+                        "fmi2EnterInitializationMode",
+                        (fmi.enter_initialization_mode)(instance),
+                    )?;
+                    check(
+                        "fmi2ExitInitializationMode",
+                        (fmi.exit_initialization_mode)(instance),
+                    )
+                }
+                Api::V3(fmi) => {
+                    check(
+                        "fmi3EnterInitializationMode",
+                        (fmi.enter_initialization_mode)(instance, false, 0., self.time, false, 0.),
+                    )?;
+                    check(
+                        "fmi3ExitInitializationMode",
+                        (fmi.exit_initialization_mode)(instance),
+                    )
+                }
+            }
+        }
+    }
+    fn set_real(&mut self, vrs: &[u32], values: &[f64]) -> Result<(), FmiError> {
+        unsafe {
+            match self.api {
+                Api::V2(fmi) => check(
+                    "fmi2SetReal",
+                    (fmi.set_real)(self.instance, vrs.as_ptr(), vrs.len(), values.as_ptr()),
+                ),
+                Api::V3(fmi) => check(
+                    "fmi3SetFloat64",
+                    (fmi.set_float64)(
+                        self.instance,
+                        vrs.as_ptr(),
+                        vrs.len(),
+                        values.as_ptr(),
+                        values.len(),
+                    ),
+                ),
+            }
+        }
+    }
+    fn get_real(&mut self, vrs: &[u32]) -> Result<Vec<f64>, FmiError> {
+        let mut values = vec![0f64; vrs.len()];
+        unsafe {
+            match self.api {
+                Api::V2(fmi) => check(
+                    "fmi2GetReal",
+                    (fmi.get_real)(self.instance, vrs.as_ptr(), vrs.len(), values.as_mut_ptr()),
+                ),
+                Api::V3(fmi) => check(
+                    "fmi3GetFloat64",
+                    (fmi.get_float64)(
+                        self.instance,
+                        vrs.as_ptr(),
+                        vrs.len(),
+                        values.as_mut_ptr(),
+                        values.len(),
+                    ),
+                ),
+            }?;
+        }
+        Ok(values)
+    }
+}
+impl Dos for FmuComponent {
+    type Input = Vec<f64>;
+    type Output = Vec<f64>;
+    fn outputs(&mut self) -> Option<Vec<IO<Self::Output>>> {
+        let outputs = self.outputs.clone();
+        outputs
+            .into_iter()
+            .map(|(kind, vrs)| self.get_real(&vrs).ok().map(|values| kind.io(Some(values))))
+            .collect()
+    }
+    fn inputs(&mut self, data: Option<Vec<IO<Self::Input>>>) -> Result<&mut Self, DOSIOSError> {
+        let data = data.unwrap_or_default();
+        let inputs = self.inputs.clone();
+        for (kind, vrs) in &inputs {
+            let io_error = |e: BoxedError| DOSIOSError::Inputs(e).with_io(&kind.io::<()>(None));
+            let values = data
+                .iter()
+                .find(|io| io.io_kind() == *kind)
+                .and_then(|io| io.as_ref())
+                .ok_or_else(|| io_error(format!("{} input is missing", kind).into()))?;
+            if values.len() != vrs.len() {
+                return Err(io_error(
+                    format!(
+                        "{} has {} elements instead of {}",
+                        kind,
+                        values.len(),
+                        vrs.len()
+                    )
+                    .into(),
+                ));
+            }
+            self.set_real(vrs, values)
+                .map_err(|e| io_error(Box::new(e)))?;
+        }
+        Ok(self)
+    }
+    fn step(&mut self) -> Result<&mut Self, DOSIOSError> {
+        let (instance, time, step_size) = (self.instance, self.time, self.step_size);
+        unsafe {
+            match self.api {
+                Api::V2(fmi) => check("fmi2DoStep", (fmi.do_step)(instance, time, step_size, 1)),
+                Api::V3(fmi) => {
+                    let (mut event, mut terminate, mut early_return, mut last_time) =
+                        (false, false, false, 0f64);
+                    check(
+                        "fmi3DoStep",
+                        (fmi.do_step)(
+                            instance,
+                            time,
+                            step_size,
+                            true,
+                            &mut event,
+                            &mut terminate,
+                            &mut early_return,
+                            &mut last_time,
+                        ),
+                    )
+                }
+            }
+        }
+        .map_err(|e| DOSIOSError::Step(Box::new(e)))?;
+        self.time += self.step_size;
+        Ok(self)
+    }
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        let status = unsafe {
+            match self.api {
+                Api::V2(fmi) => check("fmi2Reset", (fmi.reset)(self.instance)),
+                Api::V3(fmi) => check("fmi3Reset", (fmi.reset)(self.instance)),
+            }
+        };
+        self.time = 0f64;
+        status
+            .and_then(|_| self.initialize())
+            .map_err(|e| DOSIOSError::Step(Box::new(e)))?;
+        Ok(self)
+    }
+}
+type BoxedError = crate::error::BoxError;
+impl Iterator for FmuComponent {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        Dos::step(self).ok().map(|_| ())
+    }
+}
+impl IOTags for FmuComponent {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        self.outputs.iter().map(|(kind, _)| kind.io(None)).collect()
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        self.inputs.iter().map(|(kind, _)| kind.io(None)).collect()
+    }
+}
+impl Drop for FmuComponent {
+    fn drop(&mut self) {
+        unsafe {
+            match self.api {
+                Api::V2(fmi) => {
+                    (fmi.terminate)(self.instance);
+                    (fmi.free_instance)(self.instance);
+                }
+                Api::V3(fmi) => {
+                    (fmi.terminate)(self.instance);
+                    (fmi.free_instance)(self.instance);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ios;
+
+    #[test]
+    fn mapping() {
+        let mapping: Mapping = "# mount\ninput Pssn = u[1], u[2]\n\noutput SensorData = y\n"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            mapping,
+            Mapping::new()
+                .input(ios!(Pssn), &["u[1]", "u[2]"])
+                .output(ios!(SensorData), &["y"])
+        );
+        assert_eq!(mapping.to_string().parse::<Mapping>().unwrap(), mapping);
+        assert!("input NotAVariant = u".parse::<Mapping>().is_err());
+        assert!("inout Pssn = u".parse::<Mapping>().is_err());
+    }
+}
@@ -0,0 +1,957 @@
+//! FMU export
+//!
+//! A [`Model`] is a [`Dos`] component with the sizes of its inputs and outputs.
+//! The [`export_fmu!`](crate::export_fmu) macro exports the FMI 2.0 and FMI 3.0 co-simulation functions
+//! of a model from a `cdylib` crate and [`Model::package`] zips the library with the model description into an FMU.
+//!
+//! The input and output variables are named after the [`IO`] variants, e.g. `Pssn[1]`, `Pssn[2]`, ...,
+//! with consecutive value references, the inputs first.
+//! Each `DoStep` steps the component once, so the communication step size is the sample time of the component:
+//! the FMU cannot handle a variable communication step size and, if the [`Model::sample_time`] is set,
+//! `DoStep` fails for any other communication step size.
+//! The mandatory FMI functions the model does not support are exported too, and they return an error.
+//!
+//! ```ignore
+//! // lib.rs of a crate with `crate-type = ["cdylib"]`
+//! use dosio::{blocks::Gain, fmi::export::Model, ios};
+//!
+//! pub fn model() -> Model<Gain> {
+//!     Model::new("gain", Gain::new(ios!(Pssn), ios!(SensorData), 2.))
+//!         .input_sizes(vec![(ios!(Pssn), 3)])
+//!         .output_sizes(vec![(ios!(SensorData), 3)])
+//!         .sample_time(1e-3)
+//! }
+//! dosio::export_fmu!(model());
+//! ```
+
+use super::{
+    sys::{self, Fmi2Callbacks, Fmi2Logger, Fmi3LogMessage},
+    Causality, FmiError, ModelDescription, Variable, Version,
+};
+use crate::{
+    error::panic_message,
+    io::{IOKind, FINGERPRINT},
+    Dos, IOTags, IO,
+};
+use std::{
+    ffi::{CStr, CString},
+    fs,
+    io::Write,
+    os::raw::{c_char, c_int, c_void},
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+    ptr, slice,
+};
+
+/// [`Dos`] component exported as an FMU
+pub struct Model<D> {
+    name: String,
+    dos: D,
+    input_sizes: Vec<(IOKind, usize)>,
+    output_sizes: Vec<(IOKind, usize)>,
+    sample_time: Option<f64>,
+    inputs: Vec<f64>,
+    outputs: Vec<f64>,
+}
+impl<D> Model<D>
+where
+    D: Dos<Input = Vec<f64>, Output = Vec<f64>> + IOTags + Iterator + Send,
+{
+    /// Creates a model named `name`, the model identifier of the FMU
+    pub fn new(name: &str, dos: D) -> Self {
+        Self {
+            name: name.to_string(),
+            dos,
+            input_sizes: vec![],
+            output_sizes: vec![],
+            sample_time: None,
+            inputs: vec![],
+            outputs: vec![],
+        }
+    }
+    /// Sets the sizes of the inputs
+    pub fn input_sizes(mut self, sizes: Vec<(IO<()>, usize)>) -> Self {
+        self.input_sizes = sizes.into_iter().map(|(io, n)| (io.io_kind(), n)).collect();
+        self
+    }
+    /// Sets the sizes of the outputs
+    pub fn output_sizes(mut self, sizes: Vec<(IO<()>, usize)>) -> Self {
+        self.output_sizes = sizes.into_iter().map(|(io, n)| (io.io_kind(), n)).collect();
+        self
+    }
+    /// Sets the sample time of the component, the only communication step size accepted by `DoStep`
+    pub fn sample_time(mut self, sample_time: f64) -> Self {
+        self.sample_time = Some(sample_time);
+        self
+    }
+    // Returns the inputs or the outputs in the order of the tags, with their sizes
+    fn layout(
+        tags: Vec<IO<()>>,
+        sizes: &[(IOKind, usize)],
+    ) -> Result<Vec<(IOKind, usize)>, FmiError> {
+        tags.into_iter()
+            .map(|io| {
+                let kind = io.io_kind();
+                sizes
+                    .iter()
+                    .find(|(size_kind, _)| *size_kind == kind)
+                    .copied()
+                    .ok_or_else(|| FmiError::Mapping(format!("the size of {} is missing", kind)))
+            })
+            .collect()
+    }
+    fn inputs_layout(&self) -> Result<Vec<(IOKind, usize)>, FmiError> {
+        Self::layout(self.dos.inputs_tags(), &self.input_sizes)
+    }
+    fn outputs_layout(&self) -> Result<Vec<(IOKind, usize)>, FmiError> {
+        Self::layout(self.dos.outputs_tags(), &self.output_sizes)
+    }
+    /// Returns the GUID (FMI 2.0) or the instantiation token (FMI 3.0) of the model
+    ///
+    /// The token changes with the [`FINGERPRINT`] of the [`IO`] enum
+    pub fn token(&self) -> String {
+        format!("{{{:016x}-{}}}", FINGERPRINT.hash, self.name)
+    }
+    /// Returns the model description
+    pub fn model_description(&self, version: Version) -> Result<ModelDescription, FmiError> {
+        let mut variables = vec![];
+        for (layout, causality, start) in [
+            (self.inputs_layout()?, Causality::Input, Some(0f64)),
+            (self.outputs_layout()?, Causality::Output, None),
+        ] {
+            for (kind, n) in layout {
+                for i in 1..=n {
+                    variables.push(Variable {
+                        name: format!("{}[{}]", kind, i),
+                        value_reference: variables.len() as u32,
+                        causality,
+                        start,
+                    });
+                }
+            }
+        }
+        Ok(ModelDescription {
+            version,
+            model_name: self.name.clone(),
+            model_identifier: self.name.clone(),
+            token: self.token(),
+            step_size: self.sample_time,
+            variable_step_size: false,
+            variables,
+        })
+    }
+    /// Writes the FMU at `fmu` with the model `library`, the `cdylib` where [`export_fmu!`](crate::export_fmu) is invoked
+    pub fn package<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        version: Version,
+        library: P,
+        fmu: Q,
+    ) -> Result<(), FmiError> {
+        package(&self.model_description(version)?, library, fmu)
+    }
+    // Allocates the input and output buffers
+    fn prepare(&mut self) -> Result<(), FmiError> {
+        let n = |layout: Vec<(IOKind, usize)>| layout.iter().map(|(_, n)| n).sum::<usize>();
+        self.inputs = vec![0f64; n(self.inputs_layout()?)];
+        self.outputs = vec![0f64; n(self.outputs_layout()?)];
+        Ok(())
+    }
+}
+
+/// Writes the FMU at `fmu` with the model `description` and the model `library`
+pub fn package<P: AsRef<Path>, Q: AsRef<Path>>(
+    description: &ModelDescription,
+    library: P,
+    fmu: Q,
+) -> Result<(), FmiError> {
+    let binary = fs::read(library)?;
+    let mut archive = zip::ZipWriter::new(fs::File::create(fmu)?);
+    let options = zip::write::FileOptions::default();
+    archive.start_file("modelDescription.xml", options)?;
+    archive.write_all(description.to_xml().as_bytes())?;
+    archive.start_file(
+        format!(
+            "binaries/{}/{}.{}",
+            description.version.platform(),
+            description.model_identifier,
+            std::env::consts::DLL_EXTENSION
+        ),
+        options,
+    )?;
+    archive.write_all(&binary)?;
+    archive.finish()?;
+    Ok(())
+}
+
+// Object-safe interface of the exported models
+trait Simulation: Send {
+    fn set_real(&mut self, vrs: &[u32], values: &[f64]) -> Result<(), String>;
+    fn get_real(&mut self, vrs: &[u32], values: &mut [f64]) -> Result<(), String>;
+    fn do_step(&mut self, step_size: f64) -> Result<(), String>;
+    fn reset(&mut self) -> Result<(), String>;
+}
+impl<D> Simulation for Model<D>
+where
+    D: Dos<Input = Vec<f64>, Output = Vec<f64>> + IOTags + Iterator + Send,
+{
+    fn set_real(&mut self, vrs: &[u32], values: &[f64]) -> Result<(), String> {
+        for (&vr, &value) in vrs.iter().zip(values) {
+            *self
+                .inputs
+                .get_mut(vr as usize)
+                .ok_or_else(|| format!("{} is not an input value reference", vr))? = value;
+        }
+        Ok(())
+    }
+    fn get_real(&mut self, vrs: &[u32], values: &mut [f64]) -> Result<(), String> {
+        let n_input = self.inputs.len();
+        for (&vr, value) in vrs.iter().zip(values) {
+            *value = match (vr as usize).checked_sub(n_input) {
+                Some(i) if i < self.outputs.len() => self.outputs[i],
+                _ if (vr as usize) < n_input => self.inputs[vr as usize],
+                _ => return Err(format!("{} is not a value reference", vr)),
+            };
+        }
+        Ok(())
+    }
+    fn do_step(&mut self, step_size: f64) -> Result<(), String> {
+        if let Some(sample_time) = self.sample_time {
+            if (step_size - sample_time).abs() > 1e-9 * sample_time.abs() {
+                return Err(format!(
+                    "the communication step size {} is not the sample time {}",
+                    step_size, sample_time
+                ));
+            }
+        }
+        let mut values = self.inputs.as_slice();
+        let mut inputs = vec![];
+        for (kind, n) in self.inputs_layout().map_err(|e| e.to_string())? {
+            let (head, tail) = values.split_at(n);
+            inputs.push(kind.io(Some(head.to_vec())));
+            values = tail;
+        }
+        self.dos
+            .inputs(Some(inputs))
+            .and_then(|dos| dos.step())
+            .map_err(|e| e.report().to_string())?;
+        let outputs = self.dos.outputs().unwrap_or_default();
+        let mut offset = 0;
+        for (kind, n) in self.outputs_layout().map_err(|e| e.to_string())? {
+            let values = outputs
+                .iter()
+                .find(|io| io.io_kind() == kind)
+                .and_then(|io| io.as_ref())
+                .ok_or_else(|| format!("{} output is missing", kind))?;
+            if values.len() != n {
+                return Err(format!(
+                    "{} has {} elements instead of {}",
+                    kind,
+                    values.len(),
+                    n
+                ));
+            }
+            self.outputs[offset..offset + n].copy_from_slice(values);
+            offset += n;
+        }
+        Ok(())
+    }
+    fn reset(&mut self) -> Result<(), String> {
+        self.dos.reset().map_err(|e| e.report().to_string())?;
+        self.inputs.iter_mut().for_each(|u| *u = 0f64);
+        self.outputs.iter_mut().for_each(|y| *y = 0f64);
+        Ok(())
+    }
+}
+
+enum Logger {
+    Fmi2(Fmi2Logger, *mut c_void),
+    Fmi3(Fmi3LogMessage, *mut c_void),
+    None,
+}
+impl Logger {
+    fn error(&self, name: &CStr, message: &str) {
+        let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+        let category = b"logStatusError\0".as_ptr() as *const c_char;
+        unsafe {
+            match *self {
+                Logger::Fmi2(logger, env) => logger(
+                    env,
+                    name.as_ptr(),
+                    sys::ERROR,
+                    category,
+                    b"%s\0".as_ptr() as *const c_char,
+                    message.as_ptr(),
+                ),
+                Logger::Fmi3(log_message, env) => {
+                    log_message(env, sys::ERROR, category, message.as_ptr())
+                }
+                Logger::None => (),
+            }
+        }
+    }
+}
+
+// FMU instance, the `fmi2Component` or `fmi3Instance` handle
+struct Instance {
+    model: Box<dyn Simulation>,
+    name: CString,
+    logger: Logger,
+}
+impl Instance {
+    fn status(&self, result: Result<(), String>) -> c_int {
+        match result {
+            Ok(()) => sys::OK,
+            Err(e) => {
+                self.logger.error(&self.name, &e);
+                sys::ERROR
+            }
+        }
+    }
+}
+
+unsafe fn instantiate<D, F>(
+    model: F,
+    name: *const c_char,
+    token: *const c_char,
+    logger: Logger,
+) -> *mut c_void
+where
+    D: Dos<Input = Vec<f64>, Output = Vec<f64>> + IOTags + Iterator + Send + 'static,
+    F: FnOnce() -> Model<D>,
+{
+    let name = if name.is_null() {
+        CString::default()
+    } else {
+        CStr::from_ptr(name).to_owned()
+    };
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut model = model();
+        let expected = model.token();
+        if token.is_null() || CStr::from_ptr(token).to_bytes() != expected.as_bytes() {
+            return Err(format!("the instantiation token is not {}", expected));
+        }
+        model.prepare().map_err(|e| e.to_string())?;
+        Ok(model)
+    }))
+    .unwrap_or_else(|payload| {
+        Err(format!(
+            "the model panicked: {}",
+            panic_message(payload.as_ref())
+        ))
+    });
+    match result {
+        Ok(model) => Box::into_raw(Box::new(Instance {
+            model: Box::new(model),
+            name,
+            logger,
+        })) as *mut c_void,
+        Err(e) => {
+            logger.error(&name, &e);
+            ptr::null_mut()
+        }
+    }
+}
+
+unsafe fn with_instance<F>(instance: *mut c_void, f: F) -> c_int
+where
+    F: FnOnce(&mut Instance) -> Result<(), String>,
+{
+    match (instance as *mut Instance).as_mut() {
+        Some(instance) => {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| f(&mut *instance)))
+                .unwrap_or_else(|payload| {
+                    Err(format!(
+                        "the model panicked: {}",
+                        panic_message(payload.as_ref())
+                    ))
+                });
+            instance.status(result)
+        }
+        None => sys::ERROR,
+    }
+}
+unsafe fn values<'a, T>(values: *const T, n: usize) -> &'a [T] {
+    if n == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(values, n)
+    }
+}
+unsafe fn values_mut<'a, T>(values: *mut T, n: usize) -> &'a mut [T] {
+    if n == 0 {
+        &mut []
+    } else {
+        slice::from_raw_parts_mut(values, n)
+    }
+}
+
+// The functions below are the bodies of the functions exported by `export_fmu!`
+
+#[doc(hidden)]
+pub unsafe fn fmi2_instantiate<D, F>(
+    model: F,
+    name: *const c_char,
+    fmu_type: c_int,
+    guid: *const c_char,
+    callbacks: *const Fmi2Callbacks,
+) -> *mut c_void
+where
+    D: Dos<Input = Vec<f64>, Output = Vec<f64>> + IOTags + Iterator + Send + 'static,
+    F: FnOnce() -> Model<D>,
+{
+    let logger = match callbacks.as_ref() {
+        Some(Fmi2Callbacks {
+            logger: Some(logger),
+            component_environment,
+            ..
+        }) => Logger::Fmi2(*logger, *component_environment),
+        _ => Logger::None,
+    };
+    if fmu_type != sys::FMI2_COSIMULATION {
+        return ptr::null_mut();
+    }
+    instantiate(model, name, guid, logger)
+}
+#[doc(hidden)]
+pub unsafe fn fmi3_instantiate<D, F>(
+    model: F,
+    name: *const c_char,
+    token: *const c_char,
+    environment: *mut c_void,
+    log_message: Option<Fmi3LogMessage>,
+) -> *mut c_void
+where
+    D: Dos<Input = Vec<f64>, Output = Vec<f64>> + IOTags + Iterator + Send + 'static,
+    F: FnOnce() -> Model<D>,
+{
+    let logger = match log_message {
+        Some(log_message) => Logger::Fmi3(log_message, environment),
+        None => Logger::None,
+    };
+    instantiate(model, name, token, logger)
+}
+#[doc(hidden)]
+pub unsafe fn free_instance(instance: *mut c_void) {
+    if !instance.is_null() {
+        let instance = Box::from_raw(instance as *mut Instance);
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(instance)));
+    }
+}
+#[doc(hidden)]
+pub unsafe fn ok(instance: *mut c_void) -> c_int {
+    with_instance(instance, |_| Ok(()))
+}
+#[doc(hidden)]
+pub unsafe fn unsupported(instance: *mut c_void, function: &str) -> c_int {
+    with_instance(instance, |_| Err(format!("{} is not supported", function)))
+}
+#[doc(hidden)]
+pub unsafe fn reset(instance: *mut c_void) -> c_int {
+    with_instance(instance, |instance| instance.model.reset())
+}
+#[doc(hidden)]
+pub unsafe fn set_real(
+    instance: *mut c_void,
+    vr: *const u32,
+    nvr: usize,
+    value: *const f64,
+) -> c_int {
+    with_instance(instance, |instance| {
+        instance.model.set_real(values(vr, nvr), values(value, nvr))
+    })
+}
+#[doc(hidden)]
+pub unsafe fn get_real(
+    instance: *mut c_void,
+    vr: *const u32,
+    nvr: usize,
+    value: *mut f64,
+) -> c_int {
+    with_instance(instance, |instance| {
+        instance
+            .model
+            .get_real(values(vr, nvr), values_mut(value, nvr))
+    })
+}
+#[doc(hidden)]
+pub unsafe fn do_step(instance: *mut c_void, step_size: f64) -> c_int {
+    with_instance(instance, |instance| instance.model.do_step(step_size))
+}
+#[doc(hidden)]
+pub unsafe fn fmi3_do_step(
+    instance: *mut c_void,
+    time: f64,
+    step_size: f64,
+    event_handling_needed: *mut bool,
+    terminate_simulation: *mut bool,
+    early_return: *mut bool,
+    last_successful_time: *mut f64,
+) -> c_int {
+    for flag in [event_handling_needed, terminate_simulation, early_return] {
+        if let Some(flag) = flag.as_mut() {
+            *flag = false;
+        }
+    }
+    if let Some(last_successful_time) = last_successful_time.as_mut() {
+        *last_successful_time = time + step_size;
+    }
+    do_step(instance, step_size)
+}
+
+/// Exports the FMI 2.0 and FMI 3.0 co-simulation functions of a [`Model`]
+///
+/// The argument is an expression evaluated to a new [`Model`] for each FMU instance
+#[macro_export]
+macro_rules! export_fmu {
+    ($model:expr) => {
+        mod __dosio_fmu {
+            use super::*;
+            use ::std::os::raw::{c_char, c_int, c_void};
+            use $crate::fmi::export as fmu;
+
+            #[no_mangle]
+            pub extern "C" fn fmi2GetTypesPlatform() -> *const c_char {
+                b"default\0".as_ptr() as *const c_char
+            }
+            #[no_mangle]
+            pub extern "C" fn fmi2GetVersion() -> *const c_char {
+                b"2.0\0".as_ptr() as *const c_char
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2SetDebugLogging(
+                c: *mut c_void,
+                _logging_on: c_int,
+                _n_categories: usize,
+                _categories: *const *const c_char,
+            ) -> c_int {
+                fmu::ok(c)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2Instantiate(
+                name: *const c_char,
+                fmu_type: c_int,
+                guid: *const c_char,
+                _resources: *const c_char,
+                callbacks: *const c_void,
+                _visible: c_int,
+                _logging_on: c_int,
+            ) -> *mut c_void {
+                fmu::fmi2_instantiate(|| $model, name, fmu_type, guid, callbacks as *const _)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2FreeInstance(c: *mut c_void) {
+                fmu::free_instance(c)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2SetupExperiment(
+                c: *mut c_void,
+                _tolerance_defined: c_int,
+                _tolerance: f64,
+                _start_time: f64,
+                _stop_time_defined: c_int,
+                _stop_time: f64,
+            ) -> c_int {
+                fmu::ok(c)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2EnterInitializationMode(c: *mut c_void) -> c_int {
+                fmu::ok(c)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2ExitInitializationMode(c: *mut c_void) -> c_int {
+                fmu::ok(c)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2Terminate(c: *mut c_void) -> c_int {
+                fmu::ok(c)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2Reset(c: *mut c_void) -> c_int {
+                fmu::reset(c)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2SetReal(
+                c: *mut c_void,
+                vr: *const u32,
+                nvr: usize,
+                value: *const f64,
+            ) -> c_int {
+                fmu::set_real(c, vr, nvr, value)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2GetReal(
+                c: *mut c_void,
+                vr: *const u32,
+                nvr: usize,
+                value: *mut f64,
+            ) -> c_int {
+                fmu::get_real(c, vr, nvr, value)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi2DoStep(
+                c: *mut c_void,
+                _current_communication_point: f64,
+                communication_step_size: f64,
+                _no_set_fmu_state_prior_to_current_point: c_int,
+            ) -> c_int {
+                fmu::do_step(c, communication_step_size)
+            }
+            $crate::__fmu_stubs! {
+                fmi2CancelStep();
+                fmi2GetInteger(_vr: *const u32, _nvr: usize, _value: *mut c_int);
+                fmi2GetBoolean(_vr: *const u32, _nvr: usize, _value: *mut c_int);
+                fmi2GetString(_vr: *const u32, _nvr: usize, _value: *mut *const c_char);
+                fmi2SetInteger(_vr: *const u32, _nvr: usize, _value: *const c_int);
+                fmi2SetBoolean(_vr: *const u32, _nvr: usize, _value: *const c_int);
+                fmi2SetString(_vr: *const u32, _nvr: usize, _value: *const *const c_char);
+                fmi2GetFMUstate(_state: *mut *mut c_void);
+                fmi2SetFMUstate(_state: *mut c_void);
+                fmi2FreeFMUstate(_state: *mut *mut c_void);
+                fmi2SerializedFMUstateSize(_state: *mut c_void, _size: *mut usize);
+                fmi2SerializeFMUstate(_state: *mut c_void, _serialized: *mut c_char, _size: usize);
+                fmi2DeSerializeFMUstate(
+                    _serialized: *const c_char,
+                    _size: usize,
+                    _state: *mut *mut c_void
+                );
+                fmi2GetDirectionalDerivative(
+                    _unknown: *const u32,
+                    _n_unknown: usize,
+                    _known: *const u32,
+                    _n_known: usize,
+                    _dv_known: *const f64,
+                    _dv_unknown: *mut f64
+                );
+                fmi2SetRealInputDerivatives(
+                    _vr: *const u32,
+                    _nvr: usize,
+                    _order: *const c_int,
+                    _value: *const f64
+                );
+                fmi2GetRealOutputDerivatives(
+                    _vr: *const u32,
+                    _nvr: usize,
+                    _order: *const c_int,
+                    _value: *mut f64
+                );
+                fmi2GetStatus(_kind: c_int, _value: *mut c_int);
+                fmi2GetRealStatus(_kind: c_int, _value: *mut f64);
+                fmi2GetIntegerStatus(_kind: c_int, _value: *mut c_int);
+                fmi2GetBooleanStatus(_kind: c_int, _value: *mut c_int);
+                fmi2GetStringStatus(_kind: c_int, _value: *mut *const c_char);
+            }
+
+            #[no_mangle]
+            pub extern "C" fn fmi3GetVersion() -> *const c_char {
+                b"3.0\0".as_ptr() as *const c_char
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi3SetDebugLogging(
+                instance: *mut c_void,
+                _logging_on: bool,
+                _n_categories: usize,
+                _categories: *const *const c_char,
+            ) -> c_int {
+                fmu::ok(instance)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi3InstantiateCoSimulation(
+                name: *const c_char,
+                token: *const c_char,
+                _resources: *const c_char,
+                _visible: bool,
+                _logging_on: bool,
+                _event_mode_used: bool,
+                _early_return_allowed: bool,
+                _required_intermediate_variables: *const u32,
+                _n_required_intermediate_variables: usize,
+                environment: *mut c_void,
+                log_message: Option<
+                    unsafe extern "C" fn(*mut c_void, c_int, *const c_char, *const c_char),
+                >,
+                _intermediate_update: *const c_void,
+            ) -> *mut c_void {
+                fmu::fmi3_instantiate(|| $model, name, token, environment, log_message)
+            }
+            #[no_mangle]
+            pub extern "C" fn fmi3InstantiateModelExchange(
+                _name: *const c_char,
+                _token: *const c_char,
+                _resources: *const c_char,
+                _visible: bool,
+                _logging_on: bool,
+                _environment: *mut c_void,
+                _log_message: *const c_void,
+            ) -> *mut c_void {
+                ::std::ptr::null_mut()
+            }
+            #[no_mangle]
+            pub extern "C" fn fmi3InstantiateScheduledExecution(
+                _name: *const c_char,
+                _token: *const c_char,
+                _resources: *const c_char,
+                _visible: bool,
+                _logging_on: bool,
+                _environment: *mut c_void,
+                _log_message: *const c_void,
+                _clock_update: *const c_void,
+                _lock_preemption: *const c_void,
+                _unlock_preemption: *const c_void,
+            ) -> *mut c_void {
+                ::std::ptr::null_mut()
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi3FreeInstance(instance: *mut c_void) {
+                fmu::free_instance(instance)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi3EnterInitializationMode(
+                instance: *mut c_void,
+                _tolerance_defined: bool,
+                _tolerance: f64,
+                _start_time: f64,
+                _stop_time_defined: bool,
+                _stop_time: f64,
+            ) -> c_int {
+                fmu::ok(instance)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi3ExitInitializationMode(instance: *mut c_void) -> c_int {
+                fmu::ok(instance)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi3EnterStepMode(instance: *mut c_void) -> c_int {
+                fmu::ok(instance)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi3Terminate(instance: *mut c_void) -> c_int {
+                fmu::ok(instance)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi3Reset(instance: *mut c_void) -> c_int {
+                fmu::reset(instance)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi3SetFloat64(
+                instance: *mut c_void,
+                vr: *const u32,
+                nvr: usize,
+                value: *const f64,
+                n_value: usize,
+            ) -> c_int {
+                if nvr != n_value {
+                    return fmu::unsupported(instance, "fmi3SetFloat64 of arrays");
+                }
+                fmu::set_real(instance, vr, nvr, value)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi3GetFloat64(
+                instance: *mut c_void,
+                vr: *const u32,
+                nvr: usize,
+                value: *mut f64,
+                n_value: usize,
+            ) -> c_int {
+                if nvr != n_value {
+                    return fmu::unsupported(instance, "fmi3GetFloat64 of arrays");
+                }
+                fmu::get_real(instance, vr, nvr, value)
+            }
+            #[no_mangle]
+            pub unsafe extern "C" fn fmi3DoStep(
+                instance: *mut c_void,
+                current_communication_point: f64,
+                communication_step_size: f64,
+                _no_set_fmu_state_prior_to_current_point: bool,
+                event_handling_needed: *mut bool,
+                terminate_simulation: *mut bool,
+                early_return: *mut bool,
+                last_successful_time: *mut f64,
+            ) -> c_int {
+                fmu::fmi3_do_step(
+                    instance,
+                    current_communication_point,
+                    communication_step_size,
+                    event_handling_needed,
+                    terminate_simulation,
+                    early_return,
+                    last_successful_time,
+                )
+            }
+            $crate::__fmu_stubs! {
+                fmi3GetFloat32(_vr: *const u32, _nvr: usize, _value: *mut f32, _n_value: usize);
+                fmi3GetInt8(_vr: *const u32, _nvr: usize, _value: *mut i8, _n_value: usize);
+                fmi3GetUInt8(_vr: *const u32, _nvr: usize, _value: *mut u8, _n_value: usize);
+                fmi3GetInt16(_vr: *const u32, _nvr: usize, _value: *mut i16, _n_value: usize);
+                fmi3GetUInt16(_vr: *const u32, _nvr: usize, _value: *mut u16, _n_value: usize);
+                fmi3GetInt32(_vr: *const u32, _nvr: usize, _value: *mut i32, _n_value: usize);
+                fmi3GetUInt32(_vr: *const u32, _nvr: usize, _value: *mut u32, _n_value: usize);
+                fmi3GetInt64(_vr: *const u32, _nvr: usize, _value: *mut i64, _n_value: usize);
+                fmi3GetUInt64(_vr: *const u32, _nvr: usize, _value: *mut u64, _n_value: usize);
+                fmi3GetBoolean(_vr: *const u32, _nvr: usize, _value: *mut bool, _n_value: usize);
+                fmi3GetString(
+                    _vr: *const u32,
+                    _nvr: usize,
+                    _value: *mut *const c_char,
+                    _n_value: usize
+                );
+                fmi3GetBinary(
+                    _vr: *const u32,
+                    _nvr: usize,
+                    _value_sizes: *mut usize,
+                    _value: *mut *const u8,
+                    _n_value: usize
+                );
+                fmi3GetClock(_vr: *const u32, _nvr: usize, _value: *mut bool);
+                fmi3SetFloat32(_vr: *const u32, _nvr: usize, _value: *const f32, _n_value: usize);
+                fmi3SetInt8(_vr: *const u32, _nvr: usize, _value: *const i8, _n_value: usize);
+                fmi3SetUInt8(_vr: *const u32, _nvr: usize, _value: *const u8, _n_value: usize);
+                fmi3SetInt16(_vr: *const u32, _nvr: usize, _value: *const i16, _n_value: usize);
+                fmi3SetUInt16(_vr: *const u32, _nvr: usize, _value: *const u16, _n_value: usize);
+                fmi3SetInt32(_vr: *const u32, _nvr: usize, _value: *const i32, _n_value: usize);
+                fmi3SetUInt32(_vr: *const u32, _nvr: usize, _value: *const u32, _n_value: usize);
+                fmi3SetInt64(_vr: *const u32, _nvr: usize, _value: *const i64, _n_value: usize);
+                fmi3SetUInt64(_vr: *const u32, _nvr: usize, _value: *const u64, _n_value: usize);
+                fmi3SetBoolean(_vr: *const u32, _nvr: usize, _value: *const bool, _n_value: usize);
+                fmi3SetString(
+                    _vr: *const u32,
+                    _nvr: usize,
+                    _value: *const *const c_char,
+                    _n_value: usize
+                );
+                fmi3SetBinary(
+                    _vr: *const u32,
+                    _nvr: usize,
+                    _value_sizes: *const usize,
+                    _value: *const *const u8,
+                    _n_value: usize
+                );
+                fmi3SetClock(_vr: *const u32, _nvr: usize, _value: *const bool);
+                fmi3GetNumberOfVariableDependencies(_vr: u32, _n_dependencies: *mut usize);
+                fmi3GetVariableDependencies(
+                    _dependent: u32,
+                    _element_indices_of_dependent: *mut usize,
+                    _independents: *mut u32,
+                    _element_indices_of_independents: *mut usize,
+                    _dependency_kinds: *mut c_int,
+                    _n_dependencies: usize
+                );
+                fmi3GetFMUState(_state: *mut *mut c_void);
+                fmi3SetFMUState(_state: *mut c_void);
+                fmi3FreeFMUState(_state: *mut *mut c_void);
+                fmi3SerializedFMUStateSize(_state: *mut c_void, _size: *mut usize);
+                fmi3SerializeFMUState(_state: *mut c_void, _serialized: *mut u8, _size: usize);
+                fmi3DeserializeFMUState(
+                    _serialized: *const u8,
+                    _size: usize,
+                    _state: *mut *mut c_void
+                );
+                fmi3GetDirectionalDerivative(
+                    _unknowns: *const u32,
+                    _n_unknowns: usize,
+                    _knowns: *const u32,
+                    _n_knowns: usize,
+                    _seed: *const f64,
+                    _n_seed: usize,
+                    _sensitivity: *mut f64,
+                    _n_sensitivity: usize
+                );
+                fmi3GetAdjointDerivative(
+                    _unknowns: *const u32,
+                    _n_unknowns: usize,
+                    _knowns: *const u32,
+                    _n_knowns: usize,
+                    _seed: *const f64,
+                    _n_seed: usize,
+                    _sensitivity: *mut f64,
+                    _n_sensitivity: usize
+                );
+                fmi3EnterConfigurationMode();
+                fmi3ExitConfigurationMode();
+                fmi3GetIntervalDecimal(
+                    _vr: *const u32,
+                    _nvr: usize,
+                    _intervals: *mut f64,
+                    _qualifiers: *mut c_int
+                );
+                fmi3GetIntervalFraction(
+                    _vr: *const u32,
+                    _nvr: usize,
+                    _counters: *mut u64,
+                    _resolutions: *mut u64,
+                    _qualifiers: *mut c_int
+                );
+                fmi3GetShiftDecimal(_vr: *const u32, _nvr: usize, _shifts: *mut f64);
+                fmi3GetShiftFraction(
+                    _vr: *const u32,
+                    _nvr: usize,
+                    _counters: *mut u64,
+                    _resolutions: *mut u64
+                );
+                fmi3SetIntervalDecimal(_vr: *const u32, _nvr: usize, _intervals: *const f64);
+                fmi3SetIntervalFraction(
+                    _vr: *const u32,
+                    _nvr: usize,
+                    _counters: *const u64,
+                    _resolutions: *const u64
+                );
+                fmi3SetShiftDecimal(_vr: *const u32, _nvr: usize, _shifts: *const f64);
+                fmi3SetShiftFraction(
+                    _vr: *const u32,
+                    _nvr: usize,
+                    _counters: *const u64,
+                    _resolutions: *const u64
+                );
+                fmi3EvaluateDiscreteStates();
+                fmi3UpdateDiscreteStates(
+                    _discrete_states_need_update: *mut bool,
+                    _terminate_simulation: *mut bool,
+                    _nominals_changed: *mut bool,
+                    _values_changed: *mut bool,
+                    _next_event_time_defined: *mut bool,
+                    _next_event_time: *mut f64
+                );
+                fmi3EnterEventMode();
+                fmi3EnterContinuousTimeMode();
+                fmi3CompletedIntegratorStep(
+                    _no_set_fmu_state_prior_to_current_point: bool,
+                    _enter_event_mode: *mut bool,
+                    _terminate_simulation: *mut bool
+                );
+                fmi3SetTime(_time: f64);
+                fmi3SetContinuousStates(_states: *const f64, _n_states: usize);
+                fmi3GetContinuousStateDerivatives(_derivatives: *mut f64, _n_states: usize);
+                fmi3GetEventIndicators(_indicators: *mut f64, _n_indicators: usize);
+                fmi3GetContinuousStates(_states: *mut f64, _n_states: usize);
+                fmi3GetNominalsOfContinuousStates(_nominals: *mut f64, _n_states: usize);
+                fmi3GetNumberOfEventIndicators(_n_indicators: *mut usize);
+                fmi3GetNumberOfContinuousStates(_n_states: *mut usize);
+                fmi3GetOutputDerivatives(
+                    _vr: *const u32,
+                    _nvr: usize,
+                    _order: *const i32,
+                    _value: *mut f64,
+                    _n_value: usize
+                );
+                fmi3ActivateModelPartition(_clock_reference: u32, _activation_time: f64);
+            }
+        }
+    };
+}
+
+// Exports mandatory FMI functions the models do not support, they return an error
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __fmu_stubs {
+    ($($name:ident($($arg:ident: $ty:ty),*);)*) => {
+        $(
+            #[no_mangle]
+            pub unsafe extern "C" fn $name(
+                instance: *mut ::std::os::raw::c_void,
+                $($arg: $ty),*
+            ) -> ::std::os::raw::c_int {
+                $crate::fmi::export::unsupported(instance, stringify!($name))
+            }
+        )*
+    };
+}
@@ -0,0 +1,43 @@
+/*
+ * FMI 2.0 logger of the FMUs loaded by `dosio::fmi::Fmu`
+ *
+ * The logger of FMI 2.0 is a variadic function: the message is formatted here
+ * and passed to the Rust callback of the component environment.
+ */
+#include <stdarg.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+/* Component environment, `LogEnv` in `fmi.rs` */
+typedef struct {
+    void (*log)(void *env, int status, const char *category,
+                const char *message);
+} dosio_log_env;
+
+void dosio_fmi2_logger(void *env, const char *instance, int status,
+                       const char *category, const char *format, ...) {
+    dosio_log_env *log_env = env;
+    char buffer[1024];
+    char *message = buffer;
+    va_list args;
+    int n;
+    (void)instance;
+    if (log_env == NULL || log_env->log == NULL || format == NULL)
+        return;
+    va_start(args, format);
+    n = vsnprintf(buffer, sizeof buffer, format, args);
+    va_end(args);
+    if (n < 0)
+        return;
+    if ((size_t)n >= sizeof buffer) {
+        message = malloc((size_t)n + 1);
+        if (message == NULL)
+            return;
+        va_start(args, format);
+        vsnprintf(message, (size_t)n + 1, format, args);
+        va_end(args);
+    }
+    log_env->log(env, status, category, message);
+    if (message != buffer)
+        free(message);
+}
@@ -1,26 +1,473 @@
 //! DOS inputs/outputs
 //!
 //! Provides the definitions for all the inputs and outputs used by DOS
+//!
+//! The variants renamed in the FEM keep their former names as aliases, listed as `alias <old> <new>` in the vendored manifest:
+//! the former names are deprecated types of [`jar`], so `ios!` still accepts them with a warning naming the replacement,
+//! and they are parsed by [`FromStr`] and by the deserialization of [`IO`] and [`IOKind`].
 
 use core::fmt::Debug;
-use serde::{Deserialize, Serialize};
-use std::fmt;
+use serde::{
+    de::{self, Deserializer, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor},
+    ser::{SerializeStructVariant, Serializer},
+    Deserialize, Serialize,
+};
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader},
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    path::Path,
+    str::FromStr,
+};
 
 /// IO Error type
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum IOError<T> {
+    /// The [`IO`] has no data
     Missing(IO<T>),
+    /// The [`IO`] is not of the expected variant
+    Mismatch(IOKind, IO<T>),
+    /// The data of the [`IO`] is not of the expected size
+    Size(usize, IO<T>),
+}
+impl<T> IOError<T> {
+    /// Returns the variant of the [`IO`] the error occured with
+    pub fn io_kind(&self) -> IOKind {
+        match self {
+            Self::Missing(io) => io.io_kind(),
+            Self::Mismatch(_, io) => io.io_kind(),
+            Self::Size(_, io) => io.io_kind(),
+        }
+    }
 }
 impl<T: Debug> fmt::Display for IOError<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Missing(v) => write!(f, "{:?} is missing", v),
+            Self::Mismatch(kind, v) => write!(f, "{:?} is not a {}", v, kind),
+            Self::Size(size, v) => write!(f, "{:?} is not of size {}", v, size),
         }
     }
 }
 impl<T: Debug> std::error::Error for IOError<T> {}
 
+/// Identifies the FEM model the [`IO`] enum has been generated from
+///
+/// The fingerprint of the compiled [`IO`] enum is given by [`FINGERPRINT`].
+/// Without the `FEM_REPO` environment variable at compile time, the fingerprint is the one of the vendored manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// Path to the FEM model file
+    pub path: &'static str,
+    /// FNV-1a hash of the FEM model file
+    pub hash: u64,
+    /// Number of [`IO`] variants
+    pub n_variant: usize,
+}
+impl Fingerprint {
+    /// Computes the FNV-1a hash of the file at `path`
+    pub fn hash_file<P: AsRef<Path>>(path: P) -> io::Result<u64> {
+        fnv1a(BufReader::new(File::open(path)?))
+    }
+    /// Checks that the FEM model file at `path` is the one the [`IO`] enum has been generated from
+    pub fn matches<P: AsRef<Path>>(&self, path: P) -> io::Result<bool> {
+        Ok(self.hash == Self::hash_file(path)?)
+    }
+}
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} [{:016x}] with {} variants",
+            self.path, self.hash, self.n_variant
+        )
+    }
+}
+
+// `ad_hoc!` also emits `fnv1a`, the hash of the fingerprint and of the wire ids,
+// the `ad_hoc!` arguments are aliases, as `<old> => <new>`
 dosio_macros::ad_hoc! {}
+
+impl IOKind {
+    /// Returns the variant name
+    pub fn name(self) -> &'static str {
+        Self::NAMES[self as usize]
+    }
+}
+impl fmt::Display for IOKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+impl FromStr for IOKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::NAMES
+            .binary_search(&s)
+            .map(|i| Self::ALL[i])
+            .or_else(|_| {
+                Self::ALIASES
+                    .binary_search_by_key(&s, |&(alias, _)| alias)
+                    .map(|i| Self::ALIASES[i].1)
+            })
+            .map_err(|_| format!("{} is not a `IO` variant", s))
+    }
+}
+impl<T> IO<T> {
+    /// Returns the variant name
+    pub fn kind(&self) -> String {
+        self.io_kind().name().to_string()
+    }
+}
+impl<T: Debug> Debug for IO<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct(self.io_kind().name())
+            .field("data", self.deref())
+            .finish()
+    }
+}
+impl<T: Clone> Clone for IO<T> {
+    fn clone(&self) -> Self {
+        self.io_kind().io(self.deref().clone())
+    }
+}
+impl IO<usize> {
+    /// Assign `n` to `IO` `data`
+    pub fn assign(&mut self, n: usize) {
+        *self.deref_mut() = Some(n);
+    }
+}
+impl IO<Vec<f64>> {
+    /// Compute `io` sum squared
+    pub fn sum_sqred(&self) -> f64 {
+        match self.deref() {
+            None => f64::NAN,
+            Some(values) => values.iter().map(|x: &f64| x * x).sum::<f64>(),
+        }
+    }
+    /// Compute `io` mean sum squared
+    pub fn mean_sum_sqred(&self) -> f64 {
+        match self.deref() {
+            None => f64::NAN,
+            Some(values) => {
+                values.iter().map(|x: &f64| x * x).sum::<f64>() * (values.len() as f64).recip()
+            }
+        }
+    }
+    /// Compute the mean
+    pub fn mean(&self) -> f64 {
+        match self.deref() {
+            None => f64::NAN,
+            Some(values) => values.iter().sum::<f64>() * (values.len() as f64).recip(),
+        }
+    }
+    /// Compute the variance
+    pub fn var(&self) -> f64 {
+        match self.deref() {
+            None => f64::NAN,
+            Some(values) => {
+                let n_recip = (values.len() as f64).recip();
+                let mean = values.iter().sum::<f64>() * n_recip;
+                values.iter().map(|x| x - mean).map(|x| x * x).sum::<f64>() * n_recip
+            }
+        }
+    }
+    /// Compute the standard deviation
+    pub fn std(&self) -> f64 {
+        self.var().sqrt()
+    }
+}
+impl<T, U> PartialEq<IO<T>> for IO<U> {
+    fn eq(&self, other: &IO<T>) -> bool {
+        self.io_kind() == other.io_kind()
+    }
+}
+impl<T, U> From<&IO<U>> for IO<T> {
+    /// Converts a `IO<T>` into an `Option<T>`
+    fn from(io: &IO<U>) -> Self {
+        io.io_kind().io(Default::default())
+    }
+}
+impl<T, U: Iterator<Item = T>> From<&mut IO<U>> for Option<IO<T>> {
+    /// Converts a `IO<T>` into an `Option<T>`
+    fn from(io: &mut IO<U>) -> Self {
+        let kind = io.io_kind();
+        io.as_mut()
+            .and_then(|data| data.next())
+            .map(|data| kind.io(Some(data)))
+    }
+}
+impl<T> From<IO<T>> for Option<T> {
+    /// Converts a `IO<T>` into an `Option<T>`
+    fn from(io: IO<T>) -> Self {
+        io.into_parts().1
+    }
+}
+impl<'a, T> From<&'a IO<T>> for Option<&'a T> {
+    /// Converts a `&IO<T>` into an `Option<&T>`
+    fn from(io: &'a IO<T>) -> Self {
+        io.deref().as_ref()
+    }
+}
+impl<T> From<(&IO<()>, Option<T>)> for IO<T> {
+    fn from((io, data): (&IO<()>, Option<T>)) -> Self {
+        io.io_kind().io(data)
+    }
+}
+impl<T: Debug> From<IO<T>> for Result<T, IOError<T>> {
+    /// Converts a `IO<T>` into an `Result<T,IOError<T>>`
+    fn from(io: IO<T>) -> Self {
+        let (kind, data) = io.into_parts();
+        data.ok_or_else(|| IOError::Missing(kind.io(None)))
+    }
+}
+impl<T: Clone> From<&IO<T>> for Option<T> {
+    /// Converts a `&IO<T>` into an `Option<T>`
+    fn from(io: &IO<T>) -> Self {
+        io.deref().as_ref().cloned()
+    }
+}
+impl From<(&IO<usize>, Vec<f64>)> for IO<Vec<f64>> {
+    /// Converts a `(&IO<usize>,Vec<f64>)` into an `IO<Vec<f64>>`
+    fn from((io, v): (&IO<usize>, Vec<f64>)) -> Self {
+        io.io_kind().io(Some(v))
+    }
+}
+impl From<(&IO<()>, Vec<f64>)> for IO<Vec<f64>> {
+    /// Converts a `(&IO<()>,Vec<f64>)` into an `IO<Vec<f64>>`
+    fn from((io, v): (&IO<()>, Vec<f64>)) -> Self {
+        io.io_kind().io(Some(v))
+    }
+}
+impl IO<Vec<f64>> {
+    // Applies `f` to the pairs of values of `self` and `other`
+    fn zip_with<F: Fn(&mut f64, f64)>(
+        &mut self,
+        other: &IO<Vec<f64>>,
+        f: F,
+    ) -> Result<(), IOError<Vec<f64>>> {
+        if self.io_kind() != other.io_kind() {
+            return Err(IOError::Mismatch(self.io_kind(), other.clone()));
+        }
+        if self.is_none() {
+            return Err(IOError::Missing(self.clone()));
+        }
+        self.zip_data(other, f);
+        Ok(())
+    }
+    // Applies `f` to the pairs of values of `self` and `other`, whatever the variant of `other`
+    fn zip_data<F: Fn(&mut f64, f64)>(&mut self, other: &IO<Vec<f64>>, f: F) {
+        if let (Some(x), Some(y)) = (self.deref_mut(), other.deref()) {
+            x.iter_mut().zip(y).for_each(|(x, &y)| f(x, y));
+        }
+    }
+    /// Adds the data of `other` to the data of `self`
+    ///
+    /// `other` without data is ignored, it is an error if `self` has no data or if `other` is another variant
+    pub fn try_add_assign(&mut self, other: &IO<Vec<f64>>) -> Result<(), IOError<Vec<f64>>> {
+        self.zip_with(other, |x, y| *x += y)
+    }
+    /// Subtracts the data of `other` from the data of `self`
+    ///
+    /// `other` without data is ignored, it is an error if `self` has no data or if `other` is another variant
+    pub fn try_sub_assign(&mut self, other: &IO<Vec<f64>>) -> Result<(), IOError<Vec<f64>>> {
+        self.zip_with(other, |x, y| *x -= y)
+    }
+    /// Scales the data of `self` by `rhs`, it is an error if `self` has no data
+    pub fn try_mul_assign(&mut self, rhs: f64) -> Result<(), IOError<Vec<f64>>> {
+        match self.deref_mut() {
+            Some(x) => {
+                x.iter_mut().for_each(|x| *x *= rhs);
+                Ok(())
+            }
+            None => Err(IOError::Missing(self.clone())),
+        }
+    }
+}
+/// Adds the data of `other` to the data of `self`, pairwise and whatever the variant of `other`
+///
+/// Nothing is done if either `self` or `other` has no data,
+/// use [`try_add_assign`](IO::try_add_assign) to get an error instead
+impl std::ops::AddAssign<&IO<Vec<f64>>> for IO<Vec<f64>> {
+    fn add_assign(&mut self, other: &IO<Vec<f64>>) {
+        self.zip_data(other, |x, y| *x += y)
+    }
+}
+/// Subtracts the data of `other` from the data of `self`, pairwise and whatever the variant of `other`
+///
+/// Nothing is done if either `self` or `other` has no data,
+/// use [`try_sub_assign`](IO::try_sub_assign) to get an error instead
+impl std::ops::SubAssign<&IO<Vec<f64>>> for IO<Vec<f64>> {
+    fn sub_assign(&mut self, other: &IO<Vec<f64>>) {
+        self.zip_data(other, |x, y| *x -= y)
+    }
+}
+/// Scales the data of `self` by `rhs`
+///
+/// Nothing is done if `self` has no data, use [`try_mul_assign`](IO::try_mul_assign) to get an error instead
+impl std::ops::MulAssign<f64> for IO<Vec<f64>> {
+    fn mul_assign(&mut self, rhs: f64) {
+        let _ = self.try_mul_assign(rhs);
+    }
+}
+/// Scales the data of `self` by `rhs`, as [`MulAssign`](std::ops::MulAssign)
+impl std::ops::Mul<f64> for &mut IO<Vec<f64>> {
+    type Output = ();
+    fn mul(self, rhs: f64) -> Self::Output {
+        *self *= rhs;
+    }
+}
+impl fmt::Display for IO<()> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.io_kind().name())
+    }
+}
+
+// `IO` is serialized as an externally tagged enum of struct variants `{ data: Option<T> }`
+impl<T: Serialize> Serialize for IO<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let kind = self.io_kind();
+        let mut state = serializer.serialize_struct_variant("IO", kind as u32, kind.name(), 1)?;
+        state.serialize_field("data", self.deref())?;
+        state.end()
+    }
+}
+impl Serialize for IOKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_unit_variant("IOKind", *self as u32, self.name())
+    }
+}
+// Deserializes a variant identifier
+struct KindIdentifier(IOKind);
+impl<'de> Deserialize<'de> for KindIdentifier {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KindVisitor;
+        impl<'de> Visitor<'de> for KindVisitor {
+            type Value = KindIdentifier;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a `IO` variant identifier")
+            }
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                IOKind::ALL
+                    .get(v as usize)
+                    .map(|&kind| KindIdentifier(kind))
+                    .ok_or_else(|| {
+                        E::invalid_value(de::Unexpected::Unsigned(v), &"a `IO` variant index")
+                    })
+            }
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse()
+                    .map(KindIdentifier)
+                    .map_err(|_| E::unknown_variant(v, &IOKind::NAMES))
+            }
+            fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                match std::str::from_utf8(v) {
+                    Ok(v) => self.visit_str(v),
+                    Err(_) => Err(E::invalid_value(de::Unexpected::Bytes(v), &self)),
+                }
+            }
+        }
+        deserializer.deserialize_identifier(KindVisitor)
+    }
+}
+impl<'de> Deserialize<'de> for IOKind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct IOKindVisitor;
+        impl<'de> Visitor<'de> for IOKindVisitor {
+            type Value = IOKind;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("enum IOKind")
+            }
+            fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+                let (KindIdentifier(kind), variant) = data.variant()?;
+                variant.unit_variant()?;
+                Ok(kind)
+            }
+        }
+        deserializer.deserialize_enum("IOKind", &IOKind::NAMES, IOKindVisitor)
+    }
+}
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for IO<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // the only field of the variants
+        enum Field {
+            Data,
+            Ignore,
+        }
+        impl<'de> Deserialize<'de> for Field {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct FieldVisitor;
+                impl<'de> Visitor<'de> for FieldVisitor {
+                    type Value = Field;
+                    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                        f.write_str("field identifier")
+                    }
+                    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                        Ok(if v == 0 { Field::Data } else { Field::Ignore })
+                    }
+                    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                        Ok(if v == "data" {
+                            Field::Data
+                        } else {
+                            Field::Ignore
+                        })
+                    }
+                    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+                        Ok(if v == b"data" {
+                            Field::Data
+                        } else {
+                            Field::Ignore
+                        })
+                    }
+                }
+                deserializer.deserialize_identifier(FieldVisitor)
+            }
+        }
+        // the content of a variant
+        struct DataVisitor<T>(PhantomData<T>);
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for DataVisitor<T> {
+            type Value = Option<T>;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("struct variant with a `data` field")
+            }
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                seq.next_element::<Option<T>>()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))
+            }
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut data: Option<Option<T>> = None;
+                while let Some(key) = map.next_key::<Field>()? {
+                    match key {
+                        Field::Data if data.is_some() => {
+                            return Err(de::Error::duplicate_field("data"))
+                        }
+                        Field::Data => data = Some(map.next_value()?),
+                        Field::Ignore => {
+                            map.next_value::<de::IgnoredAny>()?;
+                        }
+                    }
+                }
+                Ok(data.unwrap_or_default())
+            }
+        }
+        struct IOVisitor<T>(PhantomData<T>);
+        impl<'de, T: Deserialize<'de>> Visitor<'de> for IOVisitor<T> {
+            type Value = IO<T>;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("enum IO")
+            }
+            fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Self::Value, A::Error> {
+                let (KindIdentifier(kind), variant) = data.variant()?;
+                let data = variant.struct_variant(&["data"], DataVisitor(PhantomData))?;
+                Ok(kind.io(data))
+            }
+        }
+        deserializer.deserialize_enum("IO", &IOKind::NAMES, IOVisitor(PhantomData))
+    }
+}
 impl<T, U: Debug> std::ops::Index<IO<U>> for Vec<IO<T>> {
     type Output = IO<T>;
     fn index(&self, io: IO<U>) -> &Self::Output {
@@ -58,9 +505,107 @@ pub type Tags = IO<()>;
 mod tests {
     use super::*;
     #[test]
+    fn vendored_variants() {
+        let io = crate::ios!(OSSM1Lcl(vec![0f64; 42]));
+        assert_eq!(io.kind(), "OSSM1Lcl");
+    }
+    #[test]
+    fn io_kind() {
+        let io = crate::ios!(OSSM1Lcl(vec![1f64; 42]));
+        assert_eq!(io.io_kind(), IOKind::OSSM1Lcl);
+        assert_eq!("OSSM1Lcl".parse::<IOKind>().unwrap(), IOKind::OSSM1Lcl);
+        assert!("OSSM1Local".parse::<IOKind>().is_err());
+        assert_eq!(IOKind::ALL.len(), FINGERPRINT.n_variant);
+        assert_eq!(
+            format!("{:?}", io),
+            format!("OSSM1Lcl {{ data: Some({:?}) }}", vec![1f64; 42])
+        );
+    }
+    #[test]
+    fn arithmetic() {
+        let mut io = crate::ios!(Pssn(vec![1f64, 2.]));
+        io += &crate::ios!(Pssn(vec![1f64, 1.]));
+        io *= 2.;
+        assert_eq!(Option::<Vec<f64>>::from(&io), Some(vec![4., 6.]));
+        assert!(matches!(
+            io.try_sub_assign(&crate::ios!(SensorData(vec![1f64, 1.]))),
+            Err(IOError::Mismatch(IOKind::Pssn, _))
+        ));
+        // the operators do not fail, in debug and release builds alike
+        io -= &crate::ios!(SensorData(vec![1f64, 1.]));
+        assert_eq!(Option::<Vec<f64>>::from(&io), Some(vec![3., 5.]));
+        let mut io: IO<Vec<f64>> = IOKind::Pssn.io(None);
+        assert!(matches!(io.try_mul_assign(2.), Err(IOError::Missing(_))));
+        io *= 2.;
+        io += &crate::ios!(Pssn(vec![1f64]));
+        assert_eq!(io.deref(), &None);
+    }
+    #[test]
+    fn serde() {
+        let ios = crate::ios!(OSSM1Lcl(vec![1f64; 42]), SensorData(vec![]));
+        let bytes = serde_pickle::to_vec(&ios, true).unwrap();
+        let de_ios: Vec<IO<Vec<f64>>> = serde_pickle::from_slice(&bytes).unwrap();
+        assert_eq!(ios.len(), de_ios.len());
+        ios.iter().zip(&de_ios).for_each(|(io, de_io)| {
+            assert_eq!(io.io_kind(), de_io.io_kind());
+            assert_eq!(io.deref(), de_io.deref());
+        });
+        let bytes = serde_pickle::to_vec(&IOKind::SensorData, true).unwrap();
+        let kind: IOKind = serde_pickle::from_slice(&bytes).unwrap();
+        assert_eq!(kind, IOKind::SensorData);
+    }
+    #[test]
+    #[allow(deprecated)]
+    fn aliases() {
+        assert_eq!(
+            IOKind::ALIASES,
+            [("OSSHarpointDeltaF", IOKind::OSSHardpointDeltaF)]
+        );
+        assert_eq!(
+            "OSSHarpointDeltaF".parse::<IOKind>().unwrap(),
+            IOKind::OSSHardpointDeltaF
+        );
+        let io = crate::ios!(OSSHarpointDeltaF(vec![1f64]));
+        assert_eq!(io.io_kind(), IOKind::OSSHardpointDeltaF);
+        assert_eq!(
+            <jar::OSSHarpointDeltaF as crate::typed::UniqueIdentifier>::SIZE,
+            Some(42)
+        );
+        // a log written with the former name
+        #[derive(Serialize)]
+        enum Former {
+            OSSHarpointDeltaF { data: Option<Vec<f64>> },
+        }
+        let bytes = serde_pickle::to_vec(
+            &vec![Former::OSSHarpointDeltaF {
+                data: Some(vec![1f64]),
+            }],
+            true,
+        )
+        .unwrap();
+        let ios: Vec<IO<Vec<f64>>> = serde_pickle::from_slice(&bytes).unwrap();
+        assert_eq!(ios[0].io_kind(), IOKind::OSSHardpointDeltaF);
+        assert_eq!(ios[0].deref(), &Some(vec![1f64]));
+    }
+    #[test]
     fn from_tag_and_data() {
         let tag = IO::SensorData { data: Some(()) };
         let data = vec![1.234; 5];
-        let io: IO<Vec<f64>> = From::from((&tag, Some(data)));
+        let _io: IO<Vec<f64>> = From::from((&tag, Some(data)));
+    }
+    #[test]
+    fn fingerprint() {
+        let path =
+            std::env::temp_dir().join(format!("dosio-fingerprint-{}.bin", std::process::id()));
+        std::fs::write(&path, b"a").unwrap();
+        assert_eq!(Fingerprint::hash_file(&path).unwrap(), 0xaf63dc4c8601ec8c);
+        let fingerprint = Fingerprint {
+            path: "dosio_fingerprint.bin",
+            hash: 0xaf63dc4c8601ec8c,
+            n_variant: 0,
+        };
+        assert!(fingerprint.matches(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert!(FINGERPRINT.matches(FINGERPRINT.path).unwrap());
     }
 }
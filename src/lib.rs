@@ -2,14 +2,93 @@
 //!
 //! All components of GMT Dynamic Optics Simulations must implement the [`inputs`](Dos::inputs) and [`outputs`](Dos::outputs) method of the [`Dos`] trait.
 //! All inputs and outputs must be a variant of the enum type [`IO`].
+//!
+//! The [`IO`] variants are derived from the FEM model in the directory given by the `FEM_REPO` environment variable.
+//! Changing either `FEM_REPO` or the model triggers a rebuild and the model the crate is compiled with is identified with [`FINGERPRINT`](io::FINGERPRINT).
+//!
+//! [`IOTags`] and the [`inputs`](Dos::inputs) and [`outputs`](Dos::outputs) methods of [`Dos`] can be derived
+//! from the [`IO`] variants given to the `Option` fields of a component:
+//! ```
+//! use dosio::{ios, Dos, IOTags};
+//!
+//! #[derive(Default, IOTags, Dos)]
+//! struct Mirror {
+//!     #[dos(input = M1RBMcmd)]
+//!     cmd: Option<Vec<f64>>,
+//!     #[dos(output = M1HPLC)]
+//!     load: Option<Vec<f64>>,
+//! }
+//! impl Iterator for Mirror {
+//!     type Item = ();
+//!     fn next(&mut self) -> Option<()> {
+//!         self.load = self.cmd.take();
+//!         Some(())
+//!     }
+//! }
+//!
+//! let mut m1 = Mirror::default();
+//! assert_eq!(m1.inputs_tags(), vec![ios!(M1RBMcmd)]);
+//! let y = m1.in_step_out(Some(vec![ios!(M1RBMcmd(vec![1f64]))])).unwrap();
+//! assert_eq!(y.unwrap()[0], ios!(M1HPLC));
+//! ```
+//! Unknown variants are compile errors:
+//! ```compile_fail
+//! #[derive(dosio::IOTags)]
+//! struct Mirror {
+//!     #[dos(input = M1RBMcommand)]
+//!     cmd: Option<Vec<f64>>,
+//! }
+//! ```
+
+extern crate self as dosio;
 
+pub mod blocks;
+pub mod checkpoint;
+pub mod clock;
+pub mod combinators;
+pub mod compare;
 pub mod error;
+#[cfg(feature = "fem")]
+pub mod fem;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "fixture")]
+pub mod fixture;
+#[cfg(feature = "fmi")]
+pub mod fmi;
 pub mod io;
+#[cfg(feature = "lti")]
+pub mod lti;
+pub mod pipeline;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "regression")]
+pub mod regression;
+pub mod remote;
+#[cfg(feature = "shm")]
+pub mod shm;
+pub mod typed;
+pub mod validate;
+pub mod wire;
 
+#[doc(inline)]
+pub use checkpoint::Checkpoint;
+#[doc(inline)]
+pub use clock::{Clock, TimedDos};
+#[doc(inline)]
+pub use combinators::{DosExt, Feedback, FnDos, Parallel, Series};
+#[doc(inline)]
+pub use compare::{IOVecCompare, Tolerance};
+#[doc(inline)]
+pub use dosio_macros::{Dos, IOTags};
 #[doc(inline)]
 pub use error::DOSIOSError;
 #[doc(inline)]
 pub use io::IO;
+#[doc(inline)]
+pub use pipeline::Pipeline;
+#[doc(inline)]
+pub use validate::Validate;
 
 ///  Create IO enum
 ///
@@ -99,6 +178,27 @@ pub trait Dos {
     /// Passe a [`IO`] input vector to `Self`
     fn inputs(&mut self, data: Option<Vec<IO<Self::Input>>>) -> Result<&mut Self, DOSIOSError>;
 
+    /// Initializes `Self` before the first step
+    ///
+    /// Does nothing by default
+    fn init(&mut self) -> Result<&mut Self, DOSIOSError> {
+        Ok(self)
+    }
+
+    /// Sets `Self` back to its initial state between runs
+    ///
+    /// Does nothing by default
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        Ok(self)
+    }
+
+    /// Finalizes `Self` after the last step, e.g. flushing and closing files
+    ///
+    /// Does nothing by default
+    fn finalize(&mut self) -> Result<&mut Self, DOSIOSError> {
+        Ok(self)
+    }
+
     /// Invokes the `next` method of `Self`
     fn step(&mut self) -> Result<&mut Self, DOSIOSError>
     where
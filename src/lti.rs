@@ -0,0 +1,845 @@
+//! Discrete linear time-invariant systems
+//!
+//! Continuous models, either [`ContinuousStateSpace`] or [`TransferFunction`], are converted into discrete models
+//! with one of the [`Discretization`] methods.
+//! The discrete models are run with the [`StateSpace`] and [`BiquadCascade`] [`Dos`] components:
+//!  - [`StateSpace`] is a MIMO system with its input vector concatenating the data of the input [`IO`] variants
+//!    and its output vector split into the output [`IO`] variants,
+//!  - [`BiquadCascade`] is a SISO transfer function, in cascade form of second-order sections, applied to each channel of its input.
+//!
+//! All the models and components can be (de)serialized; [`load`] and [`save`] use pickle files.
+
+use crate::{blocks::take_or_err, io::IOKind, DOSIOSError, Dos, IOTags, IO};
+use nalgebra::{Complex, DMatrix, DVector};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    f64::consts::PI,
+    fmt,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+/// LTI error type
+#[derive(Debug)]
+pub enum LtiError {
+    /// The matrices or the polynomials have inconsistent dimensions
+    Dimension(String),
+    /// A matrix that must be inverted is singular
+    Singular,
+    /// The discretization method does not apply to the model
+    Unsupported(String),
+    /// Cannot read or write the model file
+    Io(std::io::Error),
+    /// Cannot (de)serialize the model
+    Pickle(serde_pickle::Error),
+}
+impl fmt::Display for LtiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dimension(msg) => write!(f, "Dimension mismatch: {}", msg),
+            Self::Singular => write!(f, "Singular matrix"),
+            Self::Unsupported(msg) => write!(f, "Unsupported discretization: {}", msg),
+            Self::Io(e) => write!(f, "LTI model file error: {}", e),
+            Self::Pickle(e) => write!(f, "LTI model (de)serialization error: {}", e),
+        }
+    }
+}
+impl std::error::Error for LtiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Pickle(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl From<std::io::Error> for LtiError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+impl From<serde_pickle::Error> for LtiError {
+    fn from(e: serde_pickle::Error) -> Self {
+        Self::Pickle(e)
+    }
+}
+
+/// Loads a model or a component from a pickle file
+pub fn load<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<T, LtiError> {
+    let file = BufReader::new(File::open(path)?);
+    Ok(serde_pickle::from_reader(file)?)
+}
+/// Saves a model or a component to a pickle file
+pub fn save<T: Serialize, P: AsRef<Path>>(model: &T, path: P) -> Result<(), LtiError> {
+    let mut file = BufWriter::new(File::create(path)?);
+    serde_pickle::to_writer(&mut file, model, true)?;
+    Ok(())
+}
+
+/// Matrix given row by row
+pub type Rows = Vec<Vec<f64>>;
+
+fn to_matrix(rows: &[Vec<f64>], name: &str) -> Result<DMatrix<f64>, LtiError> {
+    let n_col = rows.first().map_or(0, |row| row.len());
+    if rows.iter().any(|row| row.len() != n_col) {
+        return Err(LtiError::Dimension(format!(
+            "the rows of {} have different lengths",
+            name
+        )));
+    }
+    Ok(DMatrix::from_fn(rows.len(), n_col, |i, j| rows[i][j]))
+}
+fn to_rows(matrix: &DMatrix<f64>) -> Rows {
+    matrix
+        .row_iter()
+        .map(|row| row.iter().cloned().collect())
+        .collect()
+}
+
+/// Continuous-to-discrete conversion methods
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Discretization {
+    /// Zero-order hold on the inputs
+    ZeroOrderHold,
+    /// Bilinear transform, the frequency response is matched at the prewarping frequency in Hz if any
+    Tustin { prewarp: Option<f64> },
+    /// Poles and zeros mapped with `z=exp(s T)`, the zeros at infinity are mapped to `z=-1`
+    ///
+    /// The gain is matched at zero frequency, discounting the poles and zeros at the origin.
+    /// It applies only to [`TransferFunction`]s.
+    MatchedPole,
+}
+impl Discretization {
+    // Tustin transform `s = k (z-1)/(z+1)`
+    fn tustin_k(prewarp: Option<f64>, sampling_period: f64) -> f64 {
+        match prewarp {
+            Some(frequency) => {
+                let w = 2. * PI * frequency;
+                w / (0.5 * w * sampling_period).tan()
+            }
+            None => 2. / sampling_period,
+        }
+    }
+}
+
+/// Continuous state space model: `x' = A x + B u`, `y = C x + D u`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContinuousStateSpace {
+    pub a: Rows,
+    pub b: Rows,
+    pub c: Rows,
+    pub d: Rows,
+}
+type Matrices = (DMatrix<f64>, DMatrix<f64>, DMatrix<f64>, DMatrix<f64>);
+// Checks the dimensions of A, B, C and D
+fn matrices(a: &Rows, b: &Rows, c: &Rows, d: &Rows) -> Result<Matrices, LtiError> {
+    let (a, b, c, d) = (
+        to_matrix(a, "A")?,
+        to_matrix(b, "B")?,
+        to_matrix(c, "C")?,
+        to_matrix(d, "D")?,
+    );
+    let n = a.nrows();
+    if a.ncols() != n
+        || b.nrows() != n
+        || c.ncols() != n
+        || d.nrows() != c.nrows()
+        || d.ncols() != b.ncols()
+    {
+        return Err(LtiError::Dimension(format!(
+            "A: {:?}, B: {:?}, C: {:?}, D: {:?}",
+            a.shape(),
+            b.shape(),
+            c.shape(),
+            d.shape()
+        )));
+    }
+    Ok((a, b, c, d))
+}
+impl ContinuousStateSpace {
+    /// Converts the model into a discrete model with the given sampling period in seconds
+    pub fn discretize(
+        &self,
+        sampling_period: f64,
+        method: Discretization,
+    ) -> Result<DiscreteStateSpace, LtiError> {
+        let (a, b, c, d) = matrices(&self.a, &self.b, &self.c, &self.d)?;
+        let (n, m) = b.shape();
+        let (ad, bd, cd, dd) = match method {
+            Discretization::ZeroOrderHold => {
+                let mut em = DMatrix::zeros(n + m, n + m);
+                em.view_mut((0, 0), (n, n))
+                    .copy_from(&(&a * sampling_period));
+                em.view_mut((0, n), (n, m))
+                    .copy_from(&(&b * sampling_period));
+                let e = em.exp();
+                (
+                    e.view((0, 0), (n, n)).into_owned(),
+                    e.view((0, n), (n, m)).into_owned(),
+                    c,
+                    d,
+                )
+            }
+            Discretization::Tustin { prewarp } => {
+                let h = Discretization::tustin_k(prewarp, sampling_period).recip();
+                let identity = DMatrix::<f64>::identity(n, n);
+                let lu = (&identity - &a * h).lu();
+                let ad = lu.solve(&(&identity + &a * h)).ok_or(LtiError::Singular)?;
+                let bd = lu.solve(&(&b * (2. * h))).ok_or(LtiError::Singular)?;
+                let cd = (&identity - &a * h)
+                    .transpose()
+                    .lu()
+                    .solve(&c.transpose())
+                    .ok_or(LtiError::Singular)?
+                    .transpose();
+                let dd = &d + &c * &bd * 0.5;
+                (ad, bd, cd, dd)
+            }
+            Discretization::MatchedPole => {
+                return Err(LtiError::Unsupported(
+                    "matched pole applies to transfer functions".into(),
+                ))
+            }
+        };
+        Ok(DiscreteStateSpace {
+            a: to_rows(&ad),
+            b: to_rows(&bd),
+            c: to_rows(&cd),
+            d: to_rows(&dd),
+            sampling_period,
+        })
+    }
+}
+
+/// Discrete state space model: `x[k+1] = A x[k] + B u[k]`, `y[k] = C x[k] + D u[k]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscreteStateSpace {
+    pub a: Rows,
+    pub b: Rows,
+    pub c: Rows,
+    pub d: Rows,
+    /// Sampling period in seconds
+    pub sampling_period: f64,
+}
+
+/// [`StateSpace`] component configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateSpaceConfig {
+    /// Discrete model
+    pub model: DiscreteStateSpace,
+    /// Input variants, their data are concatenated into the input vector
+    pub inputs: Vec<IOKind>,
+    /// Output variants with their number of channels, the output vector is split into them
+    pub outputs: Vec<(IOKind, usize)>,
+}
+
+/// Discrete state space component
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "StateSpaceConfig", into = "StateSpaceConfig")]
+pub struct StateSpace {
+    config: StateSpaceConfig,
+    a: DMatrix<f64>,
+    b: DMatrix<f64>,
+    c: DMatrix<f64>,
+    d: DMatrix<f64>,
+    x: DVector<f64>,
+    u: Option<DVector<f64>>,
+    y: Option<DVector<f64>>,
+}
+impl TryFrom<StateSpaceConfig> for StateSpace {
+    type Error = LtiError;
+    fn try_from(config: StateSpaceConfig) -> Result<Self, Self::Error> {
+        let model = &config.model;
+        let (a, b, c, d) = matrices(&model.a, &model.b, &model.c, &model.d)?;
+        let n_output: usize = config.outputs.iter().map(|(_, n)| n).sum();
+        if n_output != c.nrows() {
+            return Err(LtiError::Dimension(format!(
+                "the outputs have {} channels but the model has {} outputs",
+                n_output,
+                c.nrows()
+            )));
+        }
+        Ok(Self {
+            x: DVector::zeros(a.nrows()),
+            a,
+            b,
+            c,
+            d,
+            config,
+            u: None,
+            y: None,
+        })
+    }
+}
+impl From<StateSpace> for StateSpaceConfig {
+    fn from(state_space: StateSpace) -> Self {
+        state_space.config
+    }
+}
+impl StateSpace {
+    /// Creates a new state space component
+    pub fn new(
+        model: DiscreteStateSpace,
+        inputs: Vec<IO<()>>,
+        outputs: Vec<(IO<()>, usize)>,
+    ) -> Result<Self, LtiError> {
+        Self::try_from(StateSpaceConfig {
+            model,
+            inputs: inputs.into_iter().map(|io| io.io_kind()).collect(),
+            outputs: outputs
+                .into_iter()
+                .map(|(io, n)| (io.io_kind(), n))
+                .collect(),
+        })
+    }
+    /// Returns the state vector
+    pub fn state(&self) -> &[f64] {
+        self.x.as_slice()
+    }
+    /// Returns the sampling period in seconds
+    pub fn sampling_period(&self) -> f64 {
+        self.config.model.sampling_period
+    }
+}
+impl Iterator for StateSpace {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        let u = self.u.take()?;
+        self.y = Some(&self.c * &self.x + &self.d * &u);
+        self.x = &self.a * &self.x + &self.b * &u;
+        Some(())
+    }
+}
+impl Dos for StateSpace {
+    type Input = Vec<f64>;
+    type Output = Vec<f64>;
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        let y = self.y.as_ref()?;
+        let mut i = 0;
+        Some(
+            self.config
+                .outputs
+                .iter()
+                .map(|(kind, n)| {
+                    let data = y.rows(i, *n).iter().cloned().collect();
+                    i += n;
+                    kind.io(Some(data))
+                })
+                .collect(),
+        )
+    }
+    fn inputs(&mut self, mut data: Option<Vec<IO<Vec<f64>>>>) -> Result<&mut Self, DOSIOSError> {
+        let mut u = vec![];
+        for kind in &self.config.inputs {
+            u.extend(take_or_err(&mut data, *kind)?);
+        }
+        if u.len() != self.b.ncols() {
+            return Err(DOSIOSError::Inputs(
+                format!(
+                    "the inputs have {} channels but the model has {} inputs",
+                    u.len(),
+                    self.b.ncols()
+                )
+                .into(),
+            ));
+        }
+        self.u = Some(DVector::from_vec(u));
+        Ok(self)
+    }
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.x.fill(0f64);
+        self.u = None;
+        self.y = None;
+        Ok(self)
+    }
+}
+impl IOTags for StateSpace {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        self.config
+            .outputs
+            .iter()
+            .map(|(kind, _)| kind.io(None))
+            .collect()
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        self.config
+            .inputs
+            .iter()
+            .map(|kind| kind.io(None))
+            .collect()
+    }
+}
+
+// Polynomials are given with the coefficients in descending powers
+
+fn trim(p: &[f64]) -> &[f64] {
+    let i = p.iter().position(|x| *x != 0f64).unwrap_or(p.len());
+    &p[i..]
+}
+fn poly_mul(p: &[f64], q: &[f64]) -> Vec<f64> {
+    let mut r = vec![0f64; p.len() + q.len() - 1];
+    for (i, p) in p.iter().enumerate() {
+        for (j, q) in q.iter().enumerate() {
+            r[i + j] += p * q;
+        }
+    }
+    r
+}
+fn poly_add(p: &[f64], q: &[f64]) -> Vec<f64> {
+    let n = p.len().max(q.len());
+    let coef = |p: &[f64], i: usize| {
+        if i + p.len() >= n {
+            p[i + p.len() - n]
+        } else {
+            0f64
+        }
+    };
+    (0..n).map(|i| coef(p, i) + coef(q, i)).collect()
+}
+fn poly_from_roots(roots: &[Complex<f64>]) -> Vec<f64> {
+    let mut p = vec![Complex::new(1f64, 0f64)];
+    for root in roots {
+        let mut q = p.clone();
+        q.push(Complex::new(0f64, 0f64));
+        for i in 1..q.len() {
+            q[i] -= root * p[i - 1];
+        }
+        p = q;
+    }
+    p.into_iter().map(|c| c.re).collect()
+}
+fn roots(p: &[f64]) -> Vec<Complex<f64>> {
+    let p = trim(p);
+    let n = p.len().saturating_sub(1);
+    if n == 0 {
+        return vec![];
+    }
+    let companion = DMatrix::from_fn(n, n, |i, j| {
+        if i == 0 {
+            -p[j + 1] / p[0]
+        } else if i == j + 1 {
+            1f64
+        } else {
+            0f64
+        }
+    });
+    companion.complex_eigenvalues().iter().cloned().collect()
+}
+fn characteristic_polynomial(a: &DMatrix<f64>) -> Vec<f64> {
+    if a.nrows() == 0 {
+        return vec![1f64];
+    }
+    poly_from_roots(a.clone().complex_eigenvalues().as_slice())
+}
+
+/// Continuous transfer function `num(s)/den(s)`
+///
+/// The coefficients are given in descending powers of `s`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransferFunction {
+    pub num: Vec<f64>,
+    pub den: Vec<f64>,
+}
+impl TransferFunction {
+    /// Creates a new transfer function
+    pub fn new(num: Vec<f64>, den: Vec<f64>) -> Self {
+        Self { num, den }
+    }
+    // Returns the numerator and the monic denominator of the same length
+    fn normalized(&self) -> Result<(Vec<f64>, Vec<f64>), LtiError> {
+        let (num, den) = (trim(&self.num), trim(&self.den));
+        if den.is_empty() || num.len() > den.len() {
+            return Err(LtiError::Dimension(
+                "the transfer function must be proper with a non-zero denominator".into(),
+            ));
+        }
+        let mut padded = vec![0f64; den.len() - num.len()];
+        padded.extend(num.iter().map(|x| x / den[0]));
+        Ok((padded, den.iter().map(|x| x / den[0]).collect()))
+    }
+    /// Returns the state space realization in controllable canonical form
+    pub fn realization(&self) -> Result<ContinuousStateSpace, LtiError> {
+        let (num, den) = self.normalized()?;
+        let n = den.len() - 1;
+        let a = (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| match i {
+                        0 => -den[j + 1],
+                        _ if i == j + 1 => 1f64,
+                        _ => 0f64,
+                    })
+                    .collect()
+            })
+            .collect();
+        let b = (0..n)
+            .map(|i| vec![if i == 0 { 1f64 } else { 0f64 }])
+            .collect();
+        let c = vec![(0..n).map(|j| num[j + 1] - num[0] * den[j + 1]).collect()];
+        Ok(ContinuousStateSpace {
+            a,
+            b,
+            c,
+            d: vec![vec![num[0]]],
+        })
+    }
+    /// Converts the transfer function into a discrete transfer function with the given sampling period in seconds
+    pub fn discretize(
+        &self,
+        sampling_period: f64,
+        method: Discretization,
+    ) -> Result<DiscreteTransferFunction, LtiError> {
+        let (num, den) = self.normalized()?;
+        let n = den.len() - 1;
+        let (num, den) = match method {
+            Discretization::ZeroOrderHold => {
+                let model = self.realization()?.discretize(sampling_period, method)?;
+                let (a, b, c, d) = matrices(&model.a, &model.b, &model.c, &model.d)?;
+                let den = characteristic_polynomial(&a);
+                let num = poly_add(
+                    &characteristic_polynomial(&(&a - &b * &c)),
+                    &den.iter()
+                        .map(|x| (d[(0, 0)] - 1f64) * x)
+                        .collect::<Vec<_>>(),
+                );
+                (num, den)
+            }
+            Discretization::Tustin { prewarp } => {
+                let k = Discretization::tustin_k(prewarp, sampling_period);
+                // c(s) (z+1)^n with s = k (z-1)/(z+1)
+                let substitute = |c: &[f64]| {
+                    c.iter().enumerate().fold(vec![0f64; n + 1], |p, (j, c)| {
+                        let mut q = vec![c * k.powi((n - j) as i32)];
+                        (0..n - j).for_each(|_| q = poly_mul(&q, &[1f64, -1f64]));
+                        (0..j).for_each(|_| q = poly_mul(&q, &[1f64, 1f64]));
+                        poly_add(&p, &q)
+                    })
+                };
+                let (num, den) = (substitute(&num), substitute(&den));
+                (
+                    num.iter().map(|x| x / den[0]).collect(),
+                    den.iter().map(|x| x / den[0]).collect(),
+                )
+            }
+            Discretization::MatchedPole => {
+                let is_origin = |r: &Complex<f64>| r.norm() < 1e-12;
+                let zeros = roots(&num);
+                let poles = roots(&den);
+                // gain at s=0 without the poles and zeros at the origin
+                let lead = trim(&num).first().cloned().unwrap_or(0f64);
+                let gain_s = zeros
+                    .iter()
+                    .filter(|r| !is_origin(r))
+                    .fold(Complex::new(lead, 0f64), |g, r| -g * r)
+                    / poles
+                        .iter()
+                        .filter(|r| !is_origin(r))
+                        .fold(Complex::new(1f64, 0f64), |g, r| -g * r);
+                let n_origin = zeros.iter().filter(|r| is_origin(r)).count() as i32
+                    - poles.iter().filter(|r| is_origin(r)).count() as i32;
+                let map = |r: &Complex<f64>| (r * sampling_period).exp();
+                let mut zeros_z: Vec<_> = zeros.iter().map(map).collect();
+                zeros_z.extend(vec![Complex::new(-1f64, 0f64); n - zeros.len()]);
+                let poles_z: Vec<_> = poles.iter().map(map).collect();
+                // gain at z=1 without the poles and zeros at z=1 mapped from the origin
+                let one = Complex::new(1f64, 0f64);
+                let gain_z = zeros
+                    .iter()
+                    .map(Some)
+                    .chain((zeros.len()..n).map(|_| None))
+                    .zip(&zeros_z)
+                    .filter(|(r, _)| !r.is_some_and(is_origin))
+                    .fold(one, |g, (_, z)| g * (one - z))
+                    / poles
+                        .iter()
+                        .zip(&poles_z)
+                        .filter(|(r, _)| !is_origin(r))
+                        .fold(one, |g, (_, z)| g * (one - z));
+                let gain = (gain_s / gain_z).re * sampling_period.powi(n_origin);
+                (
+                    poly_from_roots(&zeros_z)
+                        .into_iter()
+                        .map(|x| gain * x)
+                        .collect(),
+                    poly_from_roots(&poles_z),
+                )
+            }
+        };
+        let mut padded = vec![0f64; den.len().saturating_sub(num.len())];
+        padded.extend(num);
+        Ok(DiscreteTransferFunction {
+            num: padded,
+            den,
+            sampling_period,
+        })
+    }
+}
+
+/// Discrete transfer function `num(z)/den(z)`
+///
+/// The coefficients are given in descending powers of `z`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiscreteTransferFunction {
+    pub num: Vec<f64>,
+    pub den: Vec<f64>,
+    /// Sampling period in seconds
+    pub sampling_period: f64,
+}
+impl DiscreteTransferFunction {
+    /// Returns the gain and the second-order sections of the transfer function
+    pub fn biquads(&self) -> Result<(f64, Vec<Biquad>), LtiError> {
+        let den = trim(&self.den);
+        if den.is_empty() || self.num.len() > self.den.len() || trim(&self.num).len() > den.len() {
+            return Err(LtiError::Dimension(
+                "the transfer function must be proper with a non-zero denominator".into(),
+            ));
+        }
+        let num = trim(&self.num);
+        if num.is_empty() {
+            return Ok((0f64, vec![]));
+        }
+        let gain = num[0] / den[0];
+        // numerator factors in powers of z^-1, the missing zeros are delays
+        let n_delay = den.len() - num.len();
+        let num_factors = factors(&roots(num), n_delay);
+        let den_factors = factors(&roots(den), 0);
+        let n = num_factors.len().max(den_factors.len());
+        let unit = [1f64, 0f64, 0f64];
+        Ok((
+            gain,
+            (0..n)
+                .map(|i| {
+                    let b = num_factors.get(i).cloned().unwrap_or(unit);
+                    let a = den_factors.get(i).cloned().unwrap_or(unit);
+                    Biquad { b, a: [a[1], a[2]] }
+                })
+                .collect(),
+        ))
+    }
+}
+// Groups the roots and the delays into second-order factors in powers of z^-1
+fn factors(roots: &[Complex<f64>], n_delay: usize) -> Vec<[f64; 3]> {
+    let mut quadratics = vec![];
+    let mut linears = vec![];
+    for root in roots {
+        if root.im.abs() < 1e-12 * root.norm().max(1f64) {
+            linears.push([1f64, -root.re]);
+        } else if root.im > 0f64 {
+            quadratics.push([1f64, -2. * root.re, root.norm_sqr()]);
+        }
+    }
+    linears.extend(vec![[0f64, 1f64]; n_delay]);
+    for pair in linears.chunks(2) {
+        quadratics.push(match pair {
+            [p, q] => [p[0] * q[0], p[0] * q[1] + p[1] * q[0], p[1] * q[1]],
+            [p] => [p[0], p[1], 0f64],
+            _ => unreachable!(),
+        });
+    }
+    quadratics
+}
+
+/// Second-order section `(b0 + b1 z^-1 + b2 z^-2)/(1 + a1 z^-1 + a2 z^-2)`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Biquad {
+    /// Numerator `[b0, b1, b2]`
+    pub b: [f64; 3],
+    /// Denominator `[a1, a2]`
+    pub a: [f64; 2],
+}
+impl Biquad {
+    // Direct form II transposed
+    fn filter(&self, state: &mut [f64; 2], u: f64) -> f64 {
+        let y = self.b[0] * u + state[0];
+        state[0] = self.b[1] * u - self.a[0] * y + state[1];
+        state[1] = self.b[2] * u - self.a[1] * y;
+        y
+    }
+}
+
+/// Cascade of second-order sections applied to each channel of the input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BiquadCascade {
+    input: IOKind,
+    output: IOKind,
+    gain: f64,
+    sections: Vec<Biquad>,
+    #[serde(skip)]
+    states: Vec<Vec<[f64; 2]>>,
+    #[serde(skip)]
+    u: Option<Vec<f64>>,
+    #[serde(skip)]
+    y: Option<Vec<f64>>,
+}
+impl BiquadCascade {
+    /// Creates a new cascade from the gain and the sections
+    pub fn new(input: IO<()>, output: IO<()>, gain: f64, sections: Vec<Biquad>) -> Self {
+        Self {
+            input: input.io_kind(),
+            output: output.io_kind(),
+            gain,
+            sections,
+            states: vec![],
+            u: None,
+            y: None,
+        }
+    }
+    /// Creates a new cascade from a discrete transfer function
+    pub fn from_tf(
+        input: IO<()>,
+        output: IO<()>,
+        tf: &DiscreteTransferFunction,
+    ) -> Result<Self, LtiError> {
+        let (gain, sections) = tf.biquads()?;
+        Ok(Self::new(input, output, gain, sections))
+    }
+}
+impl Iterator for BiquadCascade {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        let u = self.u.take()?;
+        let (gain, sections) = (self.gain, &self.sections);
+        if self.states.len() != u.len() {
+            self.states = vec![vec![[0f64; 2]; sections.len()]; u.len()];
+        }
+        self.y = Some(
+            u.into_iter()
+                .zip(self.states.iter_mut())
+                .map(|(u, states)| {
+                    sections
+                        .iter()
+                        .zip(states.iter_mut())
+                        .fold(gain * u, |x, (section, state)| section.filter(state, x))
+                })
+                .collect(),
+        );
+        Some(())
+    }
+}
+impl Dos for BiquadCascade {
+    type Input = Vec<f64>;
+    type Output = Vec<f64>;
+    fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+        self.y.clone().map(|y| vec![self.output.io(Some(y))])
+    }
+    fn inputs(&mut self, mut data: Option<Vec<IO<Vec<f64>>>>) -> Result<&mut Self, DOSIOSError> {
+        self.u = Some(take_or_err(&mut data, self.input)?);
+        Ok(self)
+    }
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.states.clear();
+        self.u = None;
+        self.y = None;
+        Ok(self)
+    }
+}
+impl IOTags for BiquadCascade {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        vec![self.output.io(None)]
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        vec![self.input.io(None)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ios;
+
+    // Runs `dos` for `n` steps with a unit step on OSSM1Lcl and returns the 1st output channel
+    fn step_response<D>(dos: &mut D, n: usize) -> Vec<f64>
+    where
+        D: Dos<Input = Vec<f64>, Output = Vec<f64>> + Iterator,
+    {
+        (0..n)
+            .map(|_| {
+                let y = dos
+                    .in_step_out(Some(vec![ios!(OSSM1Lcl(vec![1f64]))]))
+                    .unwrap()
+                    .unwrap();
+                Option::<Vec<f64>>::from(&y[0]).unwrap()[0]
+            })
+            .collect()
+    }
+    // Frequency response of a discrete transfer function
+    fn freq_resp(tf: &DiscreteTransferFunction, frequency: f64) -> Complex<f64> {
+        let z = Complex::new(0f64, 2. * PI * frequency * tf.sampling_period).exp();
+        let eval = |p: &[f64]| {
+            p.iter()
+                .fold(Complex::new(0f64, 0f64), |acc, c| acc * z + c)
+        };
+        eval(&tf.num) / eval(&tf.den)
+    }
+
+    #[test]
+    fn zoh_state_space() {
+        // 1st order low-pass: y[k] = 1 - exp(-k T/tau)
+        let (tau, ts) = (0.1, 1e-2);
+        let model = ContinuousStateSpace {
+            a: vec![vec![-1. / tau]],
+            b: vec![vec![1. / tau]],
+            c: vec![vec![1.]],
+            d: vec![vec![0.]],
+        }
+        .discretize(ts, Discretization::ZeroOrderHold)
+        .unwrap();
+        let mut ss =
+            StateSpace::new(model, vec![ios!(OSSM1Lcl)], vec![(ios!(SensorData), 1)]).unwrap();
+        step_response(&mut ss, 20)
+            .into_iter()
+            .enumerate()
+            .for_each(|(k, y)| assert!((y - (1. - (-(k as f64) * ts / tau).exp())).abs() < 1e-12));
+    }
+
+    #[test]
+    fn tustin_prewarp_and_matched_pole() {
+        // 2nd order resonance
+        let (w0, zeta, ts) = (2. * PI * 10., 0.05, 1e-2);
+        let tf = TransferFunction::new(vec![w0 * w0], vec![1., 2. * zeta * w0, w0 * w0]);
+        let dtf = tf
+            .discretize(ts, Discretization::Tustin { prewarp: Some(10.) })
+            .unwrap();
+        // exact match at the prewarping frequency: |H(j w0)| = 1/(2 zeta)
+        assert!((freq_resp(&dtf, 10.).norm() - 0.5 / zeta).abs() < 1e-9);
+        let dtf = tf.discretize(ts, Discretization::MatchedPole).unwrap();
+        assert!((freq_resp(&dtf, 0.).norm() - 1.).abs() < 1e-9);
+        let mut poles = roots(&dtf.den);
+        poles.sort_by(|a, b| a.im.partial_cmp(&b.im).unwrap());
+        let p = Complex::new(-zeta * w0, w0 * (1. - zeta * zeta).sqrt());
+        assert!((poles[1] - (p * ts).exp()).norm() < 1e-9);
+    }
+
+    #[test]
+    fn biquads_vs_state_space() {
+        // 4th order system as a transfer function and as a state space model
+        let tf = TransferFunction::new(vec![1., 3., 400.], vec![1., 20., 1.5e4, 1.2e5, 4e6]);
+        let ts = 1e-3;
+        let mut biquads = BiquadCascade::from_tf(
+            ios!(OSSM1Lcl),
+            ios!(SensorData),
+            &tf.discretize(ts, Discretization::ZeroOrderHold).unwrap(),
+        )
+        .unwrap();
+        let model = tf
+            .realization()
+            .unwrap()
+            .discretize(ts, Discretization::ZeroOrderHold)
+            .unwrap();
+        let path =
+            std::env::temp_dir().join(format!("dosio-state-space-{}.pkl", std::process::id()));
+        save(
+            &StateSpace::new(model, vec![ios!(OSSM1Lcl)], vec![(ios!(SensorData), 1)]).unwrap(),
+            &path,
+        )
+        .unwrap();
+        let mut ss: StateSpace = load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        step_response(&mut biquads, 200)
+            .into_iter()
+            .zip(step_response(&mut ss, 200))
+            .for_each(|(b, s)| assert!((b - s).abs() < 1e-6 * s.abs().max(1e-3), "{} != {}", b, s));
+    }
+}
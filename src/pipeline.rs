@@ -0,0 +1,645 @@
+//! Threaded execution of DOS components
+//!
+//! A [`Pipeline`] connects [`Dos`] components with each other and runs them for a given number of steps.
+//! The components are stepped in the order they are added to the pipeline, as in a sequential loop
+//! where each component [`in_step_out`](Dos::in_step_out) method is called one after the other:
+//!  - a component connected to a component added before it receives the outputs of the same step,
+//!  - a component connected to itself or to a component added after it receives the outputs of the previous step
+//!    (`None` at the first step).
+//!
+//! [`Pipeline::run`] puts each component on its own thread and passes the [`IO`]s through bounded channels,
+//! while [`Pipeline::run_sequential`] calls the components one after the other in the same thread.
+//! Both give exactly the same results.
+//!
+//! The outputs of the last step that feed a component connected to itself or to a component added before it
+//! are kept from one run to the next, so a pipeline run twice for `n` steps gives the same results as run once for `2n` steps.
+//! With the `checkpoint` feature, [`Pipeline::snapshot`] saves these outputs and the state of the components added with
+//! [`Pipeline::add_checkpoint`] in a single [`Snapshot`], and [`Pipeline::restore`] sets the pipeline back to it.
+
+#[cfg(feature = "checkpoint")]
+use crate::checkpoint::{Checkpoint, Snapshot, SnapshotError};
+use crate::{error::panic_message, DOSIOSError, Dos, IO};
+#[cfg(feature = "checkpoint")]
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    sync::mpsc::{sync_channel, Receiver, SyncSender},
+    thread,
+};
+
+/// Object-safe version of [`Dos`]
+///
+/// It is implemented for all the [`Dos`] components that are also [`Iterator`]s with the same input and output type
+pub trait Component<T> {
+    /// Combines `inputs`, `step` and `outputs` in a single method
+    fn in_step_out(&mut self, data: Option<Vec<IO<T>>>) -> Result<Option<Vec<IO<T>>>, DOSIOSError>;
+    /// Invokes [`Dos::init`]
+    ///
+    /// Does nothing by default
+    fn init(&mut self) -> Result<(), DOSIOSError> {
+        Ok(())
+    }
+    /// Invokes [`Dos::finalize`]
+    ///
+    /// Does nothing by default
+    fn finalize(&mut self) -> Result<(), DOSIOSError> {
+        Ok(())
+    }
+    /// Adds the state of the component to `snapshot` under `name`
+    ///
+    /// Does nothing by default
+    #[cfg(feature = "checkpoint")]
+    fn add_to_snapshot(&self, _name: &str, _snapshot: &mut Snapshot) -> Result<(), SnapshotError> {
+        Ok(())
+    }
+    /// Restores the state of the component saved in `snapshot` under `name`
+    ///
+    /// Does nothing by default
+    #[cfg(feature = "checkpoint")]
+    fn restore_from_snapshot(
+        &mut self,
+        _name: &str,
+        _snapshot: &Snapshot,
+    ) -> Result<(), SnapshotError> {
+        Ok(())
+    }
+}
+impl<T, D> Component<T> for D
+where
+    D: Dos<Input = T, Output = T> + Iterator,
+{
+    fn in_step_out(&mut self, data: Option<Vec<IO<T>>>) -> Result<Option<Vec<IO<T>>>, DOSIOSError> {
+        Dos::in_step_out(self, data)
+    }
+    fn init(&mut self) -> Result<(), DOSIOSError> {
+        Dos::init(self).map(|_| ())
+    }
+    fn finalize(&mut self) -> Result<(), DOSIOSError> {
+        Dos::finalize(self).map(|_| ())
+    }
+}
+
+// Component added with `Pipeline::add`
+struct Borrowed<'a, D>(&'a mut D);
+impl<'a, T, D> Component<T> for Borrowed<'a, D>
+where
+    D: Dos<Input = T, Output = T> + Iterator,
+{
+    fn in_step_out(&mut self, data: Option<Vec<IO<T>>>) -> Result<Option<Vec<IO<T>>>, DOSIOSError> {
+        Dos::in_step_out(self.0, data)
+    }
+    fn init(&mut self) -> Result<(), DOSIOSError> {
+        Dos::init(self.0).map(|_| ())
+    }
+    fn finalize(&mut self) -> Result<(), DOSIOSError> {
+        Dos::finalize(self.0).map(|_| ())
+    }
+}
+
+// Component added with `Pipeline::add_checkpoint`
+#[cfg(feature = "checkpoint")]
+struct Checkpointed<'a, D>(&'a mut D);
+#[cfg(feature = "checkpoint")]
+impl<'a, T, D> Component<T> for Checkpointed<'a, D>
+where
+    D: Dos<Input = T, Output = T> + Iterator + Checkpoint,
+{
+    fn in_step_out(&mut self, data: Option<Vec<IO<T>>>) -> Result<Option<Vec<IO<T>>>, DOSIOSError> {
+        Dos::in_step_out(self.0, data)
+    }
+    fn init(&mut self) -> Result<(), DOSIOSError> {
+        Dos::init(self.0).map(|_| ())
+    }
+    fn finalize(&mut self) -> Result<(), DOSIOSError> {
+        Dos::finalize(self.0).map(|_| ())
+    }
+    fn add_to_snapshot(&self, name: &str, snapshot: &mut Snapshot) -> Result<(), SnapshotError> {
+        snapshot.with_component(name, &*self.0)?;
+        Ok(())
+    }
+    fn restore_from_snapshot(
+        &mut self,
+        name: &str,
+        snapshot: &Snapshot,
+    ) -> Result<(), SnapshotError> {
+        snapshot.restore(name, self.0)?;
+        Ok(())
+    }
+}
+
+/// Index of a component in a [`Pipeline`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeId(usize);
+
+struct Node<'a, T> {
+    name: String,
+    component: Box<dyn Component<T> + Send + 'a>,
+    output: Option<Vec<IO<()>>>,
+}
+
+struct Edge {
+    from: usize,
+    to: usize,
+    tags: Vec<IO<()>>,
+}
+impl Edge {
+    // Feedback edges carry the outputs of the previous step
+    fn is_delayed(&self) -> bool {
+        self.from >= self.to
+    }
+}
+
+// Reason a component thread has stopped before the last step
+enum Stop {
+    Failed(DOSIOSError),
+    Cancelled,
+}
+
+pub(crate) type Data<T> = Option<Vec<IO<T>>>;
+// Component outputs channel with the tags of the outputs it carries
+type Output<T> = (SyncSender<Data<T>>, Vec<IO<()>>);
+
+/// A network of DOS components
+pub struct Pipeline<'a, T> {
+    nodes: Vec<Node<'a, T>>,
+    edges: Vec<Edge>,
+    capacity: usize,
+    // outputs of the last step of each component
+    last: Vec<Data<T>>,
+}
+impl<'a, T> Default for Pipeline<'a, T> {
+    fn default() -> Self {
+        Self {
+            nodes: vec![],
+            edges: vec![],
+            capacity: 1,
+            last: vec![],
+        }
+    }
+}
+impl<'a, T: Clone + Send> Pipeline<'a, T> {
+    /// Creates an empty pipeline
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Sets the number of steps a component can get ahead of the components it feeds (default: 1)
+    pub fn capacity(self, capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ..self
+        }
+    }
+    /// Adds a component to the pipeline
+    pub fn add<D>(&mut self, name: &str, component: &'a mut D) -> NodeId
+    where
+        D: Dos<Input = T, Output = T> + Iterator + Send,
+    {
+        self.push(name, Box::new(Borrowed(component)))
+    }
+    /// Adds a component to the pipeline, its state is saved by [`snapshot`](Pipeline::snapshot)
+    #[cfg(feature = "checkpoint")]
+    pub fn add_checkpoint<D>(&mut self, name: &str, component: &'a mut D) -> NodeId
+    where
+        D: Dos<Input = T, Output = T> + Iterator + Checkpoint + Send,
+    {
+        self.push(name, Box::new(Checkpointed(component)))
+    }
+    fn push(&mut self, name: &str, component: Box<dyn Component<T> + Send + 'a>) -> NodeId {
+        self.nodes.push(Node {
+            name: name.to_string(),
+            component,
+            output: None,
+        });
+        self.last.push(None);
+        NodeId(self.nodes.len() - 1)
+    }
+    /// Returns the name of a component
+    pub fn name(&self, node: NodeId) -> &str {
+        &self.nodes[node.0].name
+    }
+    /// Passes the outputs of `from` that match `tags` to the inputs of `to`
+    ///
+    /// All the outputs are passed if `tags` is empty
+    pub fn connect(&mut self, from: NodeId, to: NodeId, tags: Vec<IO<()>>) -> &mut Self {
+        self.edges.push(Edge {
+            from: from.0,
+            to: to.0,
+            tags,
+        });
+        self
+    }
+    /// Adds the outputs of `node` that match `tags` to the outputs of the pipeline
+    ///
+    /// All the outputs are added if `tags` is empty
+    pub fn output(&mut self, node: NodeId, tags: Vec<IO<()>>) -> &mut Self {
+        self.nodes[node.0].output = Some(tags);
+        self
+    }
+    // Invokes `f` on each component, in the order they are added
+    fn for_each_component<F>(&mut self, f: F) -> Result<(), DOSIOSError>
+    where
+        F: Fn(&mut (dyn Component<T> + Send + 'a)) -> Result<(), DOSIOSError>,
+    {
+        for node in self.nodes.iter_mut() {
+            f(node.component.as_mut()).map_err(|e| e.with_component(node.name.as_str()))?;
+        }
+        Ok(())
+    }
+    /// Runs the pipeline for `n_step` steps, one component after the other
+    ///
+    /// The components are [`init`](Dos::init)ialized before the first step and [`finalize`](Dos::finalize)d after the last one.
+    /// Returns the pipeline outputs at each step
+    pub fn run_sequential(&mut self, n_step: usize) -> Result<Vec<Data<T>>, DOSIOSError> {
+        self.for_each_component(|component| component.init())?;
+        let last = &mut self.last;
+        let mut outputs = Vec::with_capacity(n_step);
+        for step in 0..n_step {
+            for (i, node) in self.nodes.iter_mut().enumerate() {
+                let inputs = merge(
+                    self.edges
+                        .iter()
+                        .filter(|edge| edge.to == i)
+                        .map(|edge| select(&last[edge.from], &edge.tags)),
+                );
+                last[i] = node
+                    .component
+                    .in_step_out(inputs)
+                    .map_err(|e| e.with_component(node.name.as_str()).with_step(step))?;
+            }
+            outputs.push(merge(self.nodes.iter().zip(last.iter()).filter_map(
+                |(node, data)| node.output.as_ref().map(|tags| select(data, tags)),
+            )));
+        }
+        self.for_each_component(|component| component.finalize())?;
+        Ok(outputs)
+    }
+    /// Runs the pipeline for `n_step` steps, each component on its own thread
+    ///
+    /// The components are [`init`](Dos::init)ialized before the first step and [`finalize`](Dos::finalize)d after the last one.
+    /// Returns the pipeline outputs at each step
+    pub fn run(&mut self, n_step: usize) -> Result<Vec<Data<T>>, DOSIOSError> {
+        self.for_each_component(|component| component.init())?;
+        let outputs = self.run_threads(n_step)?;
+        self.for_each_component(|component| component.finalize())?;
+        Ok(outputs)
+    }
+    fn run_threads(&mut self, n_step: usize) -> Result<Vec<Data<T>>, DOSIOSError> {
+        let n_node = self.nodes.len();
+        let mut receivers: Vec<Vec<Receiver<Data<T>>>> = (0..n_node).map(|_| vec![]).collect();
+        let mut senders: Vec<Vec<Output<T>>> = (0..n_node).map(|_| vec![]).collect();
+        for edge in &self.edges {
+            let (tx, rx) = sync_channel(self.capacity);
+            if edge.is_delayed() {
+                tx.send(select(&self.last[edge.from], &edge.tags))
+                    .expect("the receiver is alive");
+            }
+            senders[edge.from].push((tx, edge.tags.clone()));
+            receivers[edge.to].push(rx);
+        }
+        let mut collectors = vec![];
+        for (node, senders) in self.nodes.iter().zip(senders.iter_mut()) {
+            if let Some(tags) = &node.output {
+                let (tx, rx) = sync_channel(self.capacity);
+                senders.push((tx, tags.clone()));
+                collectors.push(rx);
+            }
+        }
+
+        thread::scope(|s| {
+            let handles: Vec<_> = self
+                .nodes
+                .iter_mut()
+                .zip(receivers)
+                .zip(senders)
+                .map(|((node, receivers), senders)| {
+                    let (name, component) = (node.name.as_str(), &mut *node.component);
+                    let handle = s.spawn(move || -> Result<Data<T>, Stop> {
+                        let mut last = None;
+                        for step in 0..n_step {
+                            let inputs = receivers
+                                .iter()
+                                .map(|rx| rx.recv().map_err(|_| Stop::Cancelled))
+                                .collect::<Result<Vec<_>, Stop>>()?;
+                            let outputs = component
+                                .in_step_out(merge(inputs.into_iter()))
+                                .map_err(|e| {
+                                    Stop::Failed(e.with_component(name).with_step(step))
+                                })?;
+                            for (tx, tags) in &senders {
+                                // at the last step, the components fed back may be done already
+                                if tx.send(select(&outputs, tags)).is_err() && step + 1 < n_step {
+                                    return Err(Stop::Cancelled);
+                                }
+                            }
+                            last = outputs;
+                        }
+                        Ok(last)
+                    });
+                    (name, handle)
+                })
+                .collect();
+
+            let mut outputs = Vec::with_capacity(n_step);
+            for _ in 0..n_step {
+                match collectors
+                    .iter()
+                    .map(|rx| rx.recv())
+                    .collect::<Result<Vec<_>, _>>()
+                {
+                    Ok(data) => outputs.push(merge(data.into_iter())),
+                    Err(_) => break,
+                }
+            }
+            drop(collectors);
+
+            let mut error = None;
+            let mut last = vec![];
+            for (name, handle) in handles {
+                let result = handle.join().unwrap_or_else(|payload| {
+                    let message = format!("the component panicked: {}", panic_message(&*payload));
+                    Err(Stop::Failed(
+                        DOSIOSError::Step(message.into()).with_component(name),
+                    ))
+                });
+                match result {
+                    Ok(data) => last.push(data),
+                    Err(Stop::Failed(e)) => {
+                        error.get_or_insert(e);
+                    }
+                    Err(Stop::Cancelled) => (),
+                }
+            }
+            match error {
+                Some(e) => Err(e),
+                None => {
+                    if n_step > 0 {
+                        self.last = last;
+                    }
+                    Ok(outputs)
+                }
+            }
+        })
+    }
+}
+#[cfg(feature = "checkpoint")]
+impl<'a, T> Pipeline<'a, T>
+where
+    T: Clone + Send + Serialize + DeserializeOwned,
+{
+    /// Returns a snapshot of the components added with [`add_checkpoint`](Pipeline::add_checkpoint)
+    /// and of the outputs of the last step of all the components
+    ///
+    /// The states and the outputs are saved under the names of the components
+    pub fn snapshot(&self) -> Result<Snapshot, SnapshotError> {
+        let mut snapshot = Snapshot::new();
+        for (node, last) in self.nodes.iter().zip(&self.last) {
+            node.component.add_to_snapshot(&node.name, &mut snapshot)?;
+            snapshot.with_buffer(&node.name, last)?;
+        }
+        Ok(snapshot)
+    }
+    /// Restores the components added with [`add_checkpoint`](Pipeline::add_checkpoint)
+    /// and the outputs of the last step from a [`snapshot`](Pipeline::snapshot)
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<&mut Self, SnapshotError> {
+        for (node, last) in self.nodes.iter_mut().zip(self.last.iter_mut()) {
+            node.component.restore_from_snapshot(&node.name, snapshot)?;
+            *last = snapshot.buffer(&node.name)?;
+        }
+        Ok(self)
+    }
+}
+
+// Clones the `IO`s matching `tags`
+fn select<T: Clone>(data: &Data<T>, tags: &[IO<()>]) -> Data<T> {
+    data.as_ref().map(|data| {
+        data.iter()
+            .filter(|io| tags.is_empty() || tags.iter().any(|tag| *io == tag))
+            .cloned()
+            .collect()
+    })
+}
+
+// Concatenates the `IO`s
+pub(crate) fn merge<T, I: Iterator<Item = Data<T>>>(data: I) -> Data<T> {
+    data.fold(None, |merged, data| match (merged, data) {
+        (None, data) => data,
+        (merged, None) => merged,
+        (Some(mut merged), Some(data)) => {
+            merged.extend(data);
+            Some(merged)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compare::IOVecCompare, io::IOKind, ios};
+
+    // y[k] = a y[k-1] + b u[k] on every channel of the input, output as `kind`
+    struct Filter {
+        a: f64,
+        b: f64,
+        y: Vec<f64>,
+        kind: IOKind,
+        fail_at: Option<usize>,
+        panic_at: Option<usize>,
+        step: usize,
+        n_init: usize,
+        n_finalize: usize,
+    }
+    impl Filter {
+        fn new(a: f64, b: f64, kind: IOKind) -> Self {
+            Self {
+                a,
+                b,
+                y: vec![0f64; 2],
+                kind,
+                fail_at: None,
+                panic_at: None,
+                step: 0,
+                n_init: 0,
+                n_finalize: 0,
+            }
+        }
+    }
+    impl Iterator for Filter {
+        type Item = ();
+        fn next(&mut self) -> Option<()> {
+            self.step += 1;
+            Some(())
+        }
+    }
+    impl Dos for Filter {
+        type Input = Vec<f64>;
+        type Output = Vec<f64>;
+        fn inputs(&mut self, data: Option<Vec<IO<Vec<f64>>>>) -> Result<&mut Self, DOSIOSError> {
+            if Some(self.step) == self.panic_at {
+                panic!("filter panic");
+            }
+            if Some(self.step) == self.fail_at {
+                return Err(DOSIOSError::Inputs("filter failure".into()));
+            }
+            let u: Vec<f64> = data
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(Option::<Vec<f64>>::from)
+                .fold(vec![1f64; 2], |u, x| {
+                    u.iter().zip(x).map(|(u, x)| u + x).collect()
+                });
+            let (a, b) = (self.a, self.b);
+            self.y
+                .iter_mut()
+                .zip(u)
+                .for_each(|(y, u)| *y = a * *y + b * u);
+            Ok(self)
+        }
+        fn outputs(&mut self) -> Option<Vec<IO<Vec<f64>>>> {
+            Some(vec![self.kind.io(Some(self.y.clone()))])
+        }
+        fn init(&mut self) -> Result<&mut Self, DOSIOSError> {
+            if self.fail_at == Some(0) {
+                return Err(DOSIOSError::Step("filter init failure".into()));
+            }
+            self.n_init += 1;
+            Ok(self)
+        }
+        fn finalize(&mut self) -> Result<&mut Self, DOSIOSError> {
+            self.n_finalize += 1;
+            Ok(self)
+        }
+    }
+
+    fn run(threaded: bool, fail_at: Option<usize>) -> Result<Vec<Data<Vec<f64>>>, DOSIOSError> {
+        let mut plant = Filter::new(0.9, 0.1, IOKind::OSSM1Lcl);
+        let mut sensor = Filter::new(0.5, 0.5, IOKind::SensorData);
+        let mut controller = Filter::new(0.99, -0.2, IOKind::M1RBMcmd);
+        controller.fail_at = fail_at;
+        let mut pipeline = Pipeline::new();
+        let p = pipeline.add("plant", &mut plant);
+        let s = pipeline.add("sensor", &mut sensor);
+        let c = pipeline.add("controller", &mut controller);
+        pipeline
+            .connect(p, s, vec![])
+            .connect(s, c, vec![ios!(SensorData)])
+            .connect(c, p, vec![ios!(M1RBMcmd)])
+            .output(p, vec![])
+            .output(s, vec![]);
+        if threaded {
+            pipeline.run(50)
+        } else {
+            pipeline.run_sequential(50)
+        }
+    }
+
+    #[test]
+    fn threaded_vs_sequential() {
+        let sequential = run(false, None).unwrap();
+        let threaded = run(true, None).unwrap();
+        assert_eq!(sequential.len(), 50);
+        sequential.iter().zip(&threaded).for_each(|(s, t)| {
+            let (s, t) = (s.as_ref().unwrap(), t.as_ref().unwrap());
+            assert_eq!(s.len(), 2);
+            assert!(s.eq_data(t));
+        });
+    }
+
+    #[cfg(feature = "checkpoint")]
+    impl crate::Checkpoint for Filter {
+        type State = Vec<f64>;
+        fn checkpoint(&self) -> Result<Self::State, DOSIOSError> {
+            Ok(self.y.clone())
+        }
+        fn restore(&mut self, state: Self::State) -> Result<&mut Self, DOSIOSError> {
+            self.y = state;
+            Ok(self)
+        }
+    }
+
+    #[cfg(feature = "checkpoint")]
+    #[test]
+    fn snapshot() {
+        let mut plant = Filter::new(0.9, 0.1, IOKind::OSSM1Lcl);
+        let mut controller = Filter::new(0.99, -0.2, IOKind::M1RBMcmd);
+        let mut pipeline = Pipeline::new();
+        let p = pipeline.add_checkpoint("plant", &mut plant);
+        let c = pipeline.add_checkpoint("controller", &mut controller);
+        pipeline
+            .connect(p, c, vec![])
+            .connect(c, p, vec![])
+            .output(p, vec![]);
+        pipeline.run(20).unwrap();
+        let snapshot = pipeline.snapshot().unwrap();
+        let expected = pipeline.run_sequential(30).unwrap();
+
+        let mut plant = Filter::new(0.9, 0.1, IOKind::OSSM1Lcl);
+        let mut controller = Filter::new(0.99, -0.2, IOKind::M1RBMcmd);
+        let mut pipeline = Pipeline::new();
+        let p = pipeline.add_checkpoint("plant", &mut plant);
+        let c = pipeline.add_checkpoint("controller", &mut controller);
+        pipeline
+            .connect(p, c, vec![])
+            .connect(c, p, vec![])
+            .output(p, vec![]);
+        let restored = pipeline.restore(&snapshot).unwrap().run(30).unwrap();
+        assert_eq!(restored.len(), 30);
+        expected.iter().zip(&restored).for_each(|(e, r)| {
+            assert!(e.as_ref().unwrap().eq_data(r.as_ref().unwrap()));
+        });
+    }
+
+    #[test]
+    fn lifecycle() {
+        let mut plant = Filter::new(0.9, 0.1, IOKind::OSSM1Lcl);
+        let mut pipeline = Pipeline::new();
+        pipeline.add("plant", &mut plant);
+        pipeline.run(3).unwrap();
+        pipeline.run_sequential(3).unwrap();
+        drop(pipeline);
+        assert_eq!((plant.n_init, plant.n_finalize), (2, 2));
+
+        for threaded in [true, false] {
+            let error = run(threaded, Some(0)).unwrap_err();
+            assert!(matches!(error, DOSIOSError::Step(_)));
+            assert_eq!(
+                error
+                    .context()
+                    .and_then(|context| context.component.clone()),
+                Some("controller".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn failure() {
+        for threaded in [true, false] {
+            let context = run(threaded, Some(10)).unwrap_err().context().cloned();
+            assert_eq!(
+                context.and_then(|context| context.component),
+                Some("controller".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn panic() {
+        let mut plant = Filter::new(0.9, 0.1, IOKind::OSSM1Lcl);
+        let mut controller = Filter::new(0.99, -0.2, IOKind::M1RBMcmd);
+        controller.panic_at = Some(5);
+        let mut pipeline = Pipeline::new();
+        let p = pipeline.add("plant", &mut plant);
+        let c = pipeline.add("controller", &mut controller);
+        pipeline
+            .connect(p, c, vec![])
+            .connect(c, p, vec![])
+            .output(p, vec![]);
+        let error = pipeline.run(10).unwrap_err();
+        assert!(matches!(error, DOSIOSError::Step(_)));
+        assert_eq!(
+            error
+                .context()
+                .and_then(|context| context.component.clone()),
+            Some("controller".to_string())
+        );
+        assert!(error.report().to_string().contains("filter panic"));
+    }
+}
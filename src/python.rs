@@ -0,0 +1,397 @@
+//! Python bindings
+//!
+//! [`register`] adds to a Python module:
+//!  - the `IOKind` enum of the [`IO`] variants, a Python `IntEnum`,
+//!  - the `IO` class, an [`IO<Vec<f64>>`](IO) with its data as a numpy array,
+//!  - the `Component` class, a Rust [`Dos`] component, or a graph of components, stepped from Python.
+//!
+//! ```ignore
+//! use dosio::{blocks::Gain, ios, python::{register, RustComponent}};
+//! use pyo3::prelude::*;
+//!
+//! #[pymodule]
+//! fn simulation(m: &Bound<'_, PyModule>) -> PyResult<()> {
+//!     register(m)?;
+//!     m.add("gain", RustComponent::with_tags(Gain::new(ios!(Pssn), ios!(SensorData), 2.)))
+//! }
+//! ```
+//! ```python
+//! from simulation import IO, IOKind, gain
+//! y = gain.in_step_out([IO(IOKind.Pssn, [1.0, 2.0])])
+//! y[0].data  # array([2., 4.])
+//! ```
+//!
+//! In the other direction, [`PythonComponent`] wraps a Python object as a [`Dos`] component,
+//! so it can be called from a Rust [`Pipeline`](crate::Pipeline).
+//! The Python object must implement the methods `inputs(data)`, `step()` and `outputs()`,
+//! `data` and the outputs being lists of `IO` or `None`,
+//! and may implement the methods `inputs_tags()` and `outputs_tags()` returning lists of `IOKind` or of variant names.
+
+use crate::{io::IOKind, pipeline::Component, DOSIOSError, Dos, IOTags, IO};
+use numpy::{PyArray1, PyReadonlyArray1};
+use pyo3::{exceptions::PyValueError, prelude::*, sync::PyOnceLock, types::PyString};
+use std::sync::Mutex;
+
+impl From<DOSIOSError> for PyErr {
+    fn from(e: DOSIOSError) -> Self {
+        pyo3::exceptions::PyRuntimeError::new_err(e.report().to_string())
+    }
+}
+
+// Returns the variant of an `IOKind` member or of a variant name
+fn io_kind(kind: &Bound<'_, PyAny>) -> PyResult<IOKind> {
+    if let Ok(name) = kind.cast::<PyString>() {
+        let name = name.to_str()?;
+        return name
+            .parse()
+            .map_err(|_| PyValueError::new_err(format!("unknown IO variant {}", name)));
+    }
+    let index: usize = kind.extract()?;
+    IOKind::ALL
+        .get(index)
+        .copied()
+        .ok_or_else(|| PyValueError::new_err(format!("unknown IO variant {}", index)))
+}
+
+// `sys.modules`
+static MODULES: PyOnceLock<Py<PyAny>> = PyOnceLock::new();
+
+// Returns the data of an `IO` from a numpy array or a sequence of floats
+fn values(data: &Bound<'_, PyAny>) -> PyResult<Vec<f64>> {
+    // an array can only be given if numpy has been imported
+    let numpy = MODULES
+        .import(data.py(), "sys", "modules")?
+        .contains("numpy")?;
+    if numpy {
+        if let Ok(array) = data.extract::<PyReadonlyArray1<'_, f64>>() {
+            return Ok(array.as_array().to_vec());
+        }
+    }
+    data.extract()
+}
+
+/// Python `IO` class
+#[pyclass(name = "IO", module = "dosio", from_py_object)]
+#[derive(Debug, Clone)]
+pub struct PyIO {
+    kind: IOKind,
+    data: Option<Vec<f64>>,
+}
+#[pymethods]
+impl PyIO {
+    /// Creates a new `IO` from an `IOKind` member or a variant name and, optionally, a numpy array or a sequence of floats
+    #[new]
+    #[pyo3(signature = (kind, data=None))]
+    fn new(kind: &Bound<'_, PyAny>, data: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        Ok(Self {
+            kind: io_kind(kind)?,
+            data: data.map(values).transpose()?,
+        })
+    }
+    /// Variant name
+    #[getter]
+    fn name(&self) -> &'static str {
+        self.kind.name()
+    }
+    /// Data as a numpy array, `None` if there is no data
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> Option<Bound<'py, PyArray1<f64>>> {
+        self.data
+            .as_ref()
+            .map(|data| PyArray1::from_slice(py, data))
+    }
+    /// Data as a list, `None` if there is no data
+    fn tolist(&self) -> Option<Vec<f64>> {
+        self.data.clone()
+    }
+    fn __repr__(&self) -> String {
+        match &self.data {
+            Some(data) => format!("IO({}, {:?})", self.kind, data),
+            None => format!("IO({})", self.kind),
+        }
+    }
+}
+impl From<IO<Vec<f64>>> for PyIO {
+    fn from(io: IO<Vec<f64>>) -> Self {
+        let (kind, data) = io.into_parts();
+        Self { kind, data }
+    }
+}
+impl From<PyIO> for IO<Vec<f64>> {
+    fn from(io: PyIO) -> Self {
+        io.kind.io(io.data)
+    }
+}
+
+/// Python `Component` class, a Rust [`Dos`] component stepped from Python
+#[pyclass(name = "Component", module = "dosio")]
+pub struct RustComponent {
+    dos: Mutex<Box<dyn Component<Vec<f64>> + Send>>,
+    inputs_tags: Vec<IO<()>>,
+    outputs_tags: Vec<IO<()>>,
+}
+impl RustComponent {
+    /// Creates a new Python component from a Rust component
+    pub fn new<D>(dos: D) -> Self
+    where
+        D: Dos<Input = Vec<f64>, Output = Vec<f64>> + Iterator + Send + 'static,
+    {
+        Self {
+            dos: Mutex::new(Box::new(dos)),
+            inputs_tags: vec![],
+            outputs_tags: vec![],
+        }
+    }
+    /// Creates a new Python component from a Rust component with its [`IOTags`]
+    pub fn with_tags<D>(dos: D) -> Self
+    where
+        D: Dos<Input = Vec<f64>, Output = Vec<f64>> + IOTags + Iterator + Send + 'static,
+    {
+        let inputs_tags = dos.inputs_tags();
+        let outputs_tags = dos.outputs_tags();
+        Self {
+            inputs_tags,
+            outputs_tags,
+            ..Self::new(dos)
+        }
+    }
+}
+#[pymethods]
+impl RustComponent {
+    /// Passes the inputs to the component, steps it and returns its outputs
+    ///
+    /// The GIL is released while the component is stepped
+    #[pyo3(signature = (inputs=None))]
+    fn in_step_out(
+        &self,
+        py: Python<'_>,
+        inputs: Option<Vec<PyIO>>,
+    ) -> PyResult<Option<Vec<PyIO>>> {
+        let inputs = inputs.map(|inputs| inputs.into_iter().map(IO::from).collect());
+        let dos = &self.dos;
+        let outputs = py.detach(|| match dos.lock() {
+            Ok(mut dos) => dos.in_step_out(inputs).map_err(PyErr::from),
+            Err(_) => Err(pyo3::exceptions::PyRuntimeError::new_err(
+                "the component is poisoned",
+            )),
+        })?;
+        Ok(outputs.map(|outputs| outputs.into_iter().map(PyIO::from).collect()))
+    }
+    /// Names of the inputs
+    #[getter]
+    fn inputs_tags(&self) -> Vec<&'static str> {
+        self.inputs_tags
+            .iter()
+            .map(|tag| tag.io_kind().name())
+            .collect()
+    }
+    /// Names of the outputs
+    #[getter]
+    fn outputs_tags(&self) -> Vec<&'static str> {
+        self.outputs_tags
+            .iter()
+            .map(|tag| tag.io_kind().name())
+            .collect()
+    }
+}
+
+/// Python object wrapped as a [`Dos`] component
+///
+/// As [`outputs`](Dos::outputs) cannot fail, a failed call returns `None` and the Python exception is given by [`take_error`](PythonComponent::take_error)
+pub struct PythonComponent {
+    obj: Py<PyAny>,
+    inputs_tags: Vec<IO<()>>,
+    outputs_tags: Vec<IO<()>>,
+    error: Option<DOSIOSError>,
+}
+impl PythonComponent {
+    /// Creates a new component from a Python object
+    pub fn new(obj: Py<PyAny>) -> PyResult<Self> {
+        let (inputs_tags, outputs_tags) = Python::attach(|py| -> PyResult<_> {
+            let obj = obj.bind(py);
+            let tags = |method: &str| -> PyResult<Vec<IO<()>>> {
+                if !obj.hasattr(method)? {
+                    return Ok(vec![]);
+                }
+                obj.call_method0(method)?
+                    .try_iter()?
+                    .map(|tag| io_kind(&tag?).map(|kind| kind.io(None)))
+                    .collect()
+            };
+            Ok((tags("inputs_tags")?, tags("outputs_tags")?))
+        })?;
+        Ok(Self {
+            obj,
+            inputs_tags,
+            outputs_tags,
+            error: None,
+        })
+    }
+    /// Takes the Python exception of the last failed call to [`outputs`](Dos::outputs)
+    pub fn take_error(&mut self) -> Option<DOSIOSError> {
+        self.error.take()
+    }
+}
+impl Dos for PythonComponent {
+    type Input = Vec<f64>;
+    type Output = Vec<f64>;
+    fn outputs(&mut self) -> Option<Vec<IO<Self::Output>>> {
+        let outputs = Python::attach(|py| {
+            self.obj
+                .call_method0(py, "outputs")?
+                .extract::<Option<Vec<PyIO>>>(py)
+        });
+        match outputs {
+            Ok(outputs) => outputs.map(|outputs| outputs.into_iter().map(IO::from).collect()),
+            Err(e) => {
+                self.error = Some(DOSIOSError::Outputs(Box::new(e)));
+                None
+            }
+        }
+    }
+    fn inputs(&mut self, data: Option<Vec<IO<Self::Input>>>) -> Result<&mut Self, DOSIOSError> {
+        let data: Option<Vec<PyIO>> = data.map(|data| data.into_iter().map(PyIO::from).collect());
+        Python::attach(|py| self.obj.call_method1(py, "inputs", (data,)))
+            .map_err(|e| DOSIOSError::Inputs(Box::new(e)))?;
+        Ok(self)
+    }
+    fn step(&mut self) -> Result<&mut Self, DOSIOSError> {
+        Python::attach(|py| self.obj.call_method0(py, "step"))
+            .map_err(|e| DOSIOSError::Step(Box::new(e)))?;
+        Ok(self)
+    }
+}
+impl Iterator for PythonComponent {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        Dos::step(self).ok().map(|_| ())
+    }
+}
+impl IOTags for PythonComponent {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        self.outputs_tags.clone()
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        self.inputs_tags.clone()
+    }
+}
+
+/// Adds the `IOKind` enum and the `IO` and `Component` classes to the Python module `m`
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    // the aliases are members with the value of their new variant
+    let members: Vec<(&str, usize)> = IOKind::ALL
+        .iter()
+        .map(|&kind| (kind.name(), kind as usize))
+        .chain(
+            IOKind::ALIASES
+                .iter()
+                .map(|&(alias, kind)| (alias, kind as usize)),
+        )
+        .collect();
+    let io_kind = m
+        .py()
+        .import("enum")?
+        .getattr("IntEnum")?
+        .call1(("IOKind", members))?;
+    io_kind.setattr("__module__", m.name()?)?;
+    m.add("IOKind", io_kind)?;
+    m.add_class::<PyIO>()?;
+    m.add_class::<RustComponent>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{blocks::Gain, ios, Pipeline};
+    use pyo3::types::{PyDict, PyModule};
+    use std::ffi::CString;
+
+    const SCRIPT: &str = r#"
+from dosio import IO, IOKind
+assert gain.inputs_tags == ["Pssn"]
+y = gain.in_step_out([IO(IOKind.Pssn, [1.0, 2.0])])
+assert y[0].name == "SensorData" and y[0].tolist() == [2.0, 4.0], y
+try:
+    gain.in_step_out([IO("SensorData")])
+    assert False
+except RuntimeError as e:
+    assert "Pssn" in str(e), e
+
+class Counter:
+    def __init__(self):
+        self.k = 0
+    def outputs_tags(self):
+        return [IOKind.Pssn]
+    def inputs(self, data):
+        assert data is None
+    def step(self):
+        self.k += 1
+    def outputs(self):
+        return [IO("Pssn", [float(self.k)])]
+counter = Counter()
+"#;
+
+    #[test]
+    fn python() {
+        Python::initialize();
+        let source = Python::attach(|py| -> PyResult<Py<PyAny>> {
+            let m = PyModule::new(py, "dosio")?;
+            register(&m)?;
+            py.import("sys")?
+                .getattr("modules")?
+                .set_item("dosio", &m)?;
+            let gain = RustComponent::with_tags(Gain::new(ios!(Pssn), ios!(SensorData), 2.));
+            let globals = PyDict::new(py);
+            globals.set_item("dosio", m)?;
+            globals.set_item("gain", Py::new(py, gain)?)?;
+            py.run(&CString::new(SCRIPT).unwrap(), Some(&globals), None)?;
+            Ok(globals.get_item("counter")?.unwrap().unbind())
+        })
+        .unwrap();
+        let mut counter = PythonComponent::new(source).unwrap();
+        assert_eq!(counter.outputs_tags(), vec![ios!(Pssn)]);
+        let mut gain = Gain::new(ios!(Pssn), ios!(SensorData), 2.);
+        let mut pipeline = Pipeline::new();
+        let a = pipeline.add("counter", &mut counter);
+        let b = pipeline.add("gain", &mut gain);
+        pipeline.connect(a, b, vec![]).output(b, vec![]);
+        let outputs = pipeline.run(3).unwrap();
+        let y: Vec<f64> = outputs
+            .into_iter()
+            .map(|y| y.unwrap()[0].as_ref().unwrap()[0])
+            .collect();
+        assert_eq!(y, vec![2., 4., 6.]);
+    }
+
+    const NUMPY_SCRIPT: &str = r#"
+import numpy as np
+from dosio import IO, IOKind
+u = IO(IOKind.Pssn, np.array([1.0, 2.0]))
+assert isinstance(u.data, np.ndarray) and u.data.tolist() == [1.0, 2.0], u
+y = gain.in_step_out([u])
+assert isinstance(y[0].data, np.ndarray) and (y[0].data == np.array([2.0, 4.0])).all(), y
+"#;
+
+    #[test]
+    fn numpy() {
+        Python::initialize();
+        Python::attach(|py| -> PyResult<()> {
+            // numpy is an optional dependency of the Python bindings
+            if py.import("numpy").is_err() {
+                eprintln!("numpy is not installed, the test is skipped");
+                return Ok(());
+            }
+            let m = PyModule::new(py, "dosio")?;
+            register(&m)?;
+            py.import("sys")?
+                .getattr("modules")?
+                .set_item("dosio", &m)?;
+            let gain = RustComponent::with_tags(Gain::new(ios!(Pssn), ios!(SensorData), 2.));
+            let globals = PyDict::new(py);
+            globals.set_item("gain", Py::new(py, gain)?)?;
+            py.run(&CString::new(NUMPY_SCRIPT).unwrap(), Some(&globals), None)
+        })
+        .unwrap();
+    }
+}
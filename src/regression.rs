@@ -0,0 +1,492 @@
+//! Golden-run regression testing
+//!
+//! A [`Run`] records the inputs and the outputs of a [`Dos`] model at each step and is saved to disk as a pickle file.
+//! Later on, the same inputs are replayed to the model and the new outputs are compared step by step to the recorded ones,
+//! within the [`Tolerances`] set for each [`IO`] variant.
+//! The comparison results in a [`Report`] with the pass/fail status and the worst deviation of each variant.
+//!
+//! The [`check`] function either records the reference run if it does not exist yet or replays it, so it fits in a `cargo test`:
+//! ```no_run
+//! # use dosio::{regression::{check, Tolerances}, Tolerance, Dos, IO, DOSIOSError};
+//! # fn run<D: Dos<Input = Vec<f64>, Output = Vec<f64>> + Iterator>(mut model: D, inputs: Vec<Option<Vec<IO<Vec<f64>>>>>) {
+//! let report = check("golden/model.pkl", &mut model, inputs, &Tolerances::new(Tolerance::rel(1e-9))).unwrap();
+//! report.assert();
+//! # }
+//! ```
+//! Setting the environment variable `DOSIO_REGRESSION_RECORD` forces the reference run to be recorded again.
+
+use crate::{
+    compare::{pair, Difference, Tolerance},
+    io::{IOKind, FINGERPRINT},
+    DOSIOSError, Dos, IO,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    env, fmt,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+/// Inputs or outputs of a step
+pub type Data = Option<Vec<IO<Vec<f64>>>>;
+
+/// Regression error type
+#[derive(Debug)]
+pub enum RegressionError {
+    /// Cannot read or write the run file
+    Io(std::io::Error),
+    /// Cannot (de)serialize the run
+    Pickle(serde_pickle::Error),
+    /// The model has failed
+    Dos(DOSIOSError),
+}
+impl fmt::Display for RegressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Regression run file error: {}", e),
+            Self::Pickle(e) => write!(f, "Regression run (de)serialization error: {}", e),
+            Self::Dos(e) => write!(f, "Regression run model error: {}", e),
+        }
+    }
+}
+impl std::error::Error for RegressionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Pickle(e) => Some(e),
+            Self::Dos(e) => Some(e),
+        }
+    }
+}
+impl From<std::io::Error> for RegressionError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+impl From<serde_pickle::Error> for RegressionError {
+    fn from(e: serde_pickle::Error) -> Self {
+        Self::Pickle(e)
+    }
+}
+impl From<DOSIOSError> for RegressionError {
+    fn from(e: DOSIOSError) -> Self {
+        Self::Dos(e)
+    }
+}
+
+/// Per-variant tolerances
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Tolerances {
+    /// Tolerance for the variants without a specific tolerance
+    pub default: Tolerance,
+    /// Variant specific tolerances
+    pub variants: HashMap<IOKind, Tolerance>,
+}
+impl Tolerances {
+    /// Creates a new set of tolerances with the `default` tolerance
+    pub fn new(default: Tolerance) -> Self {
+        Self {
+            default,
+            variants: HashMap::new(),
+        }
+    }
+    /// Sets the tolerance of the variant `kind`
+    pub fn with(mut self, kind: IOKind, tolerance: Tolerance) -> Self {
+        self.variants.insert(kind, tolerance);
+        self
+    }
+    /// Returns the tolerance of the variant `kind`
+    pub fn get(&self, kind: IOKind) -> Tolerance {
+        self.variants.get(&kind).cloned().unwrap_or(self.default)
+    }
+}
+
+/// A simulation step
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Step {
+    /// Inputs to the model
+    pub inputs: Data,
+    /// Outputs from the model
+    pub outputs: Data,
+}
+
+/// A reference run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Run {
+    /// Run name
+    pub name: String,
+    /// Hash of the FEM model of the [`IO`] enum at the time of recording
+    pub fingerprint: u64,
+    /// Recorded steps
+    pub steps: Vec<Step>,
+}
+impl Run {
+    /// Records a run of `model` driven by the sequence of `inputs`
+    pub fn record<D, I>(name: &str, model: &mut D, inputs: I) -> Result<Self, RegressionError>
+    where
+        D: Dos<Input = Vec<f64>, Output = Vec<f64>> + Iterator,
+        I: IntoIterator<Item = Data>,
+    {
+        Self::record_with(name, inputs, |data| model.in_step_out(data))
+    }
+    /// Records a run of the pipeline `step` driven by the sequence of `inputs`
+    ///
+    /// `step` is invoked once per step with the inputs and returns the outputs
+    pub fn record_with<I, F>(name: &str, inputs: I, mut step: F) -> Result<Self, RegressionError>
+    where
+        I: IntoIterator<Item = Data>,
+        F: FnMut(Data) -> Result<Data, DOSIOSError>,
+    {
+        let steps = inputs
+            .into_iter()
+            .map(|inputs| {
+                Ok(Step {
+                    inputs: inputs.clone(),
+                    outputs: step(inputs)?,
+                })
+            })
+            .collect::<Result<Vec<Step>, DOSIOSError>>()?;
+        Ok(Self {
+            name: name.to_string(),
+            fingerprint: FINGERPRINT.hash,
+            steps,
+        })
+    }
+    /// Saves the run to a pickle file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), RegressionError> {
+        if let Some(dir) = path.as_ref().parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut file = BufWriter::new(File::create(path)?);
+        serde_pickle::to_writer(&mut file, self, true)?;
+        Ok(())
+    }
+    /// Loads a run from a pickle file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RegressionError> {
+        let file = BufReader::new(File::open(path)?);
+        Ok(serde_pickle::from_reader(file)?)
+    }
+    /// Replays the run inputs to `model` and compares the outputs to the recorded ones
+    pub fn replay<D>(
+        &self,
+        model: &mut D,
+        tolerances: &Tolerances,
+    ) -> Result<Report, RegressionError>
+    where
+        D: Dos<Input = Vec<f64>, Output = Vec<f64>> + Iterator,
+    {
+        self.replay_with(tolerances, |data| model.in_step_out(data))
+    }
+    /// Replays the run inputs to the pipeline `step` and compares the outputs to the recorded ones
+    pub fn replay_with<F>(
+        &self,
+        tolerances: &Tolerances,
+        mut step: F,
+    ) -> Result<Report, RegressionError>
+    where
+        F: FnMut(Data) -> Result<Data, DOSIOSError>,
+    {
+        let mut report = Report::new(&self.name, self.fingerprint != FINGERPRINT.hash);
+        for (i, recorded) in self.steps.iter().enumerate() {
+            let outputs = step(recorded.inputs.clone())?;
+            report.compare(
+                i,
+                recorded.outputs.as_deref(),
+                outputs.as_deref(),
+                tolerances,
+            );
+        }
+        Ok(report)
+    }
+}
+
+/// Replays the run saved at `path` or records it if it does not exist
+///
+/// The run is always recorded if the environment variable `DOSIO_REGRESSION_RECORD` is set.
+/// The name of the run is the file stem of `path`.
+pub fn check<P, D, I>(
+    path: P,
+    model: &mut D,
+    inputs: I,
+    tolerances: &Tolerances,
+) -> Result<Report, RegressionError>
+where
+    P: AsRef<Path>,
+    D: Dos<Input = Vec<f64>, Output = Vec<f64>> + Iterator,
+    I: IntoIterator<Item = Data>,
+{
+    let path = path.as_ref();
+    if path.exists() && env::var_os("DOSIO_REGRESSION_RECORD").is_none() {
+        Run::load(path)?.replay(model, tolerances)
+    } else {
+        let name = path
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let run = Run::record(&name, model, inputs)?;
+        run.save(path)?;
+        Ok(Report::new(&name, false).recorded(run.steps.len()))
+    }
+}
+
+/// Worst deviation of a variant over a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Deviation {
+    /// Variant
+    pub kind: IOKind,
+    /// Step of the largest deviation
+    pub step: usize,
+    /// Largest absolute deviation
+    pub max_abs_error: f64,
+    /// Number of steps not within tolerance
+    pub n_failed_step: usize,
+}
+
+/// Regression report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    /// Run name
+    pub name: String,
+    /// Number of steps
+    pub n_step: usize,
+    /// Whether the run has just been recorded
+    pub recorded: bool,
+    /// Whether the run has been recorded with another FEM model
+    pub fingerprint_mismatch: bool,
+    /// Worst deviation of each variant with at least a deviation
+    pub deviations: Vec<Deviation>,
+    /// First failure of the run
+    pub first_failure: Option<(usize, Difference)>,
+    /// Number of failures
+    pub n_failure: usize,
+}
+impl Report {
+    fn new(name: &str, fingerprint_mismatch: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            n_step: 0,
+            recorded: false,
+            fingerprint_mismatch,
+            deviations: vec![],
+            first_failure: None,
+            n_failure: 0,
+        }
+    }
+    fn recorded(mut self, n_step: usize) -> Self {
+        self.recorded = true;
+        self.n_step = n_step;
+        self
+    }
+    /// Returns `true` if all the outputs are within tolerance
+    pub fn passed(&self) -> bool {
+        self.n_failure == 0
+    }
+    /// Panics with the report if any output is not within tolerance
+    pub fn assert(&self) {
+        assert!(self.passed(), "{}", self);
+    }
+    fn fail(&mut self, step: usize, difference: Difference) {
+        self.n_failure += 1;
+        if self.first_failure.is_none() {
+            self.first_failure = Some((step, difference));
+        }
+    }
+    fn deviation(&mut self, kind: IOKind, step: usize, max_abs_error: f64, failed: bool) {
+        let deviation = match self.deviations.iter_mut().find(|d| d.kind == kind) {
+            Some(deviation) => deviation,
+            None => {
+                self.deviations.push(Deviation {
+                    kind,
+                    step,
+                    max_abs_error: 0f64,
+                    n_failed_step: 0,
+                });
+                self.deviations.last_mut().unwrap()
+            }
+        };
+        if max_abs_error > deviation.max_abs_error || max_abs_error.is_nan() {
+            deviation.max_abs_error = max_abs_error;
+            deviation.step = step;
+        }
+        if failed {
+            deviation.n_failed_step += 1;
+        }
+    }
+    fn compare(
+        &mut self,
+        step: usize,
+        recorded: Option<&[IO<Vec<f64>>]>,
+        replayed: Option<&[IO<Vec<f64>>]>,
+        tolerances: &Tolerances,
+    ) {
+        self.n_step += 1;
+        let (recorded, replayed) = match (recorded, replayed) {
+            (Some(recorded), Some(replayed)) => (recorded, replayed),
+            (None, None) => return,
+            (recorded, replayed) => {
+                self.fail(
+                    step,
+                    Difference::Step {
+                        lhs: recorded.is_some(),
+                        rhs: replayed.is_some(),
+                    },
+                );
+                return;
+            }
+        };
+        let (pairs, recorded_only, replayed_only) = pair(recorded, replayed);
+        for (recorded, replayed) in pairs {
+            let kind = recorded.io_kind();
+            let failure = recorded.diff(replayed, tolerances.get(kind));
+            match recorded.diff(replayed, Tolerance::exact()) {
+                Some(Difference::Values { max_abs_error, .. }) => {
+                    self.deviation(kind, step, max_abs_error, failure.is_some())
+                }
+                Some(_) => self.deviation(kind, step, f64::INFINITY, true),
+                None => (),
+            }
+            if let Some(failure) = failure {
+                self.fail(step, failure);
+            }
+        }
+        for (io, recorded) in recorded_only
+            .into_iter()
+            .map(|io| (io, true))
+            .chain(replayed_only.into_iter().map(|io| (io, false)))
+        {
+            self.deviation(io.io_kind(), step, f64::INFINITY, true);
+            self.fail(
+                step,
+                Difference::Missing {
+                    kind: io.io_kind(),
+                    lhs: recorded,
+                    rhs: !recorded,
+                },
+            );
+        }
+    }
+}
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.recorded {
+            return writeln!(f, "{}: recorded {} steps", self.name, self.n_step);
+        }
+        writeln!(
+            f,
+            "{}: {} ({} failures in {} steps)",
+            self.name,
+            if self.passed() { "PASSED" } else { "FAILED" },
+            self.n_failure,
+            self.n_step
+        )?;
+        if self.fingerprint_mismatch {
+            writeln!(f, " the run was recorded with another FEM model")?;
+        }
+        if let Some((step, difference)) = &self.first_failure {
+            writeln!(f, " first failure at step #{}: {}", step, difference)?;
+        }
+        let mut deviations: Vec<&Deviation> = self.deviations.iter().collect();
+        deviations.sort_by(|a, b| b.max_abs_error.total_cmp(&a.max_abs_error));
+        for deviation in deviations {
+            writeln!(
+                f,
+                " {:<24} max. abs. error: {:e} at step #{} ({} failed steps)",
+                deviation.kind.name(),
+                deviation.max_abs_error,
+                deviation.step,
+                deviation.n_failed_step
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ios;
+
+    // y = k x
+    struct Gain {
+        k: f64,
+        y: Vec<f64>,
+    }
+    impl Iterator for Gain {
+        type Item = ();
+        fn next(&mut self) -> Option<()> {
+            Some(())
+        }
+    }
+    impl Dos for Gain {
+        type Input = Vec<f64>;
+        type Output = Vec<f64>;
+        fn inputs(&mut self, data: Data) -> Result<&mut Self, DOSIOSError> {
+            if let Some(x) = data.and_then(|mut data| data.pop()).and_then(Option::from) {
+                self.y = x;
+                let k = self.k;
+                self.y.iter_mut().for_each(|y| *y *= k);
+            }
+            Ok(self)
+        }
+        fn outputs(&mut self) -> Data {
+            Some(vec![ios!(SensorData(self.y.clone()))])
+        }
+    }
+
+    fn inputs() -> Vec<Data> {
+        (0..10)
+            .map(|i| Some(vec![ios!(OSSM1Lcl(vec![i as f64; 3]))]))
+            .collect()
+    }
+
+    #[test]
+    fn golden() {
+        let path = env::temp_dir()
+            .join(format!("dosio-regression-{}", std::process::id()))
+            .join("gain.pkl");
+        let _ = std::fs::remove_file(&path);
+        let mut model = Gain { k: 2f64, y: vec![] };
+        let tolerances = Tolerances::new(Tolerance::exact());
+        let report = check(&path, &mut model, inputs(), &tolerances).unwrap();
+        assert!(report.recorded);
+        check(&path, &mut model, inputs(), &tolerances)
+            .unwrap()
+            .assert();
+
+        let mut model = Gain {
+            k: 2.001,
+            y: vec![],
+        };
+        let report = check(&path, &mut model, inputs(), &tolerances).unwrap();
+        assert!(!report.passed());
+        assert_eq!(report.n_failure, 9);
+        assert_eq!(report.deviations[0].step, 9);
+        let tolerances = tolerances.with(IOKind::SensorData, Tolerance::rel(1e-3));
+        check(&path, &mut model, inputs(), &tolerances)
+            .unwrap()
+            .assert();
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn missing_step() {
+        let tolerances = Tolerances::new(Tolerance::exact());
+        let mut report = Report::new("gain", false);
+        report.compare(0, None, Some(&[]), &tolerances);
+        report.compare(1, Some(&[ios!(SensorData(vec![1.]))]), None, &tolerances);
+        report.compare(2, None, None, &tolerances);
+        assert_eq!(report.n_failure, 2);
+        assert_eq!(
+            report.first_failure,
+            Some((
+                0,
+                Difference::Step {
+                    lhs: false,
+                    rhs: true
+                }
+            ))
+        );
+    }
+}
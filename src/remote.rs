@@ -0,0 +1,616 @@
+//! Remote components
+//!
+//! A [`Server`] exposes a [`Dos`] component over a TCP or a Unix-domain socket
+//! and a [`Client`] is the local proxy of the remote component, forwarding the calls to the [`Dos`] methods.
+//!
+//! The messages are encoded with the [`wire`](crate::wire) codec.
+//! On connection, the server and the client exchange the fingerprint of their [`IO`] enum in the stream headers
+//! and the server sends the [`IOTags`] of the component.
+//! Each call is a command byte followed, for [`inputs`](Dos::inputs), by the inputs frame,
+//! and is answered with a status byte followed, for [`outputs`](Dos::outputs), by the outputs frame
+//! or, for an error, by the [`DOSIOSError`] variant and the error message.
+//!
+//! ```no_run
+//! use dosio::{blocks::Gain, ios, remote::{Client, Server}, Dos};
+//! use std::{net::TcpListener, thread, time::Duration};
+//!
+//! let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+//! let addr = listener.local_addr().unwrap();
+//! thread::spawn(move || {
+//!     let mut server = Server::new(Gain::new(ios!(Pssn), ios!(SensorData), 2.));
+//!     server.serve_tcp(&listener)
+//! });
+//! let mut gain = Client::connect_tcp(addr, Some(Duration::from_secs(1))).unwrap();
+//! let y = gain.in_step_out(Some(vec![ios!(Pssn(vec![1.]))])).unwrap();
+//! ```
+
+use crate::{
+    error::BoxError,
+    io::IOKind,
+    wire::{Decoder, Encoder, WireError},
+    DOSIOSError, Dos, IOTags, IO,
+};
+use std::{
+    error::Error,
+    fmt,
+    io::{self, BufWriter, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+#[cfg(unix)]
+use std::{
+    os::unix::net::{UnixListener, UnixStream},
+    path::Path,
+};
+
+type Data = Vec<IO<Vec<f64>>>;
+
+/// Remote component error
+#[derive(Debug)]
+pub enum RemoteError {
+    /// Encoding or decoding error
+    Wire(WireError),
+    /// The peer did not answer in time
+    Timeout,
+    /// Unexpected message
+    Protocol(String),
+    /// Error of the remote component
+    Server(String),
+}
+impl fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Wire(e) => write!(f, "Remote connection error: {}", e),
+            Self::Timeout => write!(f, "Remote connection timed out"),
+            Self::Protocol(msg) => write!(f, "Remote protocol error: {}", msg),
+            Self::Server(msg) => write!(f, "Remote component error: {}", msg),
+        }
+    }
+}
+impl Error for RemoteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Wire(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl From<WireError> for RemoteError {
+    fn from(e: WireError) -> Self {
+        match e {
+            WireError::Io(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                Self::Timeout
+            }
+            e => Self::Wire(e),
+        }
+    }
+}
+impl From<io::Error> for RemoteError {
+    fn from(e: io::Error) -> Self {
+        WireError::Io(e).into()
+    }
+}
+
+/// Socket stream
+pub trait Stream: Read + Write + Send + Sized + 'static {
+    /// Returns a new handle to the stream
+    fn try_clone_stream(&self) -> io::Result<Self>;
+    /// Sets the read and write timeouts
+    fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+}
+impl Stream for TcpStream {
+    fn try_clone_stream(&self) -> io::Result<Self> {
+        self.try_clone()
+    }
+    fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.set_nodelay(true)?;
+        self.set_read_timeout(timeout)?;
+        self.set_write_timeout(timeout)
+    }
+}
+#[cfg(unix)]
+impl Stream for UnixStream {
+    fn try_clone_stream(&self) -> io::Result<Self> {
+        self.try_clone()
+    }
+    fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.set_read_timeout(timeout)?;
+        self.set_write_timeout(timeout)
+    }
+}
+
+// Commands
+pub(crate) const CLOSE: u8 = 0;
+pub(crate) const INPUTS: u8 = 1;
+pub(crate) const STEP: u8 = 2;
+pub(crate) const OUTPUTS: u8 = 3;
+pub(crate) const INIT: u8 = 4;
+pub(crate) const RESET: u8 = 5;
+pub(crate) const FINALIZE: u8 = 6;
+// Status
+pub(crate) const OK: u8 = 0;
+pub(crate) const ERR: u8 = 1;
+
+// Request of a client
+pub(crate) enum Request {
+    Close,
+    Inputs(Option<Data>),
+    Step,
+    Outputs,
+    Init,
+    Reset,
+    Finalize,
+}
+impl Request {
+    pub(crate) fn command(&self) -> u8 {
+        match self {
+            Self::Close => CLOSE,
+            Self::Inputs(_) => INPUTS,
+            Self::Step => STEP,
+            Self::Outputs => OUTPUTS,
+            Self::Init => INIT,
+            Self::Reset => RESET,
+            Self::Finalize => FINALIZE,
+        }
+    }
+    // Returns the request of the command, but for the inputs
+    pub(crate) fn from_command(command: u8) -> Result<Self, RemoteError> {
+        Ok(match command {
+            CLOSE => Self::Close,
+            STEP => Self::Step,
+            OUTPUTS => Self::Outputs,
+            INIT => Self::Init,
+            RESET => Self::Reset,
+            FINALIZE => Self::Finalize,
+            command => {
+                return Err(RemoteError::Protocol(format!(
+                    "unknown command {}",
+                    command
+                )))
+            }
+        })
+    }
+}
+
+// Response of a server
+pub(crate) enum Response {
+    Done(Result<(), DOSIOSError>),
+    Outputs(Option<Data>),
+}
+
+// Splits an error of the remote component into its variant, the name of its IO and its message
+pub(crate) fn error_parts(e: &DOSIOSError) -> (u8, &'static str, String) {
+    let variant = match e {
+        DOSIOSError::Inputs(_) => INPUTS,
+        DOSIOSError::Outputs(_) => OUTPUTS,
+        DOSIOSError::Step(_) => STEP,
+    };
+    let io = e
+        .context()
+        .and_then(|context| context.io.as_ref())
+        .map(|io| io.io_kind().name())
+        .unwrap_or_default();
+    let message = e
+        .chain()
+        .skip(1)
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join(": ");
+    (variant, io, message)
+}
+// Rebuilds the error of the remote component
+pub(crate) fn error_from_parts(variant: u8, io: &str, message: String) -> DOSIOSError {
+    let variant: fn(BoxError) -> DOSIOSError = match variant {
+        INPUTS => DOSIOSError::Inputs,
+        OUTPUTS => DOSIOSError::Outputs,
+        _ => DOSIOSError::Step,
+    };
+    let e = variant(Box::new(RemoteError::Server(message)));
+    match io.parse::<IOKind>() {
+        Ok(kind) => e.with_io(&kind.io::<()>(None)),
+        Err(_) => e,
+    }
+}
+
+// Transport of the requests and responses between a client and a server
+pub(crate) trait Transport: Send {
+    fn send_request(&mut self, request: Request) -> Result<(), RemoteError>;
+    // Returns [`Request::Close`] if the client has left
+    fn receive_request(&mut self) -> Result<Request, RemoteError>;
+    fn send_response(&mut self, response: Response) -> Result<(), RemoteError>;
+    fn receive_response(&mut self) -> Result<Response, RemoteError>;
+}
+
+// Encoder and decoder of a socket, the writes are buffered and flushed once per message
+struct Connection {
+    encoder: Encoder<Box<dyn Write + Send>>,
+    decoder: Decoder<Box<dyn Read + Send>>,
+}
+impl Connection {
+    // Exchanges the stream headers
+    fn new<S: Stream>(stream: S, timeout: Option<Duration>) -> Result<Self, RemoteError> {
+        stream.set_timeout(timeout)?;
+        let reader = stream.try_clone_stream()?;
+        let mut encoder = Encoder::new(Box::new(BufWriter::new(stream)) as Box<dyn Write + Send>)?;
+        encoder.flush()?;
+        let decoder = Decoder::new(Box::new(reader) as Box<dyn Read + Send>)?;
+        Ok(Self { encoder, decoder })
+    }
+    fn write_u8(&mut self, value: u8) -> Result<(), RemoteError> {
+        self.encoder.get_mut().write_all(&[value])?;
+        Ok(())
+    }
+    fn read_u8(&mut self) -> Result<u8, RemoteError> {
+        let mut value = [0u8];
+        self.decoder.get_mut().read_exact(&mut value)?;
+        Ok(value[0])
+    }
+    fn write_str(&mut self, value: &str) -> Result<(), RemoteError> {
+        let writer = self.encoder.get_mut();
+        writer.write_all(&(value.len() as u32).to_le_bytes())?;
+        writer.write_all(value.as_bytes())?;
+        Ok(())
+    }
+    fn read_str(&mut self) -> Result<String, RemoteError> {
+        let reader = self.decoder.get_mut();
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len)?;
+        let mut value = vec![0u8; u32::from_le_bytes(len) as usize];
+        reader.read_exact(&mut value)?;
+        String::from_utf8(value).map_err(|e| RemoteError::Protocol(e.to_string()))
+    }
+    fn write_frame(&mut self, data: Option<&[IO<Vec<f64>>]>) -> Result<(), RemoteError> {
+        match data {
+            Some(data) => {
+                self.write_u8(1)?;
+                self.encoder.encode(data)?;
+            }
+            None => self.write_u8(0)?,
+        }
+        Ok(())
+    }
+    fn read_frame(&mut self) -> Result<Option<Data>, RemoteError> {
+        if self.read_u8()? == 0 {
+            return Ok(None);
+        }
+        self.decoder
+            .decode()?
+            .map(Some)
+            .ok_or_else(|| RemoteError::Protocol("the connection is closed".into()))
+    }
+    fn flush(&mut self) -> Result<(), RemoteError> {
+        self.encoder.flush()?;
+        Ok(())
+    }
+}
+impl Transport for Connection {
+    fn send_request(&mut self, request: Request) -> Result<(), RemoteError> {
+        self.write_u8(request.command())?;
+        if let Request::Inputs(data) = request {
+            self.write_frame(data.as_deref())?;
+        }
+        self.flush()
+    }
+    fn receive_request(&mut self) -> Result<Request, RemoteError> {
+        match self.read_u8() {
+            Ok(INPUTS) => Ok(Request::Inputs(self.read_frame()?)),
+            Ok(command) => Request::from_command(command),
+            // the client has left without closing
+            Err(RemoteError::Wire(WireError::Io(e)))
+                if e.kind() == io::ErrorKind::UnexpectedEof =>
+            {
+                Ok(Request::Close)
+            }
+            Err(e) => Err(e),
+        }
+    }
+    fn send_response(&mut self, response: Response) -> Result<(), RemoteError> {
+        match response {
+            Response::Done(Ok(())) => self.write_u8(OK)?,
+            Response::Done(Err(e)) => {
+                let (variant, io, message) = error_parts(&e);
+                self.write_u8(ERR)?;
+                self.write_u8(variant)?;
+                self.write_str(io)?;
+                self.write_str(&message)?;
+            }
+            Response::Outputs(data) => {
+                self.write_u8(OUTPUTS)?;
+                self.write_frame(data.as_deref())?;
+            }
+        }
+        self.flush()
+    }
+    fn receive_response(&mut self) -> Result<Response, RemoteError> {
+        match self.read_u8()? {
+            OK => Ok(Response::Done(Ok(()))),
+            ERR => {
+                let variant = self.read_u8()?;
+                let io = self.read_str()?;
+                let message = self.read_str()?;
+                Ok(Response::Done(Err(error_from_parts(variant, &io, message))))
+            }
+            OUTPUTS => Ok(Response::Outputs(self.read_frame()?)),
+            status => Err(RemoteError::Protocol(format!("unknown status {}", status))),
+        }
+    }
+}
+
+fn tags_frame(tags: Vec<IO<()>>) -> Data {
+    tags.into_iter().map(|tag| tag.io_kind().io(None)).collect()
+}
+
+/// Server of a remote component
+pub struct Server<D> {
+    pub(crate) dos: D,
+    pub(crate) timeout: Option<Duration>,
+}
+impl<D> Server<D>
+where
+    D: Dos<Input = Vec<f64>, Output = Vec<f64>> + IOTags + Iterator,
+{
+    /// Creates a new server of the component
+    pub fn new(dos: D) -> Self {
+        Self { dos, timeout: None }
+    }
+    /// Sets the read and write timeouts of the connections
+    ///
+    /// The read timeout is also the largest time between 2 calls of the client
+    pub fn timeout(self, timeout: Duration) -> Self {
+        Self {
+            timeout: Some(timeout),
+            ..self
+        }
+    }
+    /// Returns the component
+    pub fn into_inner(self) -> D {
+        self.dos
+    }
+    /// Serves the component on `stream` until the client closes the connection
+    pub fn serve<S: Stream>(&mut self, stream: S) -> Result<(), RemoteError> {
+        let mut conn = Connection::new(stream, self.timeout)?;
+        conn.write_frame(Some(&tags_frame(self.dos.inputs_tags())))?;
+        conn.write_frame(Some(&tags_frame(self.dos.outputs_tags())))?;
+        conn.flush()?;
+        self.run(&mut conn)
+    }
+    /// Accepts a connection on `listener` and serves the component
+    pub fn serve_tcp(&mut self, listener: &TcpListener) -> Result<(), RemoteError> {
+        let (stream, _) = listener.accept()?;
+        self.serve(stream)
+    }
+    /// Accepts a connection on `listener` and serves the component
+    #[cfg(unix)]
+    pub fn serve_unix(&mut self, listener: &UnixListener) -> Result<(), RemoteError> {
+        let (stream, _) = listener.accept()?;
+        self.serve(stream)
+    }
+    // Answers the requests until the client closes the connection
+    pub(crate) fn run<T: Transport>(&mut self, transport: &mut T) -> Result<(), RemoteError> {
+        loop {
+            let response = match transport.receive_request()? {
+                Request::Close => return Ok(()),
+                Request::Inputs(data) => Response::Done(self.dos.inputs(data).map(|_| ())),
+                Request::Step => Response::Done(self.dos.step().map(|_| ())),
+                Request::Outputs => Response::Outputs(self.dos.outputs()),
+                Request::Init => Response::Done(self.dos.init().map(|_| ())),
+                Request::Reset => Response::Done(self.dos.reset().map(|_| ())),
+                Request::Finalize => Response::Done(self.dos.finalize().map(|_| ())),
+            };
+            transport.send_response(response)?;
+        }
+    }
+}
+
+/// Proxy of a remote component
+///
+/// As [`outputs`](Dos::outputs) cannot fail, a failed call returns `None` and the error is given by [`take_error`](Client::take_error)
+pub struct Client {
+    transport: Box<dyn Transport>,
+    inputs_tags: Vec<IO<()>>,
+    outputs_tags: Vec<IO<()>>,
+    error: Option<DOSIOSError>,
+}
+impl Client {
+    /// Connects to the server at the other end of `stream`
+    ///
+    /// The connection fails if the server [`IO`] enum does not have the same fingerprint
+    pub fn new<S: Stream>(stream: S, timeout: Option<Duration>) -> Result<Self, RemoteError> {
+        let mut conn = Connection::new(stream, timeout)?;
+        let mut tags = || -> Result<Vec<IO<()>>, RemoteError> {
+            Ok(conn
+                .read_frame()?
+                .unwrap_or_default()
+                .into_iter()
+                .map(|io| io.io_kind().io(None))
+                .collect())
+        };
+        let inputs_tags = tags()?;
+        let outputs_tags = tags()?;
+        Ok(Self::with_transport(
+            Box::new(conn),
+            inputs_tags,
+            outputs_tags,
+        ))
+    }
+    pub(crate) fn with_transport(
+        transport: Box<dyn Transport>,
+        inputs_tags: Vec<IO<()>>,
+        outputs_tags: Vec<IO<()>>,
+    ) -> Self {
+        Self {
+            transport,
+            inputs_tags,
+            outputs_tags,
+            error: None,
+        }
+    }
+    /// Connects to the server at `addr`
+    pub fn connect_tcp<A: ToSocketAddrs>(
+        addr: A,
+        timeout: Option<Duration>,
+    ) -> Result<Self, RemoteError> {
+        let stream = match timeout {
+            Some(timeout) => {
+                let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+                    RemoteError::Protocol("the server address is not valid".into())
+                })?;
+                TcpStream::connect_timeout(&addr, timeout)?
+            }
+            None => TcpStream::connect(addr)?,
+        };
+        Self::new(stream, timeout)
+    }
+    /// Connects to the server at `path`
+    #[cfg(unix)]
+    pub fn connect_unix<P: AsRef<Path>>(
+        path: P,
+        timeout: Option<Duration>,
+    ) -> Result<Self, RemoteError> {
+        Self::new(UnixStream::connect(path)?, timeout)
+    }
+    /// Takes the error of the last failed call to [`outputs`](Dos::outputs)
+    pub fn take_error(&mut self) -> Option<DOSIOSError> {
+        self.error.take()
+    }
+    // Sends the request and returns the response
+    fn call(&mut self, request: Request) -> Result<Response, RemoteError> {
+        self.transport.send_request(request)?;
+        self.transport.receive_response()
+    }
+    // Sends the request and returns the result
+    fn done(
+        &mut self,
+        request: Request,
+        variant: fn(BoxError) -> DOSIOSError,
+    ) -> Result<&mut Self, DOSIOSError> {
+        match self.call(request) {
+            Ok(Response::Done(result)) => result?,
+            Ok(Response::Outputs(_)) => {
+                return Err(variant(Box::new(RemoteError::Protocol(
+                    "unexpected outputs".into(),
+                ))))
+            }
+            Err(e) => return Err(variant(Box::new(e))),
+        }
+        Ok(self)
+    }
+}
+impl Dos for Client {
+    type Input = Vec<f64>;
+    type Output = Vec<f64>;
+    fn outputs(&mut self) -> Option<Vec<IO<Self::Output>>> {
+        let error = match self.call(Request::Outputs) {
+            Ok(Response::Outputs(data)) => return data,
+            Ok(Response::Done(Err(e))) => e,
+            Ok(Response::Done(Ok(()))) => {
+                DOSIOSError::Outputs(Box::new(RemoteError::Protocol("missing outputs".into())))
+            }
+            Err(e) => DOSIOSError::Outputs(Box::new(e)),
+        };
+        self.error = Some(error);
+        None
+    }
+    fn inputs(&mut self, data: Option<Vec<IO<Self::Input>>>) -> Result<&mut Self, DOSIOSError> {
+        self.done(Request::Inputs(data), DOSIOSError::Inputs)
+    }
+    fn step(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.done(Request::Step, DOSIOSError::Step)
+    }
+    fn init(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.done(Request::Init, DOSIOSError::Step)
+    }
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.done(Request::Reset, DOSIOSError::Step)
+    }
+    fn finalize(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.done(Request::Finalize, DOSIOSError::Step)
+    }
+}
+impl Iterator for Client {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        self.step().ok().map(|_| ())
+    }
+}
+impl IOTags for Client {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        self.outputs_tags.clone()
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        self.inputs_tags.clone()
+    }
+}
+impl Drop for Client {
+    fn drop(&mut self) {
+        let _ = self.transport.send_request(Request::Close);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{blocks::Gain, ios};
+    use std::thread;
+
+    fn gain() -> Gain {
+        Gain::new(ios!(Pssn), ios!(SensorData), vec![2., -1.])
+    }
+    // Runs a few steps of the remote gain and compares with the local gain
+    fn run(mut client: Client) {
+        let mut local = gain();
+        assert_eq!(client.inputs_tags(), vec![ios!(Pssn)]);
+        assert_eq!(client.outputs_tags(), vec![ios!(SensorData)]);
+        for i in 0..5 {
+            let u = vec![ios!(Pssn(vec![i as f64, 1.]))];
+            let y = client.in_step_out(Some(u.clone())).unwrap().unwrap();
+            let y_local = local.in_step_out(Some(u)).unwrap().unwrap();
+            assert_eq!(y[0].as_ref(), y_local[0].as_ref());
+        }
+        let error = client
+            .in_step_out(Some(vec![ios!(SensorData(vec![1.]))]))
+            .unwrap_err();
+        assert!(matches!(error, DOSIOSError::Inputs(_)));
+        assert_eq!(
+            error.context().and_then(|context| context.io.clone()),
+            Some(ios!(Pssn))
+        );
+    }
+
+    #[test]
+    fn tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || Server::new(gain()).serve_tcp(&listener));
+        run(Client::connect_tcp(addr, Some(Duration::from_secs(5))).unwrap());
+        server.join().unwrap().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix() {
+        let path = std::env::temp_dir().join(format!("dosio-remote-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = thread::spawn(move || Server::new(gain()).serve_unix(&listener));
+        run(Client::connect_unix(&path, Some(Duration::from_secs(5))).unwrap());
+        server.join().unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn timeout() {
+        // the connection is accepted by the system but the server never answers
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        assert!(matches!(
+            Client::connect_tcp(addr, Some(Duration::from_millis(100))),
+            Err(RemoteError::Timeout)
+        ));
+    }
+}
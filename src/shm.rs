@@ -0,0 +1,681 @@
+//! Shared-memory transport
+//!
+//! Components in processes on the same host can exchange their inputs and outputs through a memory-mapped file
+//! instead of a socket, with [`Server::serve_shm`] and [`Client::connect_shm`].
+//!
+//! The layout of the file is fixed by the server from the wire [`id`]s of the inputs and outputs of the component
+//! and from the length of their data:
+//!  - a header with the magic bytes `DOSM`, the layout [`VERSION`], the [`FINGERPRINT`] hash of the [`IO`] enum,
+//!    the capacity of the ring buffers and the number of inputs and outputs,
+//!  - the id and the length of each input and output,
+//!  - the requests ring buffer and the responses ring buffer.
+//!
+//! A ring buffer is an array of fixed-size slots and 2 sequence counters, the number of slots written by the producer
+//! and the number of slots read by the consumer.
+//! A slot is written only if the producer is less than the capacity ahead of the consumer and is read only if the consumer is
+//! behind the producer, each side spinning on the counter of the other side, so the transport does not need any lock or system call.
+//!
+//! ```no_run
+//! use dosio::{blocks::Gain, ios, remote::{Client, Server}, Dos};
+//! use std::{thread, time::Duration};
+//!
+//! thread::spawn(|| {
+//!     let mut server = Server::new(Gain::new(ios!(Pssn), ios!(SensorData), 2.));
+//!     server.serve_shm(
+//!         "/dev/shm/gain",
+//!         vec![(ios!(Pssn), 7)],
+//!         vec![(ios!(SensorData), 7)],
+//!     )
+//! });
+//! let mut gain = Client::connect_shm("/dev/shm/gain", Some(Duration::from_secs(1))).unwrap();
+//! let y = gain.in_step_out(Some(vec![ios!(Pssn(vec![1.; 7]))])).unwrap();
+//! ```
+
+use crate::{
+    io::{IOKind, FINGERPRINT},
+    remote::{
+        error_from_parts, error_parts, Client, RemoteError, Request, Response, Server, Transport,
+        ERR, INPUTS, OK, OUTPUTS,
+    },
+    wire::{id, WireError},
+    Dos, IOTags, IO,
+};
+use memmap2::MmapMut;
+use std::{
+    fs::{self, OpenOptions},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Magic bytes at the start of the shared memory
+pub const MAGIC: [u8; 4] = *b"DOSM";
+/// Layout version
+pub const VERSION: u16 = 1;
+/// Number of slots of the ring buffers
+pub const CAPACITY: usize = 4;
+/// Largest size of the error message of the remote component, longer messages are truncated
+pub const ERROR_SIZE: usize = 1024;
+
+const CACHE_LINE: usize = 64;
+// Header offsets
+const FINGERPRINT_OFFSET: usize = 8;
+const CAPACITY_OFFSET: usize = 16;
+const N_INPUTS_OFFSET: usize = 24;
+const N_OUTPUTS_OFFSET: usize = 32;
+const CLOSED_OFFSET: usize = 40;
+const TAGS_OFFSET: usize = CACHE_LINE;
+// Slot offsets
+const DATA_FLAG_OFFSET: usize = 8;
+const FLAGS_OFFSET: usize = 16;
+// Closed flags
+const SERVER: u64 = 1;
+const CLIENT: u64 = 2;
+// Number of iterations spinning on a counter before yielding the thread
+const SPINS: usize = 1000;
+
+// Spinning only helps if the peer runs on another core
+fn spins() -> usize {
+    match thread::available_parallelism() {
+        Ok(n) if n.get() > 1 => SPINS,
+        _ => 0,
+    }
+}
+
+fn align(size: usize, to: usize) -> usize {
+    size.div_ceil(to) * to
+}
+// The sizes of the layout are read from the header of the shared memory, so their arithmetic is checked
+fn checked_align(size: usize, to: usize) -> Option<usize> {
+    size.div_ceil(to).checked_mul(to)
+}
+
+// Size of a slot with the data of `ios` or at least `min_payload` bytes
+fn slot_size(ios: &[(IOKind, usize)], min_payload: usize) -> Option<usize> {
+    let n = ios
+        .iter()
+        .try_fold(0usize, |sum, (_, n)| sum.checked_add(*n))?;
+    let payload = checked_align(ios.len(), 8)?
+        .checked_add(n.checked_mul(8)?)?
+        .max(min_payload);
+    checked_align(FLAGS_OFFSET.checked_add(payload)?, CACHE_LINE)
+}
+
+// Ring buffer: the producer counter, the consumer counter on the next cache line and the slots
+#[derive(Debug, Clone, Copy)]
+struct Ring {
+    offset: usize,
+    slot_size: usize,
+    capacity: usize,
+}
+impl Ring {
+    fn write_seq(&self) -> usize {
+        self.offset
+    }
+    fn read_seq(&self) -> usize {
+        self.offset + CACHE_LINE
+    }
+    fn slot(&self, seq: u64) -> usize {
+        self.offset + 2 * CACHE_LINE + (seq as usize % self.capacity) * self.slot_size
+    }
+    fn end(&self) -> usize {
+        self.offset + 2 * CACHE_LINE + self.capacity * self.slot_size
+    }
+    fn checked_end(&self) -> Option<usize> {
+        self.offset
+            .checked_add(2 * CACHE_LINE)?
+            .checked_add(self.capacity.checked_mul(self.slot_size)?)
+    }
+}
+
+// Layout of the shared memory
+#[derive(Debug, Clone)]
+struct Layout {
+    inputs: Vec<(IOKind, usize)>,
+    outputs: Vec<(IOKind, usize)>,
+    requests: Ring,
+    responses: Ring,
+}
+impl Layout {
+    // Returns `None` if the capacity is 0 or if the layout does not fit in the address space
+    fn new(
+        inputs: Vec<(IOKind, usize)>,
+        outputs: Vec<(IOKind, usize)>,
+        capacity: usize,
+    ) -> Option<Self> {
+        if capacity == 0 {
+            return None;
+        }
+        let requests = Ring {
+            offset: checked_align(TAGS_OFFSET + 8 * (inputs.len() + outputs.len()), CACHE_LINE)?,
+            slot_size: slot_size(&inputs, 0)?,
+            capacity,
+        };
+        let responses = Ring {
+            offset: requests.checked_end()?,
+            slot_size: slot_size(&outputs, 4 + ERROR_SIZE)?,
+            capacity,
+        };
+        responses.checked_end()?;
+        Some(Self {
+            inputs,
+            outputs,
+            requests,
+            responses,
+        })
+    }
+    fn len(&self) -> usize {
+        self.responses.end()
+    }
+}
+
+fn write_u64(slot: &mut [u8], offset: usize, value: u64) {
+    slot[offset..offset + 8].copy_from_slice(&value.to_ne_bytes());
+}
+fn read_u64(slot: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&slot[offset..offset + 8]);
+    u64::from_ne_bytes(bytes)
+}
+
+// Writes `data` into `slot` according to `layout`
+fn encode(
+    slot: &mut [u8],
+    layout: &[(IOKind, usize)],
+    data: Option<&[IO<Vec<f64>>]>,
+) -> Result<(), RemoteError> {
+    let data = match data {
+        Some(data) => data,
+        None => {
+            write_u64(slot, DATA_FLAG_OFFSET, 0);
+            return Ok(());
+        }
+    };
+    write_u64(slot, DATA_FLAG_OFFSET, 1);
+    let values_offset = FLAGS_OFFSET + align(layout.len(), 8);
+    slot[FLAGS_OFFSET..FLAGS_OFFSET + layout.len()]
+        .iter_mut()
+        .for_each(|flag| *flag = 0);
+    for io in data {
+        let kind = io.io_kind();
+        let (i, offset, n) = layout
+            .iter()
+            .scan(values_offset, |offset, &(layout_kind, n)| {
+                let item = (layout_kind, *offset, n);
+                *offset += 8 * n;
+                Some(item)
+            })
+            .enumerate()
+            .find(|(_, (layout_kind, _, _))| *layout_kind == kind)
+            .map(|(i, (_, offset, n))| (i, offset, n))
+            .ok_or_else(|| {
+                RemoteError::Protocol(format!("{} is not in the shared memory layout", kind))
+            })?;
+        match io.as_ref() {
+            Some(values) if values.len() != n => {
+                return Err(RemoteError::Protocol(format!(
+                    "{} has {} elements instead of {}",
+                    kind,
+                    values.len(),
+                    n
+                )))
+            }
+            Some(values) => {
+                slot[FLAGS_OFFSET + i] = 2;
+                slot[offset..offset + 8 * n]
+                    .chunks_exact_mut(8)
+                    .zip(values)
+                    .for_each(|(bytes, x)| bytes.copy_from_slice(&x.to_ne_bytes()));
+            }
+            None => slot[FLAGS_OFFSET + i] = 1,
+        }
+    }
+    Ok(())
+}
+// Reads the data in `slot` according to `layout`
+fn decode(slot: &[u8], layout: &[(IOKind, usize)]) -> Option<Vec<IO<Vec<f64>>>> {
+    if read_u64(slot, DATA_FLAG_OFFSET) == 0 {
+        return None;
+    }
+    let mut offset = FLAGS_OFFSET + align(layout.len(), 8);
+    let mut data = Vec::with_capacity(layout.len());
+    for (i, &(kind, n)) in layout.iter().enumerate() {
+        match slot[FLAGS_OFFSET + i] {
+            1 => data.push(kind.io(None)),
+            2 => data.push(
+                kind.io(Some(
+                    slot[offset..offset + 8 * n]
+                        .chunks_exact(8)
+                        .map(|x| {
+                            f64::from_ne_bytes([x[0], x[1], x[2], x[3], x[4], x[5], x[6], x[7]])
+                        })
+                        .collect(),
+                )),
+            ),
+            _ => (),
+        }
+        offset += 8 * n;
+    }
+    Some(data)
+}
+
+// Shared-memory transport
+struct SharedMemory {
+    mmap: MmapMut,
+    layout: Layout,
+    timeout: Option<Duration>,
+    // SERVER or CLIENT
+    side: u64,
+    // Number of iterations spinning on a counter before yielding the thread
+    spins: usize,
+    // The file is removed by the server
+    path: Option<PathBuf>,
+}
+impl SharedMemory {
+    fn atomic(&self, offset: usize) -> &AtomicU64 {
+        assert!(offset.is_multiple_of(8) && offset + 8 <= self.mmap.len());
+        // Safety: the offset is aligned and within the mapping, the mapping is page aligned,
+        // and the counters are only accessed atomically by both processes
+        unsafe { &*(self.mmap.as_ptr().add(offset) as *const AtomicU64) }
+    }
+    fn is_peer_closed(&self) -> bool {
+        self.atomic(CLOSED_OFFSET).load(Ordering::Acquire) & !self.side != 0
+    }
+    // Spins until `ready`, returns `false` if the peer has closed the connection
+    fn wait<F: Fn(&Self) -> bool>(&self, ready: F) -> Result<bool, RemoteError> {
+        let start = Instant::now();
+        let mut spins = 0;
+        loop {
+            if ready(self) {
+                return Ok(true);
+            }
+            if self.is_peer_closed() {
+                return Ok(false);
+            }
+            if spins < self.spins {
+                spins += 1;
+                std::hint::spin_loop();
+            } else {
+                if self
+                    .timeout
+                    .is_some_and(|timeout| start.elapsed() > timeout)
+                {
+                    return Err(RemoteError::Timeout);
+                }
+                thread::yield_now();
+            }
+        }
+    }
+    // Writes the next slot of the ring
+    fn push<F>(&mut self, ring: Ring, write: F) -> Result<(), RemoteError>
+    where
+        F: FnOnce(&mut [u8], &Layout) -> Result<(), RemoteError>,
+    {
+        let seq = self.atomic(ring.write_seq()).load(Ordering::Relaxed);
+        let capacity = ring.capacity as u64;
+        if !self
+            .wait(|this| seq - this.atomic(ring.read_seq()).load(Ordering::Acquire) < capacity)?
+        {
+            return Err(RemoteError::Protocol("the connection is closed".into()));
+        }
+        let offset = ring.slot(seq);
+        write(
+            &mut self.mmap[offset..offset + ring.slot_size],
+            &self.layout,
+        )?;
+        self.atomic(ring.write_seq())
+            .store(seq + 1, Ordering::Release);
+        Ok(())
+    }
+    // Reads the next slot of the ring, returns `None` if the peer has closed the connection
+    fn pop<T, F>(&mut self, ring: Ring, read: F) -> Result<Option<T>, RemoteError>
+    where
+        F: FnOnce(&[u8], &Layout) -> Result<T, RemoteError>,
+    {
+        let seq = self.atomic(ring.read_seq()).load(Ordering::Relaxed);
+        if !self.wait(|this| this.atomic(ring.write_seq()).load(Ordering::Acquire) > seq)? {
+            return Ok(None);
+        }
+        let offset = ring.slot(seq);
+        let value = read(&self.mmap[offset..offset + ring.slot_size], &self.layout)?;
+        self.atomic(ring.read_seq())
+            .store(seq + 1, Ordering::Release);
+        Ok(Some(value))
+    }
+}
+impl Transport for SharedMemory {
+    fn send_request(&mut self, request: Request) -> Result<(), RemoteError> {
+        let ring = self.layout.requests;
+        self.push(ring, |slot, layout| {
+            write_u64(slot, 0, request.command() as u64);
+            match &request {
+                Request::Inputs(data) => encode(slot, &layout.inputs, data.as_deref()),
+                _ => Ok(()),
+            }
+        })
+    }
+    fn receive_request(&mut self) -> Result<Request, RemoteError> {
+        let ring = self.layout.requests;
+        let request = self.pop(ring, |slot, layout| match read_u64(slot, 0) as u8 {
+            INPUTS => Ok(Request::Inputs(decode(slot, &layout.inputs))),
+            command => Request::from_command(command),
+        })?;
+        Ok(request.unwrap_or(Request::Close))
+    }
+    fn send_response(&mut self, response: Response) -> Result<(), RemoteError> {
+        let ring = self.layout.responses;
+        self.push(ring, |slot, layout| match response {
+            Response::Done(Ok(())) => {
+                write_u64(slot, 0, OK as u64);
+                Ok(())
+            }
+            Response::Done(Err(e)) => {
+                let (variant, io, message) = error_parts(&e);
+                let text = format!("{}\n{}", io, message);
+                let mut n = text.len().min(ERROR_SIZE);
+                while !text.is_char_boundary(n) {
+                    n -= 1;
+                }
+                write_u64(slot, 0, ERR as u64);
+                write_u64(slot, DATA_FLAG_OFFSET, variant as u64);
+                slot[FLAGS_OFFSET..FLAGS_OFFSET + 4].copy_from_slice(&(n as u32).to_ne_bytes());
+                slot[FLAGS_OFFSET + 4..FLAGS_OFFSET + 4 + n].copy_from_slice(&text.as_bytes()[..n]);
+                Ok(())
+            }
+            Response::Outputs(data) => {
+                write_u64(slot, 0, OUTPUTS as u64);
+                encode(slot, &layout.outputs, data.as_deref())
+            }
+        })
+    }
+    fn receive_response(&mut self) -> Result<Response, RemoteError> {
+        let ring = self.layout.responses;
+        self.pop(ring, |slot, layout| match read_u64(slot, 0) as u8 {
+            OK => Ok(Response::Done(Ok(()))),
+            ERR => {
+                let variant = read_u64(slot, DATA_FLAG_OFFSET) as u8;
+                let mut n = [0u8; 4];
+                n.copy_from_slice(&slot[FLAGS_OFFSET..FLAGS_OFFSET + 4]);
+                let n = (u32::from_ne_bytes(n) as usize).min(ERROR_SIZE);
+                let text = String::from_utf8_lossy(&slot[FLAGS_OFFSET + 4..FLAGS_OFFSET + 4 + n]);
+                let (io, message) = text.split_once('\n').unwrap_or(("", &text));
+                Ok(Response::Done(Err(error_from_parts(
+                    variant,
+                    io,
+                    message.to_string(),
+                ))))
+            }
+            OUTPUTS => Ok(Response::Outputs(decode(slot, &layout.outputs))),
+            status => Err(RemoteError::Protocol(format!("unknown status {}", status))),
+        })?
+        .ok_or_else(|| RemoteError::Protocol("the connection is closed".into()))
+    }
+}
+impl Drop for SharedMemory {
+    fn drop(&mut self) {
+        self.atomic(CLOSED_OFFSET)
+            .fetch_or(self.side, Ordering::AcqRel);
+        if let Some(path) = &self.path {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+// Pairs the tags with their sizes
+fn sizes(
+    tags: Vec<IO<()>>,
+    sizes: &[(IO<()>, usize)],
+) -> Result<Vec<(IOKind, usize)>, RemoteError> {
+    tags.into_iter()
+        .map(|tag| {
+            sizes
+                .iter()
+                .find(|(io, _)| io.io_kind() == tag.io_kind())
+                .map(|(_, n)| (tag.io_kind(), *n))
+                .ok_or_else(|| {
+                    RemoteError::Protocol(format!("the size of {} is missing", tag.io_kind()))
+                })
+        })
+        .collect()
+}
+
+impl<D> Server<D>
+where
+    D: Dos<Input = Vec<f64>, Output = Vec<f64>> + IOTags + Iterator,
+{
+    /// Serves the component through the shared memory at `path` until the client closes the connection
+    ///
+    /// The memory-mapped file is created, with the size of each input and output of the component given by `input_sizes`
+    /// and `output_sizes`, and it is removed when the client closes the connection.
+    /// On Linux, `path` should be in `/dev/shm` for the file never to be written to disk.
+    pub fn serve_shm<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        input_sizes: Vec<(IO<()>, usize)>,
+        output_sizes: Vec<(IO<()>, usize)>,
+    ) -> Result<(), RemoteError> {
+        let layout = Layout::new(
+            sizes(self.dos.inputs_tags(), &input_sizes)?,
+            sizes(self.dos.outputs_tags(), &output_sizes)?,
+            CAPACITY,
+        )
+        .ok_or_else(|| RemoteError::Protocol("the shared memory is too large".into()))?;
+        let path = path.as_ref();
+        let _ = fs::remove_file(path);
+        // the file is renamed once the header is written, so the client never maps a partial header
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&tmp)?;
+        file.set_len(layout.len() as u64)?;
+        // Safety: the file is only shared with the client that follows the same layout
+        let mut mmap = unsafe { MmapMut::map_mut(&file)? };
+        mmap[..4].copy_from_slice(&MAGIC);
+        mmap[4..6].copy_from_slice(&VERSION.to_ne_bytes());
+        write_u64(&mut mmap, FINGERPRINT_OFFSET, FINGERPRINT.hash);
+        write_u64(&mut mmap, CAPACITY_OFFSET, CAPACITY as u64);
+        write_u64(&mut mmap, N_INPUTS_OFFSET, layout.inputs.len() as u64);
+        write_u64(&mut mmap, N_OUTPUTS_OFFSET, layout.outputs.len() as u64);
+        for (i, (kind, n)) in layout.inputs.iter().chain(&layout.outputs).enumerate() {
+            let offset = TAGS_OFFSET + 8 * i;
+            mmap[offset..offset + 4].copy_from_slice(&id(*kind).to_ne_bytes());
+            mmap[offset + 4..offset + 8].copy_from_slice(&(*n as u32).to_ne_bytes());
+        }
+        mmap.flush()?;
+        fs::rename(&tmp, path)?;
+        let mut shm = SharedMemory {
+            mmap,
+            layout,
+            timeout: self.timeout,
+            side: SERVER,
+            spins: spins(),
+            path: Some(path.to_path_buf()),
+        };
+        self.run(&mut shm)
+    }
+}
+
+impl Client {
+    /// Connects to the server through the shared memory at `path`
+    ///
+    /// Waits for the server to create the memory-mapped file for at most `timeout`.
+    /// The connection fails if the server [`IO`] enum does not have the same fingerprint
+    pub fn connect_shm<P: AsRef<Path>>(
+        path: P,
+        timeout: Option<Duration>,
+    ) -> Result<Self, RemoteError> {
+        let start = Instant::now();
+        let file = loop {
+            match OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(path.as_ref())
+            {
+                Ok(file) => break file,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    if timeout.is_some_and(|timeout| start.elapsed() > timeout) {
+                        return Err(RemoteError::Timeout);
+                    }
+                    thread::sleep(Duration::from_millis(1));
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+        // Safety: the file is only shared with the server that follows the same layout
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+        if mmap.len() < TAGS_OFFSET || mmap[..4] != MAGIC {
+            return Err(WireError::Magic.into());
+        }
+        let version = u16::from_ne_bytes([mmap[4], mmap[5]]);
+        if version != VERSION {
+            return Err(WireError::Version(version).into());
+        }
+        let fingerprint = read_u64(&mmap, FINGERPRINT_OFFSET);
+        if fingerprint != FINGERPRINT.hash {
+            return Err(WireError::Fingerprint {
+                expected: FINGERPRINT.hash,
+                actual: fingerprint,
+            }
+            .into());
+        }
+        let capacity = read_u64(&mmap, CAPACITY_OFFSET) as usize;
+        let n_inputs = read_u64(&mmap, N_INPUTS_OFFSET) as usize;
+        let n_outputs = read_u64(&mmap, N_OUTPUTS_OFFSET) as usize;
+        if capacity == 0 {
+            return Err(RemoteError::Protocol(
+                "the capacity of the shared memory is 0".into(),
+            ));
+        }
+        n_inputs
+            .checked_add(n_outputs)
+            .and_then(|n| n.checked_mul(8))
+            .and_then(|n| n.checked_add(TAGS_OFFSET))
+            .filter(|&n| n <= mmap.len())
+            .ok_or_else(|| {
+                RemoteError::Protocol("the shared memory is smaller than its header".into())
+            })?;
+        let mut tags = (0..n_inputs + n_outputs)
+            .map(|i| {
+                let offset = TAGS_OFFSET + 8 * i;
+                let tag = u32::from_ne_bytes([
+                    mmap[offset],
+                    mmap[offset + 1],
+                    mmap[offset + 2],
+                    mmap[offset + 3],
+                ]);
+                let n = u32::from_ne_bytes([
+                    mmap[offset + 4],
+                    mmap[offset + 5],
+                    mmap[offset + 6],
+                    mmap[offset + 7],
+                ]);
+                IOKind::ALL
+                    .iter()
+                    .find(|&&kind| id(kind) == tag)
+                    .map(|&kind| (kind, n as usize))
+                    .ok_or(WireError::Variant(tag))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let outputs = tags.split_off(n_inputs);
+        let layout = Layout::new(tags, outputs, capacity)
+            .filter(|layout| layout.len() <= mmap.len())
+            .ok_or_else(|| {
+                RemoteError::Protocol("the shared memory is smaller than its layout".into())
+            })?;
+        let inputs_tags = layout
+            .inputs
+            .iter()
+            .map(|(kind, _)| kind.io(None))
+            .collect();
+        let outputs_tags = layout
+            .outputs
+            .iter()
+            .map(|(kind, _)| kind.io(None))
+            .collect();
+        let shm = SharedMemory {
+            mmap,
+            layout,
+            timeout,
+            side: CLIENT,
+            spins: spins(),
+            path: None,
+        };
+        Ok(Self::with_transport(
+            Box::new(shm),
+            inputs_tags,
+            outputs_tags,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{blocks::Gain, ios, DOSIOSError};
+
+    #[test]
+    fn round_trip() {
+        let path = std::env::temp_dir().join(format!("dosio-shm-{}", std::process::id()));
+        let server_path = path.clone();
+        let server = thread::spawn(move || {
+            Server::new(Gain::new(ios!(Pssn), ios!(SensorData), 2.)).serve_shm(
+                server_path,
+                vec![(ios!(Pssn), 3)],
+                vec![(ios!(SensorData), 3)],
+            )
+        });
+        let mut client = Client::connect_shm(&path, Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(client.inputs_tags(), vec![ios!(Pssn)]);
+        for i in 0..2 * CAPACITY {
+            let y = client
+                .in_step_out(Some(vec![ios!(Pssn(vec![i as f64, 1., -1.]))]))
+                .unwrap()
+                .unwrap();
+            assert_eq!(y, vec![ios!(SensorData)]);
+            assert_eq!(y[0].as_ref(), Some(&vec![2. * i as f64, 2., -2.]));
+        }
+        let error = client
+            .in_step_out(Some(vec![ios!(Pssn(vec![1.]))]))
+            .unwrap_err();
+        assert!(matches!(error, DOSIOSError::Inputs(_)));
+        let error = client.in_step_out(Some(vec![])).unwrap_err();
+        assert!(matches!(error, DOSIOSError::Inputs(_)));
+        assert_eq!(
+            error.context().and_then(|context| context.io.clone()),
+            Some(ios!(Pssn))
+        );
+        drop(client);
+        server.join().unwrap().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn corrupted_header() {
+        let path = std::env::temp_dir().join(format!("dosio-shm-header-{}", std::process::id()));
+        let mut header = vec![0u8; 4096];
+        header[..4].copy_from_slice(&MAGIC);
+        header[4..6].copy_from_slice(&VERSION.to_ne_bytes());
+        write_u64(&mut header, FINGERPRINT_OFFSET, FINGERPRINT.hash);
+        // no slot
+        fs::write(&path, &header).unwrap();
+        assert!(matches!(
+            Client::connect_shm(&path, None),
+            Err(RemoteError::Protocol(_))
+        ));
+        // more inputs than the address space
+        write_u64(&mut header, CAPACITY_OFFSET, CAPACITY as u64);
+        write_u64(&mut header, N_INPUTS_OFFSET, u64::MAX);
+        write_u64(&mut header, N_OUTPUTS_OFFSET, 1);
+        fs::write(&path, &header).unwrap();
+        assert!(matches!(
+            Client::connect_shm(&path, None),
+            Err(RemoteError::Protocol(_))
+        ));
+        fs::remove_file(&path).unwrap();
+    }
+}
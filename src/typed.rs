@@ -0,0 +1,177 @@
+//! Typed inputs and outputs
+//!
+//! Each [`IO`] variant has a marker type in [`jar`](crate::io::jar) that implements [`UniqueIdentifier`],
+//! binding the variant to the type of its data, `Vec<f64>` for all the variants,
+//! and giving the size of the FEM inputs or outputs group of the variant.
+//! Components declare the signals they consume with [`Read`] and the signals they produce with [`Write`],
+//! so that connecting a producer to a consumer of another signal fails at compile time:
+//! ```
+//! use dosio::{io::jar::M1RBMcmd, typed::{connect, Data, Read, Write}};
+//!
+//! struct Controller(Vec<f64>);
+//! impl Write<M1RBMcmd> for Controller {
+//!     fn write(&mut self) -> Option<Data<M1RBMcmd>> {
+//!         Some(Data::new(self.0.clone()))
+//!     }
+//! }
+//! struct Mirror(Vec<f64>);
+//! impl Read<M1RBMcmd> for Mirror {
+//!     fn read(&mut self, data: Data<M1RBMcmd>) {
+//!         self.0 = data.into_inner();
+//!     }
+//! }
+//! let (mut controller, mut m1) = (Controller(vec![1f64; 42]), Mirror(vec![]));
+//! assert!(connect::<M1RBMcmd, _, _>(&mut controller, &mut m1));
+//! assert_eq!(m1.0.len(), 42);
+//! ```
+//! ```compile_fail
+//! # use dosio::{io::jar::{M1RBMcmd, M1HPLC}, typed::{connect, Data, Read, Write}};
+//! # struct Controller(Vec<f64>);
+//! # impl Write<M1RBMcmd> for Controller {
+//! #     fn write(&mut self) -> Option<Data<M1RBMcmd>> {
+//! #         Some(Data::new(self.0.clone()))
+//! #     }
+//! # }
+//! struct Mirror(Vec<f64>);
+//! impl Read<M1HPLC> for Mirror {
+//!     fn read(&mut self, data: Data<M1HPLC>) {
+//!         self.0 = data.into_inner();
+//!     }
+//! }
+//! let (mut controller, mut m1) = (Controller(vec![1f64; 42]), Mirror(vec![]));
+//! connect::<M1RBMcmd, _, _>(&mut controller, &mut m1);
+//! ```
+//! [`Data`] converts to and from [`IO`], the dynamic representation of the inputs and outputs.
+
+use crate::io::{IOError, IOKind, IO};
+use std::{convert::TryFrom, fmt, marker::PhantomData, ops::Deref};
+
+/// Binds an [`IO`] variant to the type of its data
+pub trait UniqueIdentifier {
+    /// Data type
+    type Data;
+    /// [`IO`] variant
+    const KIND: IOKind;
+    /// Size of the FEM inputs or outputs group, if the variant is a FEM input or output
+    ///
+    /// The size is checked when the data is created with [`Data::try_new`] or converted from an [`IO`]
+    const SIZE: Option<usize> = None;
+}
+
+/// Data of the [`IO`] variant `U`
+pub struct Data<U: UniqueIdentifier>(U::Data, PhantomData<U>);
+impl<U: UniqueIdentifier> Data<U> {
+    /// Creates a new data
+    pub fn new(data: U::Data) -> Self {
+        Self(data, PhantomData)
+    }
+    /// Returns the data
+    pub fn into_inner(self) -> U::Data {
+        self.0
+    }
+}
+impl<U: UniqueIdentifier<Data = Vec<f64>>> Data<U> {
+    /// Creates a new data, checking its length against [`UniqueIdentifier::SIZE`]
+    pub fn try_new(data: Vec<f64>) -> Result<Self, IOError<Vec<f64>>> {
+        match U::SIZE {
+            Some(size) if size != data.len() => Err(IOError::Size(size, U::KIND.io(Some(data)))),
+            _ => Ok(Self::new(data)),
+        }
+    }
+}
+impl<U: UniqueIdentifier> Deref for Data<U> {
+    type Target = U::Data;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<U: UniqueIdentifier> Clone for Data<U>
+where
+    U::Data: Clone,
+{
+    fn clone(&self) -> Self {
+        Self::new(self.0.clone())
+    }
+}
+impl<U: UniqueIdentifier> fmt::Debug for Data<U>
+where
+    U::Data: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple(U::KIND.name()).field(&self.0).finish()
+    }
+}
+impl<U: UniqueIdentifier> From<Data<U>> for IO<U::Data> {
+    fn from(data: Data<U>) -> Self {
+        U::KIND.io(Some(data.0))
+    }
+}
+impl<U: UniqueIdentifier<Data = Vec<f64>>> TryFrom<IO<Vec<f64>>> for Data<U> {
+    type Error = IOError<Vec<f64>>;
+    /// Converts an [`IO`] of the variant `U` with data into [`Data`]
+    ///
+    /// An [`IO`] of another variant is returned as a [`IOError::Mismatch`] error,
+    /// an [`IO`] without data as a [`IOError::Missing`] error and data of the wrong size as a [`IOError::Size`] error
+    fn try_from(io: IO<Vec<f64>>) -> Result<Self, Self::Error> {
+        match io.into_parts() {
+            (kind, data) if kind != U::KIND => Err(IOError::Mismatch(U::KIND, kind.io(data))),
+            (_, Some(data)) => Self::try_new(data),
+            (kind, None) => Err(IOError::Missing(kind.io(None))),
+        }
+    }
+}
+
+/// Consumer of the signal `U`
+pub trait Read<U: UniqueIdentifier> {
+    /// Reads the data of `U`
+    fn read(&mut self, data: Data<U>);
+}
+
+/// Producer of the signal `U`
+pub trait Write<U: UniqueIdentifier> {
+    /// Writes the data of `U`, if any
+    fn write(&mut self) -> Option<Data<U>>;
+}
+
+/// Moves the signal `U` from `producer` to `consumer`
+///
+/// Returns `false` if `producer` has no data
+pub fn connect<U, W, R>(producer: &mut W, consumer: &mut R) -> bool
+where
+    U: UniqueIdentifier,
+    W: Write<U> + ?Sized,
+    R: Read<U> + ?Sized,
+{
+    match producer.write() {
+        Some(data) => {
+            consumer.read(data);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{io::jar, ios};
+    use std::convert::TryInto;
+
+    #[test]
+    fn io_round_trip() {
+        assert_eq!(<jar::OSSElDriveTorque as UniqueIdentifier>::SIZE, Some(8));
+        assert_eq!(<jar::Pssn as UniqueIdentifier>::SIZE, None);
+        let data: Data<jar::Pssn> = ios!(Pssn(vec![1f64])).try_into().unwrap();
+        assert_eq!(*data, vec![1f64]);
+        let io: IO<Vec<f64>> = data.into();
+        assert_eq!(io, ios!(Pssn));
+        let data: Result<Data<jar::Pssn>, _> = ios!(SensorData(vec![1f64])).try_into();
+        assert!(matches!(data, Err(IOError::Mismatch(IOKind::Pssn, _))));
+        let data: Result<Data<jar::Pssn>, _> = jar::Pssn::io::<Vec<f64>>().try_into();
+        assert!(matches!(data, Err(IOError::Missing(_))));
+        let data: Result<Data<jar::OSSElDriveTorque>, _> =
+            ios!(OSSElDriveTorque(vec![0f64; 7])).try_into();
+        assert!(matches!(data, Err(IOError::Size(8, _))));
+        assert!(Data::<jar::OSSElDriveTorque>::try_new(vec![0f64; 8]).is_ok());
+    }
+}
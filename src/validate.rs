@@ -0,0 +1,372 @@
+//! Inputs and outputs contract validation
+//!
+//! [`Validate`] wraps a [`Dos`] component and checks the [`IO`]s passed to [`inputs`](Dos::inputs)
+//! and returned by [`outputs`](Dos::outputs) against the [`IOTags`] of the component:
+//! missing tags, unexpected tags, duplicated tags and, if the expected sizes are given, the length of the data.
+//!
+//! The [`Mode`] sets what happens on a violation of the contract:
+//! the [`Error`](Mode::Error) mode returns a [`DOSIOSError`] with a [`ValidationError`],
+//! the [`Warn`](Mode::Warn) mode writes the violations to the standard error
+//! and the [`PassThrough`](Mode::PassThrough) mode skips the validation.
+
+use crate::{io::IOKind, Checkpoint, DOSIOSError, Dos, IOTags, IO};
+use std::{error::Error, fmt};
+
+/// Validation mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Returns an error on violation
+    Error,
+    /// Writes the violations to the standard error
+    Warn,
+    /// Skips the validation
+    PassThrough,
+}
+
+/// Violation of the contract of a component
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    /// A tag is missing
+    Missing(IO<()>),
+    /// An [`IO`] is not part of the tags
+    Unexpected(IO<()>),
+    /// An [`IO`] is given more than once
+    Duplicate(IO<()>),
+    /// The length of the [`IO`] data does not match the expected size
+    Length {
+        io: IO<()>,
+        expected: usize,
+        actual: usize,
+    },
+}
+impl Violation {
+    /// Returns the [`IO`] the violation occured with
+    pub fn io(&self) -> &IO<()> {
+        match self {
+            Self::Missing(io) => io,
+            Self::Unexpected(io) => io,
+            Self::Duplicate(io) => io,
+            Self::Length { io, .. } => io,
+        }
+    }
+}
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(io) => write!(f, "{} is missing", io.io_kind()),
+            Self::Unexpected(io) => write!(f, "{} is unexpected", io.io_kind()),
+            Self::Duplicate(io) => write!(f, "{} is duplicated", io.io_kind()),
+            Self::Length {
+                io,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} has {} elements instead of {}",
+                io.io_kind(),
+                actual,
+                expected
+            ),
+        }
+    }
+}
+
+/// Contract validation error
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError(pub Vec<Violation>);
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.0
+                .iter()
+                .map(|violation| violation.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+impl Error for ValidationError {}
+
+/// Data length
+pub trait Length {
+    /// Returns the number of elements
+    fn length(&self) -> usize;
+}
+impl<T> Length for Vec<T> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+// Checks `data` against `tags`
+fn check<T>(
+    tags: &[IO<()>],
+    data: &[IO<T>],
+    sizes: &[(IOKind, usize)],
+    length: Option<fn(&T) -> usize>,
+) -> Vec<Violation> {
+    let mut violations = vec![];
+    let mut kinds: Vec<IOKind> = Vec::with_capacity(data.len());
+    for io in data {
+        let kind = io.io_kind();
+        if kinds.contains(&kind) {
+            violations.push(Violation::Duplicate(kind.io(None)));
+            continue;
+        }
+        kinds.push(kind);
+        if !tags.iter().any(|tag| tag.io_kind() == kind) {
+            violations.push(Violation::Unexpected(kind.io(None)));
+        }
+        if let (Some(value), Some(length)) = (io.as_ref(), length) {
+            if let Some((_, expected)) = sizes.iter().find(|(size_kind, _)| *size_kind == kind) {
+                let actual = length(value);
+                if actual != *expected {
+                    violations.push(Violation::Length {
+                        io: kind.io(None),
+                        expected: *expected,
+                        actual,
+                    });
+                }
+            }
+        }
+    }
+    violations.extend(
+        tags.iter()
+            .filter(|tag| !kinds.contains(&tag.io_kind()))
+            .map(|tag| Violation::Missing(tag.io_kind().io(None))),
+    );
+    violations
+}
+
+/// Validation wrapper of a [`Dos`] component
+///
+/// `None` inputs are not validated, as a component may not receive any input at the first step,
+/// e.g. in a [`Feedback`](crate::Feedback) loop or after a delayed [`Pipeline`](crate::Pipeline) edge.
+///
+/// As [`outputs`](Dos::outputs) cannot fail, in the [`Error`](Mode::Error) mode outputs that violate the contract
+/// are discarded and the error is returned by the next call to [`in_step_out`](Dos::in_step_out) or [`take_error`](Validate::take_error).
+pub struct Validate<D: Dos> {
+    dos: D,
+    mode: Mode,
+    input_sizes: Vec<(IOKind, usize)>,
+    output_sizes: Vec<(IOKind, usize)>,
+    input_length: Option<fn(&D::Input) -> usize>,
+    output_length: Option<fn(&D::Output) -> usize>,
+    error: Option<DOSIOSError>,
+}
+impl<D: Dos + IOTags> Validate<D> {
+    /// Creates a new validation wrapper
+    pub fn new(dos: D, mode: Mode) -> Self {
+        Self {
+            dos,
+            mode,
+            input_sizes: vec![],
+            output_sizes: vec![],
+            input_length: None,
+            output_length: None,
+            error: None,
+        }
+    }
+    /// Returns a reference to the wrapped component
+    pub fn inner(&self) -> &D {
+        &self.dos
+    }
+    /// Returns the wrapped component
+    pub fn into_inner(self) -> D {
+        self.dos
+    }
+    /// Takes the last outputs validation error
+    pub fn take_error(&mut self) -> Option<DOSIOSError> {
+        self.error.take()
+    }
+    // Reports the violations according to the mode
+    fn report(
+        &self,
+        violations: Vec<Violation>,
+        variant: fn(crate::error::BoxError) -> DOSIOSError,
+    ) -> Result<(), DOSIOSError> {
+        if violations.is_empty() {
+            return Ok(());
+        }
+        let io = violations[0].io().clone();
+        let error = variant(Box::new(ValidationError(violations))).with_io(&io);
+        match self.mode {
+            Mode::Error => Err(error),
+            _ => {
+                eprintln!("warning: {}", error.report());
+                Ok(())
+            }
+        }
+    }
+}
+impl<D> Validate<D>
+where
+    D: Dos + IOTags,
+    D::Input: Length,
+{
+    /// Sets the expected size of the inputs data
+    pub fn input_sizes(self, sizes: Vec<(IO<()>, usize)>) -> Self {
+        Self {
+            input_sizes: sizes
+                .into_iter()
+                .map(|(io, size)| (io.io_kind(), size))
+                .collect(),
+            input_length: Some(<D::Input as Length>::length),
+            ..self
+        }
+    }
+}
+impl<D> Validate<D>
+where
+    D: Dos + IOTags,
+    D::Output: Length,
+{
+    /// Sets the expected size of the outputs data
+    pub fn output_sizes(self, sizes: Vec<(IO<()>, usize)>) -> Self {
+        Self {
+            output_sizes: sizes
+                .into_iter()
+                .map(|(io, size)| (io.io_kind(), size))
+                .collect(),
+            output_length: Some(<D::Output as Length>::length),
+            ..self
+        }
+    }
+}
+impl<D: Dos + IOTags + Iterator> Iterator for Validate<D> {
+    type Item = D::Item;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.dos.next()
+    }
+}
+impl<D: Dos + IOTags> Dos for Validate<D> {
+    type Input = D::Input;
+    type Output = D::Output;
+    fn outputs(&mut self) -> Option<Vec<IO<Self::Output>>> {
+        let outputs = self.dos.outputs()?;
+        if self.mode == Mode::PassThrough {
+            return Some(outputs);
+        }
+        let violations = check(
+            &self.dos.outputs_tags(),
+            &outputs,
+            &self.output_sizes,
+            self.output_length,
+        );
+        match self.report(violations, DOSIOSError::Outputs) {
+            Ok(()) => Some(outputs),
+            Err(e) => {
+                self.error = Some(e);
+                None
+            }
+        }
+    }
+    fn inputs(&mut self, data: Option<Vec<IO<Self::Input>>>) -> Result<&mut Self, DOSIOSError> {
+        if let (Some(data), false) = (&data, self.mode == Mode::PassThrough) {
+            let violations = check(
+                &self.dos.inputs_tags(),
+                data,
+                &self.input_sizes,
+                self.input_length,
+            );
+            self.report(violations, DOSIOSError::Inputs)?;
+        }
+        self.dos.inputs(data)?;
+        Ok(self)
+    }
+    fn init(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.dos.init()?;
+        Ok(self)
+    }
+    fn reset(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.error = None;
+        self.dos.reset()?;
+        Ok(self)
+    }
+    fn finalize(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.dos.finalize()?;
+        Ok(self)
+    }
+    fn in_step_out(
+        &mut self,
+        data: Option<Vec<IO<Self::Input>>>,
+    ) -> Result<Option<Vec<IO<Self::Output>>>, DOSIOSError>
+    where
+        Self: Sized + Iterator,
+    {
+        let outputs = self.inputs(data)?.step()?.outputs();
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(outputs),
+        }
+    }
+}
+impl<D: Dos + IOTags> IOTags for Validate<D> {
+    fn outputs_tags(&self) -> Vec<IO<()>> {
+        self.dos.outputs_tags()
+    }
+    fn inputs_tags(&self) -> Vec<IO<()>> {
+        self.dos.inputs_tags()
+    }
+}
+impl<D: Dos + IOTags + Checkpoint> Checkpoint for Validate<D> {
+    type State = D::State;
+    fn checkpoint(&self) -> Result<Self::State, DOSIOSError> {
+        self.dos.checkpoint()
+    }
+    fn restore(&mut self, state: Self::State) -> Result<&mut Self, DOSIOSError> {
+        self.dos.restore(state)?;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{blocks::Gain, ios, DosExt};
+
+    #[test]
+    fn contract() {
+        let mut gain = Gain::new(ios!(Pssn), ios!(SensorData), 2.)
+            .validate(Mode::Error)
+            .input_sizes(vec![(ios!(Pssn), 2)]);
+        assert!(gain
+            .in_step_out(Some(vec![ios!(Pssn(vec![1., 2.]))]))
+            .is_ok());
+        let error = gain
+            .in_step_out(Some(vec![
+                ios!(Pssn(vec![1., 2., 3.])),
+                ios!(Pssn(vec![1., 2.])),
+                ios!(SensorData(vec![0.])),
+            ]))
+            .unwrap_err();
+        assert!(matches!(error, DOSIOSError::Inputs(_)));
+        let violations = error
+            .chain()
+            .find_map(|e| e.downcast_ref::<ValidationError>())
+            .cloned()
+            .unwrap();
+        assert_eq!(
+            violations.0,
+            vec![
+                Violation::Length {
+                    io: ios!(Pssn),
+                    expected: 2,
+                    actual: 3
+                },
+                Violation::Duplicate(ios!(Pssn)),
+                Violation::Unexpected(ios!(SensorData))
+            ]
+        );
+        assert!(gain.in_step_out(Some(vec![])).is_err());
+        let mut gain = gain.into_inner().validate(Mode::Warn);
+        assert!(gain
+            .in_step_out(Some(vec![
+                ios!(Pssn(vec![1., 2.])),
+                ios!(SensorData(vec![0.]))
+            ]))
+            .is_ok());
+    }
+}
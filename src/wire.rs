@@ -0,0 +1,436 @@
+//! Binary wire codec
+//!
+//! A compact framing of `Vec<IO<Vec<f64>>>` for streaming the inputs and outputs of components.
+//!
+//! A stream starts with a header:
+//!  - the magic bytes `DOSW`,
+//!  - the format [`VERSION`] as a little-endian `u16`,
+//!  - the hash of the [`FINGERPRINT`] of the [`IO`] enum as a little-endian `u64`,
+//!
+//! followed by frames, one per `Vec<IO<Vec<f64>>>`:
+//!  - the number of [`IO`]s as a little-endian `u32`,
+//!  - for each [`IO`], its [`id`] as a little-endian `u32`, the payload [`Precision`] tag as a `u8`
+//!    (0 for `None`) and, if there is a payload, its length as a little-endian `u32` followed by the values
+//!    as little-endian `f64` or `f32`.
+//!
+//! The variant ids are the FNV-1a hashes of the variant names folded to 32 bits, so they do not depend on the model the [`IO`] enum is built from.
+
+use crate::{
+    io::{fnv1a, IOKind, FINGERPRINT},
+    IO,
+};
+use std::{
+    error::Error,
+    fmt,
+    io::{self, Read, Write},
+};
+
+/// Magic bytes at the start of a stream
+pub const MAGIC: [u8; 4] = *b"DOSW";
+/// Format version
+pub const VERSION: u16 = 1;
+/// Default maximum number of values of a payload, see [`Decoder::max_payload`]
+pub const MAX_PAYLOAD: usize = 1 << 24;
+
+/// Wire codec error
+#[derive(Debug)]
+pub enum WireError {
+    /// Read or write error
+    Io(io::Error),
+    /// The stream does not start with [`MAGIC`]
+    Magic,
+    /// Unsupported format version
+    Version(u16),
+    /// The stream has been encoded with another [`IO`] enum
+    Fingerprint { expected: u64, actual: u64 },
+    /// Unknown variant id
+    Variant(u32),
+    /// Unknown payload tag
+    Tag(u8),
+    /// The payload length is larger than the maximum of the decoder
+    Payload { len: usize, max: usize },
+}
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Wire I/O error: {}", e),
+            Self::Magic => write!(f, "Not a DOS wire stream"),
+            Self::Version(version) => write!(
+                f,
+                "Unsupported wire format version {} (expected {})",
+                version, VERSION
+            ),
+            Self::Fingerprint { expected, actual } => write!(
+                f,
+                "The stream IO fingerprint [{:016x}] does not match [{:016x}]",
+                actual, expected
+            ),
+            Self::Variant(id) => write!(f, "Unknown IO variant id {:08x}", id),
+            Self::Tag(tag) => write!(f, "Unknown payload tag {}", tag),
+            Self::Payload { len, max } => write!(
+                f,
+                "The payload length {} is larger than the maximum {}",
+                len, max
+            ),
+        }
+    }
+}
+impl Error for WireError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl From<io::Error> for WireError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Returns the wire id of the variant, the FNV-1a hash of its name folded to 32 bits
+pub fn id(kind: IOKind) -> u32 {
+    name_id(kind.name())
+}
+fn name_id(name: &str) -> u32 {
+    let hash = fnv1a(name.as_bytes()).expect("reading a slice cannot fail");
+    (hash ^ (hash >> 32)) as u32
+}
+// Variants sorted by id, the ids of the aliases decode streams written with the former names
+fn ids() -> Vec<(u32, IOKind)> {
+    let mut ids: Vec<_> = IOKind::ALL
+        .iter()
+        .map(|&kind| (id(kind), kind))
+        .chain(
+            IOKind::ALIASES
+                .iter()
+                .map(|&(alias, kind)| (name_id(alias), kind)),
+        )
+        .collect();
+    ids.sort_unstable();
+    ids
+}
+
+/// Payload precision
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    /// 64 bits floating point values
+    F64 = 1,
+    /// 32 bits floating point values, the values are rounded on encoding
+    F32 = 2,
+}
+
+/// Streaming encoder
+pub struct Encoder<W: Write> {
+    writer: W,
+    precision: Precision,
+    buffer: Vec<u8>,
+}
+impl<W: Write> Encoder<W> {
+    /// Creates a new encoder with `f64` payloads and writes the stream header
+    pub fn new(mut writer: W) -> Result<Self, WireError> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&FINGERPRINT.hash.to_le_bytes())?;
+        Ok(Self {
+            writer,
+            precision: Precision::F64,
+            buffer: vec![],
+        })
+    }
+    /// Sets the payload precision
+    pub fn precision(self, precision: Precision) -> Self {
+        Self { precision, ..self }
+    }
+    /// Encodes a frame
+    pub fn encode(&mut self, data: &[IO<Vec<f64>>]) -> Result<&mut Self, WireError> {
+        let buffer = &mut self.buffer;
+        buffer.clear();
+        buffer.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        for io in data {
+            buffer.extend_from_slice(&id(io.io_kind()).to_le_bytes());
+            match io.as_ref() {
+                None => buffer.push(0),
+                Some(values) => {
+                    buffer.push(self.precision as u8);
+                    buffer.extend_from_slice(&(values.len() as u32).to_le_bytes());
+                    match self.precision {
+                        Precision::F64 => values
+                            .iter()
+                            .for_each(|x| buffer.extend_from_slice(&x.to_le_bytes())),
+                        Precision::F32 => values
+                            .iter()
+                            .for_each(|x| buffer.extend_from_slice(&(*x as f32).to_le_bytes())),
+                    }
+                }
+            }
+        }
+        self.writer.write_all(buffer)?;
+        Ok(self)
+    }
+    /// Flushes the writer
+    pub fn flush(&mut self) -> Result<&mut Self, WireError> {
+        self.writer.flush()?;
+        Ok(self)
+    }
+    /// Returns a mutable reference to the writer
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+    /// Returns the writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Streaming decoder
+pub struct Decoder<R: Read> {
+    reader: R,
+    fingerprint: u64,
+    ids: Vec<(u32, IOKind)>,
+    max_payload: usize,
+}
+impl<R: Read> Decoder<R> {
+    /// Creates a new decoder, reads and checks the stream header
+    pub fn new(reader: R) -> Result<Self, WireError> {
+        let decoder = Self::unchecked(reader)?;
+        if decoder.fingerprint != FINGERPRINT.hash {
+            return Err(WireError::Fingerprint {
+                expected: FINGERPRINT.hash,
+                actual: decoder.fingerprint,
+            });
+        }
+        Ok(decoder)
+    }
+    /// Creates a new decoder and reads the stream header without checking the fingerprint
+    pub fn unchecked(mut reader: R) -> Result<Self, WireError> {
+        let mut header = [0u8; 14];
+        reader.read_exact(&mut header)?;
+        if header[..4] != MAGIC {
+            return Err(WireError::Magic);
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != VERSION {
+            return Err(WireError::Version(version));
+        }
+        let mut fingerprint = [0u8; 8];
+        fingerprint.copy_from_slice(&header[6..]);
+        Ok(Self {
+            reader,
+            fingerprint: u64::from_le_bytes(fingerprint),
+            ids: ids(),
+            max_payload: MAX_PAYLOAD,
+        })
+    }
+    /// Sets the maximum number of values of a payload, [`MAX_PAYLOAD`] by default
+    ///
+    /// A longer payload is a [`WireError::Payload`] error, so a corrupted length does not allocate an arbitrary large buffer.
+    pub fn max_payload(self, max_payload: usize) -> Self {
+        Self {
+            max_payload,
+            ..self
+        }
+    }
+    /// Returns the fingerprint hash of the stream
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+    fn read_u32(&mut self) -> Result<u32, WireError> {
+        let mut bytes = [0u8; 4];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+    // Reads the payload values into `values`, the bytes are read in place
+    fn read_values(&mut self, values: &mut Vec<f64>, size: usize) -> Result<(), WireError> {
+        let n = self.read_u32()? as usize;
+        if n > self.max_payload {
+            return Err(WireError::Payload {
+                len: n,
+                max: self.max_payload,
+            });
+        }
+        values.resize(n, 0f64);
+        // Safety: the slice covers the initialized values and any bit pattern is a valid `f64`
+        let bytes =
+            unsafe { std::slice::from_raw_parts_mut(values.as_mut_ptr() as *mut u8, n * 8) };
+        self.reader.read_exact(&mut bytes[..n * size])?;
+        if size == 8 {
+            values
+                .iter_mut()
+                .for_each(|x| *x = f64::from_bits(u64::from_le(x.to_bits())));
+        } else {
+            // the `f32` values fill the first half of the bytes and are widened from the last one,
+            // so each `f64` is written over `f32` values that have already been read
+            for i in (0..n).rev() {
+                let x = f32::from_le_bytes([
+                    bytes[4 * i],
+                    bytes[4 * i + 1],
+                    bytes[4 * i + 2],
+                    bytes[4 * i + 3],
+                ]);
+                values[i] = x as f64;
+            }
+        }
+        Ok(())
+    }
+    /// Decodes the next frame into `data`
+    ///
+    /// The [`IO`]s in `data` and their payloads are reused, so decoding frames of the same layout does not allocate.
+    /// Returns `false` at the end of the stream, a stream that ends within a frame is an error.
+    pub fn decode_into(&mut self, data: &mut Vec<IO<Vec<f64>>>) -> Result<bool, WireError> {
+        let mut bytes = [0u8; 4];
+        let mut n_read = 0;
+        while n_read < bytes.len() {
+            match self.reader.read(&mut bytes[n_read..]) {
+                Ok(0) if n_read == 0 => return Ok(false),
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(n) => n_read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        let n_io = u32::from_le_bytes(bytes) as usize;
+        data.truncate(n_io);
+        for i in 0..n_io {
+            let id = self.read_u32()?;
+            let kind = self
+                .ids
+                .binary_search_by_key(&id, |(id, _)| *id)
+                .map(|k| self.ids[k].1)
+                .map_err(|_| WireError::Variant(id))?;
+            let mut tag = [0u8];
+            self.reader.read_exact(&mut tag)?;
+            let size = match tag[0] {
+                0 => None,
+                1 => Some(8),
+                2 => Some(4),
+                tag => return Err(WireError::Tag(tag)),
+            };
+            // reuses the payload buffer of the previous frame
+            let mut values = match data.get_mut(i) {
+                Some(io) => io.take().unwrap_or_default(),
+                None => vec![],
+            };
+            let values = match size {
+                None => None,
+                Some(size) => {
+                    self.read_values(&mut values, size)?;
+                    Some(values)
+                }
+            };
+            match data.get_mut(i) {
+                Some(io) => *io = kind.io(values),
+                None => data.push(kind.io(values)),
+            }
+        }
+        Ok(true)
+    }
+    /// Decodes the next frame, returns `None` at the end of the stream
+    pub fn decode(&mut self) -> Result<Option<Vec<IO<Vec<f64>>>>, WireError> {
+        let mut data = vec![];
+        Ok(if self.decode_into(&mut data)? {
+            Some(data)
+        } else {
+            None
+        })
+    }
+    /// Returns a mutable reference to the reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+    /// Returns the reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+impl<R: Read> Iterator for Decoder<R> {
+    type Item = Result<Vec<IO<Vec<f64>>>, WireError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.decode().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ios;
+
+    #[test]
+    fn unique_ids() {
+        let ids = ids();
+        assert!(ids.windows(2).all(|w| w[0].0 != w[1].0));
+    }
+
+    #[test]
+    fn alias_ids() {
+        let mut stream = Encoder::new(vec![])
+            .unwrap()
+            .encode(&[ios!(OSSHardpointDeltaF(vec![1.]))])
+            .unwrap()
+            .get_mut()
+            .clone();
+        // the id of the variant is replaced by the id of its former name
+        let i = stream
+            .windows(4)
+            .position(|w| w == id(IOKind::OSSHardpointDeltaF).to_le_bytes())
+            .unwrap();
+        stream[i..i + 4].copy_from_slice(&name_id("OSSHarpointDeltaF").to_le_bytes());
+        let data = Decoder::new(stream.as_slice()).unwrap().decode().unwrap();
+        assert_eq!(data.unwrap(), vec![ios!(OSSHardpointDeltaF)]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let frames = vec![
+            vec![ios!(Pssn(vec![1., 2.5])), IOKind::SensorData.io(None)],
+            vec![ios!(Pssn(vec![3., -4.])), ios!(SensorData(vec![0.125]))],
+        ];
+        let mut encoder = Encoder::new(vec![]).unwrap();
+        for frame in &frames {
+            encoder.encode(frame).unwrap();
+        }
+        let stream = encoder.into_inner();
+        let mut decoder = Decoder::new(stream.as_slice()).unwrap();
+        let mut data = vec![];
+        for frame in &frames {
+            assert!(decoder.decode_into(&mut data).unwrap());
+            assert_eq!(&data, frame);
+            data.iter()
+                .zip(frame)
+                .for_each(|(d, f)| assert_eq!(d.as_ref(), f.as_ref()));
+        }
+        assert!(!decoder.decode_into(&mut data).unwrap());
+
+        let mut encoder = Encoder::new(vec![]).unwrap().precision(Precision::F32);
+        encoder.encode(&frames[0]).unwrap();
+        let decoded: Vec<_> = Decoder::new(encoder.into_inner().as_slice())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(decoded[0][0].as_ref(), Some(&vec![1., 2.5]));
+        let mut header = b"DOSX".to_vec();
+        header.extend_from_slice(&VERSION.to_le_bytes());
+        header.extend_from_slice(&FINGERPRINT.hash.to_le_bytes());
+        assert!(matches!(
+            Decoder::new(header.as_slice()),
+            Err(WireError::Magic)
+        ));
+    }
+
+    #[test]
+    fn corrupted() {
+        let mut encoder = Encoder::new(vec![]).unwrap();
+        encoder.encode(&[ios!(Pssn(vec![1.; 4]))]).unwrap();
+        let stream = encoder.into_inner();
+        // the stream ends within the header of the frame
+        let mut decoder = Decoder::new(&stream[..16]).unwrap();
+        assert!(matches!(decoder.decode(), Err(WireError::Io(_))));
+        // the payload is longer than the maximum
+        let mut decoder = Decoder::new(stream.as_slice()).unwrap().max_payload(3);
+        assert!(matches!(
+            decoder.decode(),
+            Err(WireError::Payload { len: 4, max: 3 })
+        ));
+    }
+}
@@ -0,0 +1,18 @@
+use std::env;
+
+// Compiles the C test program of the C API, it is linked only by `tests/ffi.rs`
+// as its `dosio_*` symbols are resolved against the `dosio` crate
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=../../include/dosio.h");
+    println!("cargo:rerun-if-changed=controller.c");
+    cc::Build::new()
+        .file("controller.c")
+        .include("../../include")
+        .cargo_metadata(false)
+        .compile("dosio_ctest");
+    println!(
+        "cargo:rustc-link-search=native={}",
+        env::var("OUT_DIR").unwrap()
+    );
+}
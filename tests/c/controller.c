@@ -0,0 +1,93 @@
+/*
+ * C test program of the dosio C API
+ *
+ * `controller_new` is a C integrator imported in Rust as a `Dos` component and
+ * `host_run` drives a Rust component exported to C.
+ */
+#include <stdlib.h>
+#include <string.h>
+
+#include "dosio.h"
+
+typedef struct {
+    uint32_t input;
+    uint32_t output;
+    double gain;
+    size_t n;
+    double *u;
+    double *y;
+} controller;
+
+static int controller_set_input(void *handle, uint32_t id, const char *name,
+                                const double *data, size_t len) {
+    controller *c = handle;
+    (void)name;
+    if (id != c->input || len != c->n)
+        return -1;
+    memcpy(c->u, data, len * sizeof(double));
+    return 0;
+}
+
+static int controller_step(void *handle) {
+    controller *c = handle;
+    for (size_t i = 0; i < c->n; i++)
+        c->y[i] += c->gain * c->u[i];
+    return 0;
+}
+
+static int64_t controller_get_output(void *handle, uint32_t id,
+                                     const char *name, double *data,
+                                     size_t len) {
+    controller *c = handle;
+    if (id != c->output || strcmp(name, "SensorData") != 0)
+        return -1;
+    memcpy(data, c->y, (len < c->n ? len : c->n) * sizeof(double));
+    return (int64_t)c->n;
+}
+
+static void controller_free(void *handle) {
+    controller *c = handle;
+    free(c->u);
+    free(c->y);
+    free(c);
+}
+
+/* Integrates `n` values of `Pssn` into `SensorData` */
+dosio_c_component controller_new(double gain, size_t n) {
+    controller *c = malloc(sizeof(controller));
+    c->input = dosio_id("Pssn");
+    c->output = dosio_id("SensorData");
+    c->gain = gain;
+    c->n = n;
+    c->u = calloc(n, sizeof(double));
+    c->y = calloc(n, sizeof(double));
+    dosio_c_component component = {c, controller_set_input, controller_step,
+                                    controller_get_output, controller_free};
+    return component;
+}
+
+/* Steps `component` `n_step` times with `Pssn = [1, 2, 3]` and copies
+ * `SensorData` into `y`
+ * Returns 0 on success, the number of the failed check otherwise */
+int host_run(dosio_component *component, int n_step, double *y) {
+    const double u[3] = {1., 2., 3.};
+    if (dosio_set_input(component, "NotAVariant", u, 3) != -1 ||
+        dosio_last_error(component) == NULL)
+        return 1;
+    for (int k = 0; k < n_step; k++) {
+        if (k % 2 == 0) {
+            if (dosio_set_input(component, "Pssn", u, 3) != 0)
+                return 2;
+        } else if (dosio_set_input_id(component, dosio_id("Pssn"), u, 3) != 0)
+            return 3;
+        if (dosio_step(component) != 0)
+            return 4;
+        if (dosio_last_error(component) != NULL)
+            return 5;
+    }
+    if (dosio_get_output(component, "SensorData", y, 3) != 3)
+        return 6;
+    if (dosio_get_output_id(component, dosio_id("Pssn"), y, 3) != -1)
+        return 7;
+    return 0;
+}
@@ -0,0 +1,3 @@
+//! C test program of the `dosio` C API
+//!
+//! The build script compiles `controller.c` into the static library `dosio_ctest` for the tests of `dosio`.
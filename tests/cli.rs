@@ -0,0 +1,99 @@
+#[cfg(all(feature = "fixture", feature = "fem-prqt"))]
+use dosio::fixture::Fixture;
+use dosio::{
+    io::{IOKind, FINGERPRINT},
+    ios,
+    wire::{self, Encoder},
+};
+use std::{env, fs, path::PathBuf, process::Command};
+
+fn temp(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("dosio-cli-{}-{}", name, std::process::id()))
+}
+
+fn dosio(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_dosio"))
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn io() {
+    let variants = dosio(&["io"]);
+    let mut lines = variants.lines();
+    assert_eq!(lines.next(), Some(FINGERPRINT.to_string().as_str()));
+    let kind = ios!(Pssn).io_kind();
+    assert!(lines.any(|line| line == format!("{:08x} {}", wire::id(kind), kind)));
+    assert_eq!(
+        variants.lines().count(),
+        1 + IOKind::ALL.len() + IOKind::ALIASES.len()
+    );
+}
+
+#[cfg(all(feature = "fixture", feature = "fem-prqt"))]
+#[test]
+fn fem() {
+    let (a, b) = (temp("a"), temp("b"));
+    Fixture::new()
+        .input("OSS_ElDrive_Torque", 8)
+        .output("OSS_ElEncoder_Angle", 4)
+        .write(&a)
+        .unwrap();
+    Fixture::new()
+        .input("OSS_ElDrive_Torque", 6)
+        .output("OSS_AzEncoder_Angle", 6)
+        .write(&b)
+        .unwrap();
+    let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+    let groups = dosio(&["fem", a]);
+    assert!(groups.lines().any(|line| line.split_whitespace().eq([
+        "OSS_ElDrive_Torque",
+        "8",
+        "OSSElDriveTorque"
+    ])));
+    assert_eq!(
+        dosio(&["diff", a, b]),
+        "~ input OSS_ElDrive_Torque (8 -> 6)\n\
+         - output OSS_ElEncoder_Angle (4)\n\
+         + output OSS_AzEncoder_Angle (6)\n"
+    );
+    assert_eq!(dosio(&["diff", a, a]), "no differences\n");
+    fs::remove_dir_all(a).unwrap();
+    fs::remove_dir_all(b).unwrap();
+}
+
+#[test]
+fn log() {
+    let path = temp("log");
+    let mut encoder = Encoder::new(fs::File::create(&path).unwrap()).unwrap();
+    for i in 0..3 {
+        let x = i as f64;
+        encoder
+            .encode(&[ios!(Pssn(vec![x, -x])), ios!(SensorData(vec![2. * x]))])
+            .unwrap();
+    }
+    encoder.encode(&[ios!(Pssn(vec![3., -3.]))]).unwrap();
+    encoder.flush().unwrap();
+    drop(encoder);
+    let path = path.to_str().unwrap();
+
+    let summary = dosio(&["log", path]);
+    assert!(summary.starts_with("4 steps, 2 variants"));
+    assert_eq!(
+        dosio(&["log", path, "--csv", "-"]),
+        "step,Pssn[1],Pssn[2],SensorData[1]\n0,0,-0,0\n1,1,-1,2\n2,2,-2,4\n3,3,-3,\n"
+    );
+    assert_eq!(
+        dosio(&["log", path, "--json", "-"]),
+        "{\"steps\":4,\"variants\":{\"Pssn\":[[0.0,-0.0],[1.0,-1.0],[2.0,-2.0],[3.0,-3.0]],\
+         \"SensorData\":[[0.0],[2.0],[4.0],null]}}\n"
+    );
+    fs::remove_file(path).unwrap();
+}
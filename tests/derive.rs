@@ -0,0 +1,5 @@
+// Compile errors of the `IOTags` and `Dos` derive macros
+#[test]
+fn derive_errors() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,114 @@
+#![cfg(feature = "ffi")]
+
+use dosio::{
+    blocks::Gain,
+    ffi::{
+        dosio_free, dosio_get_output, dosio_last_error, dosio_set_input, dosio_step, export,
+        CComponent, Callbacks,
+    },
+    ios, DOSIOSError, Dos, IOTags, IO,
+};
+use std::{
+    ffi::CStr,
+    os::raw::{c_int, c_void},
+};
+
+// C test program in `tests/c/controller.c`, compiled by the `dosio-ctest` dev-dependency
+#[link(name = "dosio_ctest", kind = "static")]
+extern "C" {
+    fn controller_new(gain: f64, n: usize) -> Callbacks;
+    fn host_run(component: *mut c_void, n_step: c_int, y: *mut f64) -> c_int;
+}
+
+#[test]
+fn import() {
+    let mut controller = unsafe {
+        CComponent::new(
+            controller_new(0.5, 3),
+            vec![ios!(Pssn)],
+            vec![(ios!(SensorData), 3)],
+        )
+    };
+    assert_eq!(controller.outputs_tags(), vec![ios!(SensorData)]);
+    let mut y = None;
+    for _ in 0..4 {
+        y = controller
+            .in_step_out(Some(vec![ios!(Pssn(vec![1., 2., 3.]))]))
+            .unwrap();
+    }
+    assert_eq!(y.unwrap()[0].as_ref(), Some(&vec![2., 4., 6.]));
+    assert!(controller
+        .in_step_out(Some(vec![ios!(Pssn(vec![1.]))]))
+        .is_err());
+    assert!(controller.in_step_out(Some(vec![])).is_err());
+}
+
+#[test]
+fn export_to_c() {
+    let gain = export(Gain::new(ios!(Pssn), ios!(SensorData), 2.));
+    let mut y = [0f64; 3];
+    assert_eq!(
+        unsafe { host_run(gain as *mut c_void, 3, y.as_mut_ptr()) },
+        0
+    );
+    assert_eq!(y, [2., 4., 6.]);
+    unsafe { dosio_free(gain) };
+}
+
+// Component panicking on its second step
+struct Panicking(usize);
+impl Dos for Panicking {
+    type Input = Vec<f64>;
+    type Output = Vec<f64>;
+    fn outputs(&mut self) -> Option<Vec<IO<Self::Output>>> {
+        Some(vec![ios!(SensorData(vec![self.0 as f64]))])
+    }
+    fn inputs(&mut self, _: Option<Vec<IO<Self::Input>>>) -> Result<&mut Self, DOSIOSError> {
+        Ok(self)
+    }
+    fn step(&mut self) -> Result<&mut Self, DOSIOSError> {
+        self.0 += 1;
+        assert!(self.0 < 2, "step #{}", self.0);
+        Ok(self)
+    }
+}
+impl Iterator for Panicking {
+    type Item = ();
+    fn next(&mut self) -> Option<()> {
+        Some(())
+    }
+}
+
+#[test]
+fn failures() {
+    let last_error = |component| unsafe {
+        CStr::from_ptr(dosio_last_error(component))
+            .to_string_lossy()
+            .into_owned()
+    };
+    let name = b"SensorData\0".as_ptr() as *const _;
+    let mut y = [0f64; 1];
+
+    let component = export(Panicking(0));
+    unsafe {
+        assert_eq!(dosio_step(component), 0);
+        assert_eq!(dosio_get_output(component, name, y.as_mut_ptr(), 1), 1);
+        assert_eq!(dosio_step(component), -1);
+        assert!(last_error(component).contains("step #2"));
+        // the outputs of the previous step are cleared
+        assert_eq!(dosio_get_output(component, name, y.as_mut_ptr(), 1), -1);
+        dosio_free(component);
+    }
+
+    let gain = export(Gain::new(ios!(Pssn), ios!(SensorData), 2.));
+    unsafe {
+        let input = b"Pssn\0".as_ptr() as *const _;
+        assert_eq!(dosio_set_input(gain, input, [1f64].as_ptr(), 1), 0);
+        assert_eq!(dosio_step(gain), 0);
+        assert_eq!(dosio_get_output(gain, name, y.as_mut_ptr(), 1), 1);
+        // the input is missing
+        assert_eq!(dosio_step(gain), -1);
+        assert_eq!(dosio_get_output(gain, name, y.as_mut_ptr(), 1), -1);
+        dosio_free(gain);
+    }
+}
@@ -0,0 +1,54 @@
+#![cfg(all(feature = "fixture", any(feature = "fem-prqt", feature = "fem-hdf5")))]
+
+use dosio::{
+    fem::{ModalConfig, ModalModel, ModalSystem},
+    fixture::Fixture,
+    ios, Dos,
+};
+use std::{env, fs, path::PathBuf};
+
+fn fem_repo(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("dosio-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&path).unwrap();
+    path
+}
+
+// Writes the fixture, reads it back and checks the model is unchanged
+fn round_trip(name: &str, load: fn(&PathBuf) -> ModalModel) {
+    let fem_repo = fem_repo(name);
+    let fixture = Fixture::default().modes(6).max_frequency(50.);
+    fixture.write(&fem_repo).unwrap();
+    let model = load(&fem_repo);
+    fs::remove_dir_all(&fem_repo).unwrap();
+    assert_eq!(model, fixture.model());
+    let mut fem = ModalSystem::new(
+        &model,
+        ModalConfig::new(
+            1e-3,
+            vec![ios!(OSSElDriveTorque)],
+            vec![ios!(OSSElEncoderAngle)],
+        ),
+    )
+    .unwrap();
+    let y = fem
+        .in_step_out(Some(vec![ios!(OSSElDriveTorque(vec![1.; 8]))]))
+        .unwrap()
+        .unwrap();
+    assert_eq!(Option::<Vec<f64>>::from(&y[0]).unwrap().len(), 4);
+}
+
+#[cfg(feature = "fem-prqt")]
+#[test]
+fn zip_round_trip() {
+    round_trip("zip", |fem_repo| {
+        ModalModel::load(fem_repo.join(dosio::fixture::ZIP_MODEL)).unwrap()
+    });
+}
+
+#[cfg(feature = "fem-hdf5")]
+#[test]
+fn mat_round_trip() {
+    round_trip("mat", |fem_repo| {
+        ModalModel::load(fem_repo.join(dosio::fixture::MAT_MODEL)).unwrap()
+    });
+}
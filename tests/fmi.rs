@@ -0,0 +1,183 @@
+#![cfg(all(feature = "fmi", unix))]
+
+use dosio::{
+    blocks::Gain,
+    fmi::{export, export::Model, Causality, Fmu, Mapping, ModelDescription, Version},
+    ios, Dos, IOTags,
+};
+use std::{
+    env::consts::{DLL_PREFIX, DLL_SUFFIX},
+    fs,
+    path::PathBuf,
+    process::Command,
+    sync::{Arc, Mutex},
+};
+
+// Compiles the C test FMU in `tests/fmu/integrator.c` into a shared library
+fn integrator() -> PathBuf {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+    let version = Command::new(rustc).arg("-vV").output().unwrap();
+    let host = String::from_utf8(version.stdout)
+        .unwrap()
+        .lines()
+        .find_map(|line| line.strip_prefix("host: ").map(str::to_string))
+        .unwrap();
+    let library = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!(
+        "{}integrator-{}{}",
+        DLL_PREFIX,
+        std::process::id(),
+        DLL_SUFFIX
+    ));
+    let status = cc::Build::new()
+        .target(&host)
+        .host(&host)
+        .opt_level(0)
+        .cargo_metadata(false)
+        .out_dir(env!("CARGO_TARGET_TMPDIR"))
+        .get_compiler()
+        .to_command()
+        .args(["-shared", "-fPIC", "-o"])
+        .arg(&library)
+        .arg(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fmu/integrator.c"
+        ))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    library
+}
+
+// The same model as the `fmu` example, the `cdylib` built by `cargo test`
+fn gain() -> Model<Gain> {
+    Model::new("gain", Gain::new(ios!(Pssn), ios!(SensorData), 2.))
+        .input_sizes(vec![(ios!(Pssn), 3)])
+        .output_sizes(vec![(ios!(SensorData), 3)])
+        .sample_time(1e-3)
+}
+fn example() -> PathBuf {
+    std::env::current_exe()
+        .unwrap()
+        .parent()
+        .and_then(|deps| deps.parent())
+        .unwrap()
+        .join("examples")
+        .join(format!("{}fmu{}", DLL_PREFIX, DLL_SUFFIX))
+}
+
+#[test]
+fn import() {
+    let dir = std::env::temp_dir();
+    let path = dir.join(format!("dosio-mapping-{}.txt", std::process::id()));
+    fs::write(
+        &path,
+        "# integrator\ninput Pssn = u[1], u[2]\noutput SensorData = y[1], y[2]\n",
+    )
+    .unwrap();
+    let mapping = Mapping::from_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let library = integrator();
+    for (version, xml) in [
+        (Version::V2, include_str!("fmu/fmi2.xml")),
+        (Version::V3, include_str!("fmu/fmi3.xml")),
+    ] {
+        let description: ModelDescription = xml.parse().unwrap();
+        assert_eq!(description.version, version);
+        assert_eq!(description.step_size, Some(0.5));
+        assert_eq!(
+            description
+                .variables
+                .iter()
+                .filter(|variable| variable.causality != Causality::Local)
+                .count(),
+            4
+        );
+        let path = dir.join(format!("dosio-{:?}-{}.fmu", version, std::process::id()));
+        export::package(&description, &library, &path).unwrap();
+        let fmu = Fmu::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(fmu.description(), &description);
+
+        let mut integrator = fmu.instantiate("integrator", &mapping, 0.5).unwrap();
+        assert_eq!(integrator.inputs_tags(), vec![ios!(Pssn)]);
+        let mut y = None;
+        for _ in 0..4 {
+            y = integrator
+                .in_step_out(Some(vec![ios!(Pssn(vec![1., -2.]))]))
+                .unwrap();
+        }
+        assert_eq!(integrator.time(), 2.);
+        assert_eq!(y.unwrap()[0].as_ref(), Some(&vec![2., -4.]));
+        assert!(integrator
+            .in_step_out(Some(vec![ios!(Pssn(vec![1.]))]))
+            .is_err());
+        integrator.reset().unwrap();
+        let y = integrator
+            .in_step_out(Some(vec![ios!(Pssn(vec![1., -2.]))]))
+            .unwrap();
+        assert_eq!(y.unwrap()[0].as_ref(), Some(&vec![0.5, -1.]));
+    }
+    assert!(Mapping::new()
+        .output(ios!(SensorData), &["u[1]"])
+        .to_string()
+        .parse::<Mapping>()
+        .is_ok());
+    fs::remove_file(&library).unwrap();
+}
+
+#[test]
+fn export() {
+    let mapping = Mapping::new()
+        .input(ios!(Pssn), &["Pssn[1]", "Pssn[2]", "Pssn[3]"])
+        .output(
+            ios!(SensorData),
+            &["SensorData[1]", "SensorData[2]", "SensorData[3]"],
+        );
+    let dir = std::env::temp_dir();
+    for version in [Version::V2, Version::V3] {
+        let description = gain().model_description(version).unwrap();
+        assert!(!description.variable_step_size);
+        assert_eq!(description.step_size, Some(1e-3));
+        assert_eq!(
+            description.to_xml().parse::<ModelDescription>().unwrap(),
+            description
+        );
+        let path = dir.join(format!(
+            "dosio-gain-{:?}-{}.fmu",
+            version,
+            std::process::id()
+        ));
+        gain().package(version, example(), &path).unwrap();
+        let messages = Arc::new(Mutex::new(vec![]));
+        let log = messages.clone();
+        let fmu = Fmu::load(&path)
+            .unwrap()
+            .logger(move |_, message| log.lock().unwrap().push(message.to_string()));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(fmu.description(), &description);
+
+        let mut component = fmu.instantiate("gain", &mapping, 1e-3).unwrap();
+        let y = component
+            .in_step_out(Some(vec![ios!(Pssn(vec![1., 2., 3.]))]))
+            .unwrap();
+        assert_eq!(y.unwrap()[0].as_ref(), Some(&vec![2., 4., 6.]));
+        assert!(messages.lock().unwrap().is_empty());
+
+        let mut component = fmu.instantiate("gain", &mapping, 1e-2).unwrap();
+        assert!(component
+            .in_step_out(Some(vec![ios!(Pssn(vec![1., 2., 3.]))]))
+            .is_err());
+        assert!(messages
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|message| message.contains("is not the sample time")));
+
+        let mut description = gain().model_description(version).unwrap();
+        description.token = "{not the gain}".into();
+        export::package(&description, example(), &path).unwrap();
+        let fmu = Fmu::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(fmu.instantiate("gain", &mapping, 1e-3).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+/* Test FMU, FMI 2.0 and FMI 3.0 co-simulation:
+ * integrates the inputs u[1], u[2] (value references 0, 1)
+ * into the outputs y[1], y[2] (value references 2, 3)
+ */
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+
+#define N 2
+#define GUID "{integrator}"
+
+typedef struct {
+    double u[N];
+    double y[N];
+} integrator;
+
+static int set(void *c, const uint32_t vr[], size_t nvr, const double value[]) {
+    integrator *s = c;
+    for (size_t i = 0; i < nvr; i++) {
+        if (vr[i] >= N) return 3;
+        s->u[vr[i]] = value[i];
+    }
+    return 0;
+}
+static int get(void *c, const uint32_t vr[], size_t nvr, double value[]) {
+    integrator *s = c;
+    for (size_t i = 0; i < nvr; i++) {
+        if (vr[i] < N) value[i] = s->u[vr[i]];
+        else if (vr[i] < 2 * N) value[i] = s->y[vr[i] - N];
+        else return 3;
+    }
+    return 0;
+}
+static int step(void *c, double h) {
+    integrator *s = c;
+    for (int i = 0; i < N; i++) s->y[i] += h * s->u[i];
+    return 0;
+}
+static int reset(void *c) {
+    memset(c, 0, sizeof(integrator));
+    return 0;
+}
+static int ok(void *c) {
+    (void)c;
+    return 0;
+}
+
+/* FMI 2.0 */
+void *fmi2Instantiate(const char *name, int type, const char *guid, const char *resources,
+                      const void *functions, int visible, int logging) {
+    (void)name, (void)resources, (void)functions, (void)visible, (void)logging;
+    if (type != 1 || strcmp(guid, GUID) != 0) return NULL;
+    return calloc(1, sizeof(integrator));
+}
+int fmi2SetupExperiment(void *c, int tolerance_defined, double tolerance, double start,
+                        int stop_defined, double stop) {
+    (void)tolerance_defined, (void)tolerance, (void)start, (void)stop_defined, (void)stop;
+    return ok(c);
+}
+int fmi2EnterInitializationMode(void *c) { return ok(c); }
+int fmi2ExitInitializationMode(void *c) { return ok(c); }
+int fmi2SetReal(void *c, const uint32_t vr[], size_t nvr, const double value[]) {
+    return set(c, vr, nvr, value);
+}
+int fmi2GetReal(void *c, const uint32_t vr[], size_t nvr, double value[]) {
+    return get(c, vr, nvr, value);
+}
+int fmi2DoStep(void *c, double t, double h, int no_set) {
+    (void)t, (void)no_set;
+    return step(c, h);
+}
+int fmi2Terminate(void *c) { return ok(c); }
+int fmi2Reset(void *c) { return reset(c); }
+void fmi2FreeInstance(void *c) { free(c); }
+
+/* FMI 3.0 */
+void *fmi3InstantiateCoSimulation(const char *name, const char *token, const char *resources,
+                                  bool visible, bool logging, bool event_mode, bool early_return,
+                                  const uint32_t vr[], size_t nvr, void *env, void *log,
+                                  void *update) {
+    (void)name, (void)resources, (void)visible, (void)logging, (void)event_mode;
+    (void)early_return, (void)vr, (void)nvr, (void)env, (void)log, (void)update;
+    if (strcmp(token, GUID) != 0) return NULL;
+    return calloc(1, sizeof(integrator));
+}
+int fmi3EnterInitializationMode(void *c, bool tolerance_defined, double tolerance, double start,
+                                bool stop_defined, double stop) {
+    (void)tolerance_defined, (void)tolerance, (void)start, (void)stop_defined, (void)stop;
+    return ok(c);
+}
+int fmi3ExitInitializationMode(void *c) { return ok(c); }
+int fmi3SetFloat64(void *c, const uint32_t vr[], size_t nvr, const double value[], size_t n) {
+    return n == nvr ? set(c, vr, nvr, value) : 3;
+}
+int fmi3GetFloat64(void *c, const uint32_t vr[], size_t nvr, double value[], size_t n) {
+    return n == nvr ? get(c, vr, nvr, value) : 3;
+}
+int fmi3DoStep(void *c, double t, double h, bool no_set, bool *event, bool *terminate,
+               bool *early, double *last) {
+    (void)no_set;
+    *event = false;
+    *terminate = false;
+    *early = false;
+    *last = t + h;
+    return step(c, h);
+}
+int fmi3Terminate(void *c) { return ok(c); }
+int fmi3Reset(void *c) { return reset(c); }
+void fmi3FreeInstance(void *c) { free(c); }
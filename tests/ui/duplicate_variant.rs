@@ -0,0 +1,9 @@
+#[derive(dosio::IOTags)]
+struct Mirror {
+    #[dos(input = M1RBMcmd)]
+    cmd: Option<Vec<f64>>,
+    #[dos(input = M1RBMcmd)]
+    other_cmd: Option<Vec<f64>>,
+}
+
+fn main() {}
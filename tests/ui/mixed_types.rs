@@ -0,0 +1,9 @@
+#[derive(dosio::Dos)]
+struct Mirror {
+    #[dos(input = M1RBMcmd)]
+    cmd: Option<Vec<f64>>,
+    #[dos(input = M1HPLC)]
+    lc: Option<Vec<f32>>,
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+#[derive(dosio::IOTags)]
+struct Mirror {
+    #[dos(input = M1RBMcommand)]
+    cmd: Option<Vec<f64>>,
+}
+
+fn main() {}